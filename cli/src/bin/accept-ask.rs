@@ -0,0 +1,175 @@
+use anchor_client::Client;
+use anchor_client::ClientError as ClientError0;
+use anyhow::Result;
+use cli::{get_cluster, get_collection, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_clap_utils::input_parsers::pubkey_of;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::liqzError;
+use liqz::{
+    get_metadata_address, Credential, Denylist, Exposure, LenderPosition, LizStake, NFTAsk,
+    NFTDeposit, NFTPool, PoolTokenAccount, Reputation, Treasury,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: u64,
+
+    #[structopt(long, env)]
+    amount: f64,
+
+    #[structopt(long, env)]
+    interest_rate: u64,
+
+    #[structopt(long, env)]
+    duration: i64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+
+    let borrower_wallet_address =
+        pubkey_of(&Opt::clap().get_matches(), "borrower-wallet-address").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+    let deposit_account = NFTDeposit::get_address(
+        &program_id,
+        &opt.nft_mint_address,
+        &borrower_wallet_address,
+        opt.deposit_id,
+    );
+    let position_mint = LenderPosition::get_address(&program_id, &deposit_account);
+
+    let resp = program
+        .request()
+        .accounts(liqz::accounts::AccountsAcceptAsk {
+            pool,
+            borrower_wallet_account: borrower_wallet_address,
+            borrower_stake_account: LizStake::get_address(&program_id, &borrower_wallet_address),
+            borrower_credential_account: Credential::get_address(
+                &program_id,
+                &borrower_wallet_address,
+            ),
+            lender_wallet_account: lender_wallet_keypair.pubkey(),
+            lender_credential_account: Credential::get_address(
+                &program_id,
+                &lender_wallet_keypair.pubkey(),
+            ),
+
+            nft_mint: opt.nft_mint_address,
+            nft_metadata_account: get_metadata_address(&opt.nft_mint_address),
+            denylist_account: Denylist::get_address(&program_id),
+            borrower_dai_account: get_associated_token_address(
+                &borrower_wallet_address,
+                &opt.dai_mint_address,
+            ),
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+            treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+
+            position_mint,
+            lender_position_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &position_mint,
+            ),
+
+            deposit_account,
+            ask_account: NFTAsk::get_address(&program_id, &deposit_account),
+            collection_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::COLLECTION,
+                &get_collection(&program, &opt.nft_mint_address),
+            ),
+            borrower_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::BORROWER,
+                &borrower_wallet_address,
+            ),
+            borrower_reputation_account: Reputation::get_address(
+                &program_id,
+                &borrower_wallet_address,
+            ),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::AcceptAsk {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+            interest_rate: opt.interest_rate,
+            duration: opt.duration,
+        })
+        .signer(&lender_wallet_keypair)
+        .send();
+
+    match resp {
+        Ok(tx) => {
+            println!("The transaction is {}", tx);
+            println!("The lender position mint is {}", position_mint);
+        }
+        Err(ClientError0::SolanaClientError(ClientError {
+            kind:
+                ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    data:
+                        RpcResponseErrorData::SendTransactionPreflightFailure(
+                            RpcSimulateTransactionResult {
+                                err:
+                                    Some(TransactionError::InstructionError(
+                                        _,
+                                        InstructionError::Custom(code),
+                                    )),
+                                ..
+                            },
+                        ),
+                    ..
+                }),
+            ..
+        })) => {
+            println!("Error: {}", liqzError::from_code(code));
+        }
+        Err(e) => println!("{:?}", e),
+    };
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::Multisig;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    member_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    action_account: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let member_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "member-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&member_wallet_keypair));
+    let program = client.program(program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsApproveAdminAction {
+            member_wallet_account: member_wallet_keypair.pubkey(),
+            multisig_account: Multisig::get_address(&program_id),
+            action_account: opt.action_account,
+        })
+        .args(liqz::instruction::ApproveAdminAction {})
+        .signer(&member_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -2,10 +2,12 @@ use anchor_client::Client;
 use anyhow::Result;
 use cli::{get_cluster, load_program_from_idl, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
-use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use solana_sdk::{
+    instruction::AccountMeta, pubkey::Pubkey, signature::Signer, system_program, sysvar,
+};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use liqz::{NFTBid, NFTPool};
+use liqz::{get_metadata_address, BidRestriction, Credential, Denylist, NFTBid, NFTPool, Referrer};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -27,6 +29,30 @@ struct Opt {
 
     #[structopt(long)]
     qty: u64,
+
+    // Only this borrower wallet can take the bid
+    #[structopt(long, conflicts_with = "only-deposit")]
+    only_borrower: Option<Pubkey>,
+
+    // Only this deposit account can take the bid
+    #[structopt(long)]
+    only_deposit: Option<Pubkey>,
+
+    // Unix timestamp after which the bid cannot be taken
+    #[structopt(long)]
+    expires_at: Option<i64>,
+
+    // Lend open-ended loans, the borrower has this many seconds to repay once the loan is called
+    #[structopt(long)]
+    call_notice: Option<i64>,
+
+    // Interest is paid every this many seconds, missing a payment lets the lender liquidate
+    #[structopt(long)]
+    installment_period: Option<i64>,
+
+    // The registered referrer who brought the lender
+    #[structopt(long)]
+    referrer: Option<Pubkey>,
 }
 
 fn main() -> Result<()> {
@@ -52,6 +78,12 @@ fn main() -> Result<()> {
             lender_wallet_account: lender_wallet_keypair.pubkey(),
 
             nft_mint: opt.nft_mint_address,
+            nft_metadata_account: get_metadata_address(&opt.nft_mint_address),
+            denylist_account: Denylist::get_address(&program_id),
+            lender_credential_account: Credential::get_address(
+                &program_id,
+                &lender_wallet_keypair.pubkey(),
+            ),
             lender_dai_account: get_associated_token_address(
                 &lender_wallet_keypair.pubkey(),
                 &opt.dai_mint_address,
@@ -66,10 +98,27 @@ fn main() -> Result<()> {
             spl_program: spl_token::id(),
             system_program: system_program::id(),
             rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
         })
+        .accounts(
+            opt.referrer
+                .map(|referrer| {
+                    AccountMeta::new_readonly(Referrer::get_address(&program_id, &referrer), false)
+                })
+                .into_iter()
+                .collect::<Vec<_>>(),
+        )
         .args(liqz::instruction::PlaceBid {
             price: (opt.price * 10f64.powf(9.)) as u64,
             qty: opt.qty,
+            restriction: opt
+                .only_borrower
+                .map(BidRestriction::Borrower)
+                .or_else(|| opt.only_deposit.map(BidRestriction::Deposit)),
+            expires_at: opt.expires_at,
+            call_notice: opt.call_notice,
+            installment_period: opt.installment_period,
+            referrer: opt.referrer,
         })
         .signer(&lender_wallet_keypair)
         .send()?;
@@ -1,7 +1,7 @@
 use anchor_client::Client;
 use anchor_client::ClientError as ClientError0;
 use anyhow::Result;
-use cli::{get_cluster, load_program_from_idl, Keypair};
+use cli::{get_cluster, get_collection, load_program_from_idl, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_clap_utils::input_parsers::pubkey_of;
 use solana_client::{
@@ -9,12 +9,18 @@ use solana_client::{
     rpc_request::{RpcError, RpcResponseErrorData},
     rpc_response::RpcSimulateTransactionResult,
 };
-use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
-use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use solana_sdk::{
+    instruction::{AccountMeta, InstructionError},
+    transaction::TransactionError,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
 use liqz::liqzError;
-use liqz::{NFTBid, NFTDeposit, NFTPool};
+use liqz::{
+    get_metadata_address, Credential, Denylist, Exposure, LenderPosition, LizStake, NFTBid,
+    NFTDeposit, NFTPool, PoolTokenAccount, Referrer, Reputation, Treasury,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -28,9 +34,6 @@ struct Opt {
     #[structopt(long, env)]
     lender_wallet_address: String,
 
-    #[structopt(long, env)]
-    tai_mint_address: Pubkey,
-
     #[structopt(long, env)]
     dai_mint_address: Pubkey,
 
@@ -38,10 +41,14 @@ struct Opt {
     nft_mint_address: Pubkey,
 
     #[structopt(long, env)]
-    deposit_id: Pubkey,
+    deposit_id: u64,
 
     #[structopt(long, env)]
     amount: f64,
+
+    // The registered referrer who brought the borrower
+    #[structopt(long)]
+    referrer: Option<Pubkey>,
 }
 
 fn main() -> Result<()> {
@@ -62,54 +69,102 @@ fn main() -> Result<()> {
     let program = client.program(program_id);
 
     let pool = NFTPool::get_address(&program.id());
+    let deposit_account = NFTDeposit::get_address(
+        &program_id,
+        &opt.nft_mint_address,
+        &borrower_wallet_keypair.pubkey(),
+        opt.deposit_id,
+    );
+    let position_mint = LenderPosition::get_address(&program_id, &deposit_account);
 
     let resp = program
         .request()
         .accounts(liqz::accounts::AccountsBorrow {
-            pool,
-            borrower_wallet_account: borrower_wallet_keypair.pubkey(),
-            lender_wallet_account: lender_wallet_address,
-
-            nft_mint: opt.nft_mint_address,
-            borrower_dai_account: get_associated_token_address(
-                &borrower_wallet_keypair.pubkey(),
-                &opt.dai_mint_address,
-            ),
-            lender_dai_account: get_associated_token_address(
-                &lender_wallet_address,
-                &opt.dai_mint_address,
-            ),
-            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
-
-            lender_tai_account: get_associated_token_address(
-                &lender_wallet_address,
-                &opt.tai_mint_address,
-            ),
-            pool_tai_account: get_associated_token_address(&pool, &opt.tai_mint_address),
-
-            deposit_account: NFTDeposit::get_address(
-                &program_id,
-                &opt.nft_mint_address,
-                &borrower_wallet_keypair.pubkey(),
-                &opt.deposit_id,
-            ),
+            loan: liqz::accounts::AccountsStartLoan {
+                pool,
+                borrower_wallet_account: borrower_wallet_keypair.pubkey(),
+                borrower_stake_account: LizStake::get_address(
+                    &program_id,
+                    &borrower_wallet_keypair.pubkey(),
+                ),
+                borrower_credential_account: Credential::get_address(
+                    &program_id,
+                    &borrower_wallet_keypair.pubkey(),
+                ),
+                lender_wallet_account: lender_wallet_address,
+                lender_credential_account: Credential::get_address(
+                    &program_id,
+                    &lender_wallet_address,
+                ),
+
+                nft_mint: opt.nft_mint_address,
+                nft_metadata_account: get_metadata_address(&opt.nft_mint_address),
+                denylist_account: Denylist::get_address(&program_id),
+                borrower_dai_account: get_associated_token_address(
+                    &borrower_wallet_keypair.pubkey(),
+                    &opt.dai_mint_address,
+                ),
+                lender_dai_account: get_associated_token_address(
+                    &lender_wallet_address,
+                    &opt.dai_mint_address,
+                ),
+                treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+                pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+
+                position_mint,
+                lender_position_account: get_associated_token_address(
+                    &lender_wallet_address,
+                    &position_mint,
+                ),
+
+                deposit_account,
+                collection_exposure_account: Exposure::get_address(
+                    &program_id,
+                    Exposure::COLLECTION,
+                    &get_collection(&program, &opt.nft_mint_address),
+                ),
+                borrower_exposure_account: Exposure::get_address(
+                    &program_id,
+                    Exposure::BORROWER,
+                    &borrower_wallet_keypair.pubkey(),
+                ),
+                borrower_reputation_account: Reputation::get_address(
+                    &program_id,
+                    &borrower_wallet_keypair.pubkey(),
+                ),
+
+                ata_program: spl_associated_token_account::id(),
+                spl_program: spl_token::id(),
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+                clock: sysvar::clock::id(),
+            },
             bid_account: NFTBid::get_address(
                 &program_id,
                 &opt.nft_mint_address,
                 &lender_wallet_address,
             ),
-
-            spl_program: spl_token::id(),
-            clock: sysvar::clock::id(),
         })
+        .accounts(
+            opt.referrer
+                .map(|referrer| {
+                    AccountMeta::new_readonly(Referrer::get_address(&program_id, &referrer), false)
+                })
+                .into_iter()
+                .collect::<Vec<_>>(),
+        )
         .args(liqz::instruction::Borrow {
             amount: (opt.amount * 10f64.powf(9.)) as u64,
+            referrer: opt.referrer,
         })
         .signer(&borrower_wallet_keypair)
         .send();
 
     match resp {
-        Ok(tx) => println!("The transaction is {}", tx),
+        Ok(tx) => {
+            println!("The transaction is {}", tx);
+            println!("The lender position mint is {}", position_mint);
+        }
         Err(ClientError0::SolanaClientError(ClientError {
             kind:
                 ClientErrorKind::RpcError(RpcError::RpcResponseError {
@@ -0,0 +1,76 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{LenderPosition, NFTDeposit};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: u64,
+
+    // Cancel a previous call instead
+    #[structopt(long)]
+    cancel: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let deposit_account = NFTDeposit::get_address(
+        &program_id,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        opt.deposit_id,
+    );
+    let position_mint = LenderPosition::get_address(&program_id, &deposit_account);
+
+    let request = program.request().accounts(liqz::accounts::AccountsCallLoan {
+        lender_wallet_account: lender_wallet_keypair.pubkey(),
+        lender_position_account: get_associated_token_address(
+            &lender_wallet_keypair.pubkey(),
+            &position_mint,
+        ),
+        deposit_account,
+        clock: sysvar::clock::id(),
+    });
+
+    let request = if opt.cancel {
+        request.args(liqz::instruction::CancelLoanCall {})
+    } else {
+        request.args(liqz::instruction::CallLoan {})
+    };
+
+    let tx = request.signer(&lender_wallet_keypair).send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::{Denylist, Multisig, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    // the NFT mint or the collection, i.e. the first verified creator
+    #[structopt(long, env)]
+    asset: Pubkey,
+
+    // take the asset off the denylist instead
+    #[structopt(long)]
+    allow: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+    let denylist_account = Denylist::get_address(&program_id);
+
+    // older pools may not have the denylist, the caller pays for it even for a multisig
+    let created = program
+        .rpc()
+        .get_account(&denylist_account)
+        .map_or(false, |account| account.owner == program_id);
+    if !created {
+        let tx = program
+            .request()
+            .accounts(liqz::accounts::AccountsCreateDenylist {
+                payer: pool_owner_keypair.pubkey(),
+                denylist_account,
+
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+            })
+            .args(liqz::instruction::CreateDenylist {})
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The denylist is created in {}", tx);
+    }
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeDenylist {
+        owner,
+        pool,
+        denylist_account,
+    };
+    let args = liqz::instruction::ChangeDenylist {
+        asset: opt.asset,
+        denied: !opt.allow,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+    println!("Denylist address: {}", denylist_account);
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    // secs
+    #[structopt(long, env)]
+    proposal_voting_period: Option<i64>,
+
+    // in bp of the staked LIZ
+    #[structopt(long, env)]
+    proposal_quorum: Option<u64>,
+
+    // in bp of the votes
+    #[structopt(long, env)]
+    proposal_threshold: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeLoanSetting { owner, pool };
+    let args = liqz::instruction::ChangeGovernanceSettings {
+        proposal_voting_period: opt.proposal_voting_period,
+        proposal_quorum: opt.proposal_quorum,
+        proposal_threshold: opt.proposal_threshold,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -4,7 +4,7 @@ use cli::{get_cluster, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer};
 use structopt::StructOpt;
-use liqz::NFTPool;
+use liqz::{InterestModel, Multisig, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -15,11 +15,20 @@ struct Opt {
     #[structopt(long, env)]
     pool_owner_keypair: String,
 
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
     #[structopt(long, env)]
     incentive: Option<u64>,
 
+    // in bp per year
     #[structopt(long, env)]
-    interest_rate: Option<u64>,
+    apr: Option<u64>,
+
+    // compound the interest every second instead of simple interest
+    #[structopt(long, requires = "apr")]
+    compounded: bool,
 
     #[structopt(long, env)]
     service_fee_rate: Option<u64>,
@@ -29,6 +38,21 @@ struct Opt {
 
     #[structopt(long, env)]
     mortgage_rate: Option<u64>,
+
+    // secs
+    #[structopt(long, env)]
+    min_interest_duration: Option<i64>,
+
+    #[structopt(long, env)]
+    origination_fee: Option<f64>,
+
+    // in bp of the fee, paid to each referrer of a loan
+    #[structopt(long, env)]
+    referral_fee_rate: Option<u64>,
+
+    // in bp of the fee, kept in the insurance fund
+    #[structopt(long, env)]
+    insurance_fee_rate: Option<u64>,
 }
 
 fn main() -> Result<()> {
@@ -47,23 +71,43 @@ fn main() -> Result<()> {
 
     let pool = NFTPool::get_address(&program.id());
 
-    let tx = program
-        .request()
-        .accounts(liqz::accounts::AccountsChangeLoanSetting {
-            owner: pool_owner_keypair.pubkey(),
-            pool,
-        })
-        .args(liqz::instruction::ChangeLoanSettings {
-            incentive: opt.incentive,
-            interest_rate: opt.interest_rate,
-            service_fee_rate: opt.service_fee_rate,
-            max_loan_duration: opt.max_loan_duration,
-            mortgage_rate: opt.mortgage_rate,
-        })
-        .signer(&pool_owner_keypair)
-        .send()?;
-
-    println!("The transaction is {}", tx);
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeLoanSetting { owner, pool };
+    let args = liqz::instruction::ChangeLoanSettings {
+        incentive: opt.incentive,
+        interest_model: opt.apr.map(|apr| {
+            if opt.compounded {
+                InterestModel::CompoundedApr(apr)
+            } else {
+                InterestModel::SimpleApr(apr)
+            }
+        }),
+        service_fee_rate: opt.service_fee_rate,
+        max_loan_duration: opt.max_loan_duration,
+        mortgage_rate: opt.mortgage_rate,
+        min_interest_duration: opt.min_interest_duration,
+        origination_fee: opt.origination_fee.map(|fee| (fee * 10f64.powf(9.)) as u64),
+        referral_fee_rate: opt.referral_fee_rate,
+        insurance_fee_rate: opt.insurance_fee_rate,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
     println!("Pool address: {}", pool);
 
     Ok(())
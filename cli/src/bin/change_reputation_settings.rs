@@ -0,0 +1,86 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    // loans repaid on time to get the boost, 0 disables it
+    #[structopt(long, env)]
+    reputation_min_repaid: Option<u64>,
+
+    // in bp, added to the mortgage rate
+    #[structopt(long, env)]
+    reputation_mortgage_boost: Option<u64>,
+
+    // DAI, smaller loans do not count towards the reputation
+    #[structopt(long, env)]
+    reputation_min_principal: Option<f64>,
+
+    // secs, loans repaid sooner do not count towards the reputation
+    #[structopt(long, env)]
+    reputation_min_duration: Option<i64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeLoanSetting { owner, pool };
+    let args = liqz::instruction::ChangeReputationSettings {
+        reputation_min_repaid: opt.reputation_min_repaid,
+        reputation_mortgage_boost: opt.reputation_mortgage_boost,
+        reputation_min_principal: opt
+            .reputation_min_principal
+            .map(|principal| (principal * 10f64.powf(9.)) as u64),
+        reputation_min_duration: opt.reputation_min_duration,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
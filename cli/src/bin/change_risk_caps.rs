@@ -0,0 +1,82 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    // DAI lent against one collection
+    #[structopt(long, env)]
+    max_collection_principal: Option<f64>,
+
+    // DAI lent to one borrower
+    #[structopt(long, env)]
+    max_borrower_principal: Option<f64>,
+
+    #[structopt(long, env)]
+    max_borrower_open_loans: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeLoanSetting { owner, pool };
+    let args = liqz::instruction::ChangeRiskCaps {
+        max_collection_principal: opt
+            .max_collection_principal
+            .map(|v| (v * 10f64.powf(9.)) as u64),
+        max_borrower_principal: opt
+            .max_borrower_principal
+            .map(|v| (v * 10f64.powf(9.)) as u64),
+        max_borrower_open_loans: opt.max_borrower_open_loans,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
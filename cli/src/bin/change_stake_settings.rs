@@ -0,0 +1,104 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool, StakeTier};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    // the index of the stake tier to change, 0 to 2
+    #[structopt(long, env, requires_all = &["min-stake", "fee-discount", "mortgage-boost"])]
+    tier: Option<usize>,
+
+    // LIZ, 0 disables the tier
+    #[structopt(long, env)]
+    min_stake: Option<f64>,
+
+    // in bp of the service fee rate
+    #[structopt(long, env)]
+    fee_discount: Option<u64>,
+
+    // in bp, added to the mortgage rate
+    #[structopt(long, env)]
+    mortgage_boost: Option<u64>,
+
+    #[structopt(long, env)]
+    max_mortgage_rate: Option<u64>,
+
+    // secs
+    #[structopt(long, env)]
+    unstake_cooldown: Option<i64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id());
+
+    // tiers are set all at once, keep the ones not being changed
+    let stake_tiers = match opt.tier {
+        Some(tier) => {
+            let mut stake_tiers = program.account::<NFTPool>(pool)?.stake_tiers;
+            stake_tiers[tier] = StakeTier {
+                min_stake: (opt.min_stake.unwrap() * 10f64.powf(9.)) as u64,
+                fee_discount: opt.fee_discount.unwrap(),
+                mortgage_boost: opt.mortgage_boost.unwrap(),
+            };
+            Some(stake_tiers)
+        }
+        None => None,
+    };
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsChangeLoanSetting { owner, pool };
+    let args = liqz::instruction::ChangeStakeSettings {
+        stake_tiers,
+        max_mortgage_rate: opt.max_mortgage_rate,
+        unstake_cooldown: opt.unstake_cooldown,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool, PoolTokenAccount, Treasury};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the pool is owned by a multisig, propose the change to it as a member
+    #[structopt(long)]
+    multisig: bool,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    // the DAI token account receiving the fees, defaults to the owner's
+    #[structopt(long, env)]
+    destination_dai_account: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    amount: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+
+    let owner = if opt.multisig {
+        Multisig::get_address(&program_id)
+    } else {
+        pool_owner_keypair.pubkey()
+    };
+
+    let accounts = liqz::accounts::AccountsClaimFees {
+        owner,
+        pool,
+
+        treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+        destination_dai_account: opt.destination_dai_account.unwrap_or_else(|| {
+            get_associated_token_address(&pool_owner_keypair.pubkey(), &opt.dai_mint_address)
+        }),
+
+        spl_program: spl_token::id(),
+    };
+    let args = liqz::instruction::ClaimFees {
+        amount: (opt.amount * 10f64.powf(9.)) as u64,
+    };
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(&program, &pool_owner_keypair, accounts, args)?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(accounts)
+            .args(args)
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::{Multisig, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the members of the multisig
+    #[structopt(long, required = true)]
+    member: Vec<Pubkey>,
+
+    // approvals needed to execute an admin action
+    #[structopt(long)]
+    threshold: u64,
+
+    // the multisig already owns the pool, change its members through an admin action instead
+    #[structopt(long)]
+    multisig: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let multisig_account = Multisig::get_address(&program_id);
+
+    if opt.multisig {
+        let action = cli::propose_admin_action(
+            &program,
+            &pool_owner_keypair,
+            liqz::accounts::AccountsChangeMultisigMembers { multisig_account },
+            liqz::instruction::ChangeMultisigMembers {
+                members: opt.member,
+                threshold: opt.threshold,
+            },
+        )?;
+        println!("Admin action: {}", action);
+    } else {
+        let tx = program
+            .request()
+            .accounts(liqz::accounts::AccountsCreateMultisig {
+                owner: pool_owner_keypair.pubkey(),
+                pool: NFTPool::get_address(&program_id),
+                multisig_account,
+
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+            })
+            .args(liqz::instruction::CreateMultisig {
+                members: opt.member,
+                threshold: opt.threshold,
+            })
+            .signer(&pool_owner_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+
+    println!("Multisig account: {}", multisig_account);
+
+    Ok(())
+}
@@ -20,7 +20,7 @@ struct Opt {
     borrower_wallet_address: String,
 
     #[structopt(long, env)]
-    deposit_id: Pubkey,
+    deposit_id: u64,
 }
 
 fn main() -> Result<()> {
@@ -38,7 +38,7 @@ fn main() -> Result<()> {
         &program_id,
         &opt.nft_mint_address,
         &borrower_wallet_address,
-        &opt.deposit_id,
+        opt.deposit_id,
     );
 
     let client = Client::new(get_cluster(), Keypair::generate(&mut OsRng));
@@ -1,12 +1,11 @@
 use anchor_client::Client;
 use anyhow::Result;
 use cli::{get_cluster, load_program_from_idl, Keypair};
-use rand::rngs::OsRng;
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use liqz::{NFTDeposit, NFTPool};
+use liqz::{get_metadata_address, Denylist, DepositCounter, NFTDeposit, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -40,7 +39,7 @@ fn main() -> Result<()> {
 
     let pool = NFTPool::get_address(&program.id());
 
-    let deposit_id = solana_sdk::signature::Keypair::generate(&mut OsRng).pubkey();
+    let deposit_id = cli::next_deposit_id(&program, &borrower_wallet_keypair.pubkey());
 
     let tx = program
         .request()
@@ -49,7 +48,9 @@ fn main() -> Result<()> {
             borrower_wallet_account: borrower_wallet_keypair.pubkey(),
 
             nft_mint: opt.nft_mint_address,
+            nft_metadata_account: get_metadata_address(&opt.nft_mint_address),
             liz_mint: opt.liz_mint_address,
+            denylist_account: Denylist::get_address(&program_id),
 
             borrower_nft_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
@@ -67,7 +68,11 @@ fn main() -> Result<()> {
                 &program_id,
                 &opt.nft_mint_address,
                 &borrower_wallet_keypair.pubkey(),
-                &deposit_id,
+                deposit_id,
+            ),
+            deposit_counter_account: DepositCounter::get_address(
+                &program_id,
+                &borrower_wallet_keypair.pubkey(),
             ),
 
             ata_program: spl_associated_token_account::id(),
@@ -75,7 +80,7 @@ fn main() -> Result<()> {
             rent: sysvar::rent::id(),
             system_program: system_program::id(),
         })
-        .args(liqz::instruction::DepositNft { deposit_id })
+        .args(liqz::instruction::DepositNft {})
         .signer(&borrower_wallet_keypair)
         .send()?;
 
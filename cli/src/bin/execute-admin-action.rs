@@ -0,0 +1,63 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use liqz::{AdminAction, Multisig};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    payer_keypair: String,
+
+    #[structopt(long, env)]
+    action_account: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let payer_keypair = keypair_of(&Opt::clap().get_matches(), "payer-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&payer_keypair));
+    let program = client.program(program_id);
+
+    let action: AdminAction = program.account(opt.action_account)?;
+
+    // the multisig signs inside the program, not in the transaction.
+    // An action that creates accounts names a payer, which has to be the executor.
+    let mut remaining_accounts: Vec<AccountMeta> = action
+        .accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.pubkey,
+            is_signer: account.is_signer && account.pubkey == payer_keypair.pubkey(),
+            is_writable: account.is_writable,
+        })
+        .collect();
+    remaining_accounts.push(AccountMeta::new_readonly(program_id, false));
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsExecuteAdminAction {
+            multisig_account: Multisig::get_address(&program_id),
+            action_account: opt.action_account,
+        })
+        .accounts(remaining_accounts)
+        .args(liqz::instruction::ExecuteAdminAction {})
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
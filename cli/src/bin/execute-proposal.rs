@@ -0,0 +1,49 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, sysvar};
+use structopt::StructOpt;
+use liqz::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    payer_keypair: String,
+
+    #[structopt(long, env)]
+    proposal_account: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let payer_keypair = keypair_of(&Opt::clap().get_matches(), "payer-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&payer_keypair));
+    let program = client.program(program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsExecuteProposal {
+            pool: NFTPool::get_address(&program_id),
+            proposal_account: opt.proposal_account,
+
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::ExecuteProposal {})
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
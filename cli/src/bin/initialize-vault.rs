@@ -0,0 +1,65 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{LendingVault, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+    let vault = LendingVault::get_address(&program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsInitializeVault {
+            owner: pool_owner_keypair.pubkey(),
+            pool,
+            payer: pool_owner_keypair.pubkey(),
+
+            vault,
+            lp_mint: LendingVault::get_lp_mint_address(&program_id),
+
+            dai_mint: opt.dai_mint_address,
+            vault_dai_account: get_associated_token_address(&vault, &opt.dai_mint_address),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        })
+        .args(liqz::instruction::InitializeVault {})
+        .signer(&pool_owner_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Vault address: {}", vault);
+
+    Ok(())
+}
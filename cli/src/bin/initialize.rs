@@ -7,7 +7,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use structopt::StructOpt;
-use liqz::NFTPool;
+use liqz::{Denylist, InsuranceFund, MigrationBuffer, NFTPool, PoolTokenAccount, Treasury};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -67,6 +67,10 @@ fn main() -> Result<()> {
                 &pool,
                 &opt.dai_mint_address,
             ),
+            treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+            insurance_dai_account: InsuranceFund::get_address(&program_id, &opt.dai_mint_address),
+            denylist_account: Denylist::get_address(&program_id),
+            migration_buffer: MigrationBuffer::get_address(&program_id, &pool),
 
             ata_program: spl_associated_token_account::id(),
             spl_program: spl_token::id(),
@@ -0,0 +1,79 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{InsuranceFund, Multisig, NFTAppraisal, NFTDeposit, NFTPool, PoolTokenAccount};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    // payouts are admin actions of the multisig, propose it as a member
+    #[structopt(long, env)]
+    member_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_address: Pubkey, // the lender who took the NFT
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: u64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let member_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "member-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&member_wallet_keypair));
+    let program = client.program(program_id);
+
+    let accounts = liqz::accounts::AccountsInsurancePayout {
+        multisig_account: Multisig::get_address(&program_id),
+        pool: NFTPool::get_address(&program_id),
+
+        insurance_dai_account: InsuranceFund::get_address(&program_id, &opt.dai_mint_address),
+        lender_dai_account: get_associated_token_address(
+            &opt.lender_wallet_address,
+            &opt.dai_mint_address,
+        ),
+
+        nft_mint: opt.nft_mint_address,
+        borrower_wallet_account: opt.borrower_wallet_address,
+        appraisal_account: NFTAppraisal::get_address(&program_id, &opt.nft_mint_address),
+
+        deposit_account: NFTDeposit::get_address(
+            &program_id,
+            &opt.nft_mint_address,
+            &opt.borrower_wallet_address,
+            opt.deposit_id,
+        ),
+
+        spl_program: spl_token::id(),
+    };
+    let args = liqz::instruction::InsurancePayout {};
+
+    let action = cli::propose_admin_action(&program, &member_wallet_keypair, accounts, args)?;
+    println!("Admin action: {}", action);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::{Credential, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    credential_authority_keypair: String,
+
+    #[structopt(long, env)]
+    wallet_address: Pubkey,
+
+    // unix timestamp, the credential never expires if not set
+    #[structopt(long, env)]
+    expires_at: Option<i64>,
+
+    // revoke the credential of the wallet instead
+    #[structopt(long)]
+    revoke: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let credential_authority_keypair =
+        keypair_of(&Opt::clap().get_matches(), "credential-authority-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&credential_authority_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+    let credential_account = Credential::get_address(&program_id, &opt.wallet_address);
+
+    let tx = if opt.revoke {
+        program
+            .request()
+            .accounts(liqz::accounts::AccountsRevokeCredential {
+                authority: credential_authority_keypair.pubkey(),
+                pool,
+                wallet_account: opt.wallet_address,
+                credential_account,
+            })
+            .args(liqz::instruction::RevokeCredential {})
+            .signer(&credential_authority_keypair)
+            .send()?
+    } else {
+        program
+            .request()
+            .accounts(liqz::accounts::AccountsIssueCredential {
+                authority: credential_authority_keypair.pubkey(),
+                pool,
+                wallet_account: opt.wallet_address,
+                credential_account,
+
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+            })
+            .args(liqz::instruction::IssueCredential {
+                expires_at: opt.expires_at,
+            })
+            .signer(&credential_authority_keypair)
+            .send()?
+    };
+
+    println!("The transaction is {}", tx);
+    println!("Credential account: {}", credential_account);
+
+    Ok(())
+}
@@ -1,12 +1,15 @@
 use anchor_client::Client;
 use anyhow::Result;
-use cli::{get_cluster, load_program_from_idl, Keypair};
+use cli::{get_cluster, load_program_from_idl, referrer_account_metas, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_clap_utils::input_parsers::pubkey_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use liqz::{NFTDeposit, NFTPool};
+use liqz::{
+    Exposure, InsuranceFund, LenderPosition, NFTDeposit, NFTPool, PoolTokenAccount, Reputation,
+    Treasury,
+};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -14,18 +17,12 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     liqz_program_address: Option<Pubkey>,
 
-    #[structopt(long, env)]
-    pool_owner_address: String,
-
     #[structopt(long, env)]
     borrower_wallet_address: Pubkey,
 
     #[structopt(long, env)]
     lender_wallet_keypair: String,
 
-    #[structopt(long, env)]
-    tai_mint_address: Pubkey,
-
     #[structopt(long, env)]
     dai_mint_address: Pubkey,
 
@@ -33,7 +30,7 @@ struct Opt {
     nft_mint_address: Pubkey,
 
     #[structopt(long, env)]
-    deposit_id: Pubkey,
+    deposit_id: u64,
 }
 
 fn main() -> Result<()> {
@@ -46,12 +43,20 @@ fn main() -> Result<()> {
 
     let lender_wallet_keypair =
         keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
-    let pool_owner_address = pubkey_of(&Opt::clap().get_matches(), "pool-owner-address").unwrap();
-
     let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
     let program = client.program(program_id);
 
     let pool = NFTPool::get_address(&program.id());
+    let deposit_account = NFTDeposit::get_address(
+        &program_id,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        opt.deposit_id,
+    );
+    let position_mint = LenderPosition::get_address(&program_id, &deposit_account);
+
+    let deposit: NFTDeposit = program.account(deposit_account)?;
+    let loan = deposit.get_active_state()?;
 
     let tx = program
         .request()
@@ -71,23 +76,30 @@ fn main() -> Result<()> {
                 &opt.dai_mint_address
             )),
             pool_dai_account: dbg!(get_associated_token_address(&pool, &opt.dai_mint_address)),
-            pool_owner_dai_account: dbg!(get_associated_token_address(
-                &pool_owner_address,
+            treasury_dai_account: dbg!(Treasury::get_address(&program_id, &opt.dai_mint_address)),
+            insurance_dai_account: dbg!(InsuranceFund::get_address(
+                &program_id,
                 &opt.dai_mint_address
             )),
 
-            lender_tai_account: dbg!(get_associated_token_address(
+            position_mint,
+            lender_position_account: dbg!(get_associated_token_address(
                 &lender_wallet_keypair.pubkey(),
-                &opt.tai_mint_address
+                &position_mint
             )),
-            pool_tai_account: dbg!(get_associated_token_address(&pool, &opt.tai_mint_address)),
 
-            deposit_account: dbg!(NFTDeposit::get_address(
+            deposit_account: dbg!(deposit_account),
+            collection_exposure_account: Exposure::get_address(
                 &program_id,
-                &opt.nft_mint_address,
-                &opt.borrower_wallet_address,
-                &opt.deposit_id,
-            )),
+                Exposure::COLLECTION,
+                &loan.collection,
+            ),
+            borrower_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::BORROWER,
+                &loan.borrower,
+            ),
+            borrower_reputation_account: Reputation::get_address(&program_id, &loan.borrower),
 
             ata_program: spl_associated_token_account::id(),
             spl_program: spl_token::id(),
@@ -95,6 +107,11 @@ fn main() -> Result<()> {
             rent: sysvar::rent::id(),
             clock: sysvar::clock::id(),
         })
+        .accounts(referrer_account_metas(
+            &program_id,
+            &deposit,
+            &opt.dai_mint_address,
+        ))
         .args(liqz::instruction::Liquidate {})
         .signer(&lender_wallet_keypair)
         .send()?;
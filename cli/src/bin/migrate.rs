@@ -0,0 +1,168 @@
+use anchor_client::anchor_lang::AnchorDeserialize;
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{
+    Denylist, DepositCounter, InsuranceFund, LegacyNFTDeposit, LegacyNFTPool, LenderPosition,
+    MigrationBuffer, NFTBid, NFTDeposit, NFTPool, PoolTokenAccount, Treasury,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    // the pool owner, the bidder, or the payer of a deposit migration
+    #[structopt(long, env)]
+    wallet_keypair: String,
+
+    // the legacy deposit account to migrate
+    #[structopt(long, conflicts_with = "bid", requires_all = &["nft-mint-address", "borrower-wallet-address"])]
+    deposit_account: Option<Pubkey>,
+
+    #[structopt(long)]
+    borrower_wallet_address: Option<Pubkey>,
+
+    // migrate the bid of the wallet on the NFT
+    #[structopt(long, requires = "nft-mint-address")]
+    bid: bool,
+
+    #[structopt(long, env)]
+    nft_mint_address: Option<Pubkey>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let wallet_keypair = keypair_of(&Opt::clap().get_matches(), "wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+
+    // the pool is migrated unless a deposit or a bid is given
+    if let Some(legacy_deposit_account) = opt.deposit_account {
+        let nft_mint = opt.nft_mint_address.unwrap();
+        let borrower = opt.borrower_wallet_address.unwrap();
+
+        let data = program.rpc().get_account_data(&legacy_deposit_account)?;
+        let legacy = LegacyNFTDeposit::deserialize(&mut &data[8..])?;
+        let lender = legacy.lender().unwrap_or_default();
+
+        let deposit_id = cli::next_deposit_id(&program, &borrower);
+        let deposit_account =
+            NFTDeposit::get_address(&program_id, &nft_mint, &borrower, deposit_id);
+        let position_mint = LenderPosition::get_address(&program_id, &deposit_account);
+
+        let tx = program
+            .request()
+            .accounts(liqz::accounts::AccountsMigrateDeposit {
+                pool,
+                payer: wallet_keypair.pubkey(),
+                borrower_wallet_account: borrower,
+
+                nft_mint,
+
+                legacy_deposit_account,
+                deposit_account,
+                deposit_counter_account: DepositCounter::get_address(&program_id, &borrower),
+
+                lender_wallet_account: lender,
+                position_mint,
+                lender_position_account: get_associated_token_address(&lender, &position_mint),
+
+                ata_program: spl_associated_token_account::id(),
+                spl_program: spl_token::id(),
+                system_program: system_program::id(),
+                rent: sysvar::rent::id(),
+            })
+            .args(liqz::instruction::MigrateDeposit {})
+            .signer(&wallet_keypair)
+            .send()?;
+
+        println!("The transaction is {}", tx);
+        println!("The deposit id is now {}", deposit_id);
+    } else if opt.bid {
+        let nft_mint = opt.nft_mint_address.unwrap();
+        let bid_account = NFTBid::get_address(&program_id, &nft_mint, &wallet_keypair.pubkey());
+
+        let accounts = || liqz::accounts::AccountsMigrateBid {
+            lender_wallet_account: wallet_keypair.pubkey(),
+            nft_mint,
+            bid_account,
+            migration_buffer: MigrationBuffer::get_address(&program_id, &bid_account),
+            rent: sysvar::rent::id(),
+            system_program: system_program::id(),
+        };
+
+        // the bid address is only free again once the stashing transaction is done
+        let tx = program
+            .request()
+            .accounts(accounts())
+            .args(liqz::instruction::MigrateBid {})
+            .signer(&wallet_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+
+        let tx = program
+            .request()
+            .accounts(accounts())
+            .args(liqz::instruction::RestoreBid {})
+            .signer(&wallet_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    } else {
+        let data = program.rpc().get_account_data(&pool)?;
+        let legacy = LegacyNFTPool::deserialize(&mut &data[8..])?;
+        let migration_buffer = MigrationBuffer::get_address(&program_id, &pool);
+
+        let tx = program
+            .request()
+            .accounts(liqz::accounts::AccountsMigratePool {
+                owner: wallet_keypair.pubkey(),
+                pool,
+                liz_mint: legacy.liz_mint,
+                dai_mint: legacy.dai_mint,
+                pool_dai_account: get_associated_token_address(&pool, &legacy.dai_mint),
+                migration_buffer,
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(liqz::instruction::MigratePool {})
+            .signer(&wallet_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+
+        let tx = program
+            .request()
+            .accounts(liqz::accounts::AccountsRestorePool {
+                owner: wallet_keypair.pubkey(),
+                pool,
+                dai_mint: legacy.dai_mint,
+                treasury_dai_account: Treasury::get_address(&program_id, &legacy.dai_mint),
+                insurance_dai_account: InsuranceFund::get_address(&program_id, &legacy.dai_mint),
+                denylist_account: Denylist::get_address(&program_id),
+                migration_buffer,
+                spl_program: spl_token::id(),
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(liqz::instruction::RestorePool {})
+            .signer(&wallet_keypair)
+            .send()?;
+        println!("The transaction is {}", tx);
+    }
+
+    Ok(())
+}
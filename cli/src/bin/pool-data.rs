@@ -34,5 +34,14 @@ fn main() -> Result<()> {
         pool_account, content
     );
 
+    println!("Total deposits: {}", content.total_deposits);
+    println!("Active loans: {}", content.active_loans);
+    println!("Outstanding principal: {}", content.outstanding_principal);
+    println!("Interest paid: {}", content.interest_paid);
+    println!("Fees accrued: {}", content.dai_fees_accrued);
+    println!("Liquidations: {}", content.liquidations);
+    println!("Default volume: {}", content.default_volume);
+    println!("DAI locked for lenders: {}", content.dai_locked);
+
     Ok(())
 }
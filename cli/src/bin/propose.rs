@@ -0,0 +1,109 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::{InterestModel, LoanSettings, NFTPool, Proposal};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    proposer_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    incentive: Option<u64>,
+
+    // in bp per year
+    #[structopt(long, env)]
+    apr: Option<u64>,
+
+    // compound the interest every second instead of simple interest
+    #[structopt(long, requires = "apr")]
+    compounded: bool,
+
+    #[structopt(long, env)]
+    service_fee_rate: Option<u64>,
+
+    #[structopt(long, env)]
+    max_loan_duration: Option<i64>,
+
+    #[structopt(long, env)]
+    mortgage_rate: Option<u64>,
+
+    // secs
+    #[structopt(long, env)]
+    min_interest_duration: Option<i64>,
+
+    #[structopt(long, env)]
+    origination_fee: Option<f64>,
+
+    // in bp of the fee, paid to each referrer of a loan
+    #[structopt(long, env)]
+    referral_fee_rate: Option<u64>,
+
+    // in bp of the fee, kept in the insurance fund
+    #[structopt(long, env)]
+    insurance_fee_rate: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let proposer_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "proposer-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&proposer_wallet_keypair));
+    let program = client.program(program_id);
+
+    let proposal_id = solana_sdk::signature::Keypair::new().pubkey();
+    let proposal_account = Proposal::get_address(&program_id, &proposal_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsPropose {
+            pool: NFTPool::get_address(&program_id),
+            proposer_wallet_account: proposer_wallet_keypair.pubkey(),
+            proposal_account,
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::Propose {
+            proposal_id,
+            settings: LoanSettings {
+                incentive: opt.incentive,
+                interest_model: opt.apr.map(|apr| {
+                    if opt.compounded {
+                        InterestModel::CompoundedApr(apr)
+                    } else {
+                        InterestModel::SimpleApr(apr)
+                    }
+                }),
+                service_fee_rate: opt.service_fee_rate,
+                max_loan_duration: opt.max_loan_duration,
+                mortgage_rate: opt.mortgage_rate,
+                min_interest_duration: opt.min_interest_duration,
+                origination_fee: opt.origination_fee.map(|fee| (fee * 10f64.powf(9.)) as u64),
+                referral_fee_rate: opt.referral_fee_rate,
+                insurance_fee_rate: opt.insurance_fee_rate,
+            },
+        })
+        .signer(&proposer_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Proposal account: {}", proposal_account);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::Referrer;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    referrer_wallet_keypair: String,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let referrer_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "referrer-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&referrer_wallet_keypair));
+    let program = client.program(program_id);
+
+    let referrer_account = Referrer::get_address(&program_id, &referrer_wallet_keypair.pubkey());
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsRegisterReferrer {
+            referrer_wallet_account: referrer_wallet_keypair.pubkey(),
+            referrer_account,
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        })
+        .args(liqz::instruction::RegisterReferrer {})
+        .signer(&referrer_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Referrer account: {}", referrer_account);
+
+    Ok(())
+}
@@ -1,12 +1,11 @@
 use anchor_client::Client;
 use anyhow::Result;
-use cli::{get_cluster, load_program_from_idl, Keypair};
+use cli::{get_cluster, load_program_from_idl, referrer_account_metas, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
-use solana_clap_utils::input_parsers::pubkey_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use liqz::{NFTDeposit, NFTPool};
+use liqz::{Exposure, InsuranceFund, NFTDeposit, NFTPool, PoolTokenAccount, Reputation, Treasury};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
@@ -14,15 +13,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     liqz_program_address: Option<Pubkey>,
 
-    #[structopt(long, env)]
-    pool_owner_address: String,
-
     #[structopt(long, env)]
     borrower_wallet_keypair: String,
 
-    #[structopt(long, env)]
-    lender_wallet_address: String,
-
     #[structopt(long, env)]
     dai_mint_address: Pubkey,
 
@@ -30,7 +23,7 @@ struct Opt {
     nft_mint_address: Pubkey,
 
     #[structopt(long, env)]
-    deposit_id: Pubkey,
+    deposit_id: u64,
 }
 
 fn main() -> Result<()> {
@@ -43,14 +36,20 @@ fn main() -> Result<()> {
 
     let borrower_wallet_keypair =
         keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
-    let pool_owner_address = pubkey_of(&Opt::clap().get_matches(), "pool-owner-address").unwrap();
-    let lender_wallet_address =
-        pubkey_of(&Opt::clap().get_matches(), "lender-wallet-address").unwrap();
 
     let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
     let program = client.program(program_id);
 
     let pool = NFTPool::get_address(&program.id());
+    let deposit_account = NFTDeposit::get_address(
+        &program_id,
+        &opt.nft_mint_address,
+        &borrower_wallet_keypair.pubkey(),
+        opt.deposit_id,
+    );
+
+    let deposit: NFTDeposit = program.account(deposit_account)?;
+    let loan = deposit.get_active_state()?;
 
     let tx = program
         .request()
@@ -58,35 +57,42 @@ fn main() -> Result<()> {
             pool,
             borrower_wallet_account: borrower_wallet_keypair.pubkey(),
 
-            pool_owner_dai_account: get_associated_token_address(
-                &pool_owner_address,
-                &opt.dai_mint_address,
-            ),
+            treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+            insurance_dai_account: InsuranceFund::get_address(&program_id, &opt.dai_mint_address),
             borrower_dai_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
                 &opt.dai_mint_address,
             ),
-            lender_dai_account: get_associated_token_address(
-                &lender_wallet_address,
-                &opt.dai_mint_address,
-            ),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
 
+            nft_mint: opt.nft_mint_address,
             borrower_nft_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
                 &opt.nft_mint_address,
             ),
             pool_nft_account: get_associated_token_address(&pool, &opt.nft_mint_address),
 
-            deposit_account: NFTDeposit::get_address(
+            deposit_account,
+            collection_exposure_account: Exposure::get_address(
                 &program_id,
-                &opt.nft_mint_address,
-                &borrower_wallet_keypair.pubkey(),
-                &opt.deposit_id,
+                Exposure::COLLECTION,
+                &loan.collection,
+            ),
+            borrower_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::BORROWER,
+                &loan.borrower,
             ),
+            borrower_reputation_account: Reputation::get_address(&program_id, &loan.borrower),
 
             spl_program: spl_token::id(),
             clock: sysvar::clock::id(),
         })
+        .accounts(referrer_account_metas(
+            &program_id,
+            &deposit,
+            &opt.dai_mint_address,
+        ))
         .args(liqz::instruction::Repay {})
         .signer(&borrower_wallet_keypair)
         .send()?;
@@ -0,0 +1,41 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::get_cluster;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use structopt::StructOpt;
+use liqz::Reputation;
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let reputation_account = Reputation::get_address(&program_id, &opt.borrower_wallet_address);
+
+    // reading an account does not need a real payer
+    let client = Client::new(get_cluster(), Keypair::new());
+    let program = client.program(program_id);
+
+    let content: Reputation = program.account(reputation_account)?;
+
+    println!(
+        "The reputation address is {} with content {:?}",
+        reputation_account, content
+    );
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{LizStake, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    staker_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    liz_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    amount: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let staker_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "staker-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&staker_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+    let stake_account = LizStake::get_address(&program_id, &staker_wallet_keypair.pubkey());
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsStakeLiz {
+            pool,
+            staker_wallet_account: staker_wallet_keypair.pubkey(),
+
+            liz_mint: opt.liz_mint_address,
+            staker_liz_account: get_associated_token_address(
+                &staker_wallet_keypair.pubkey(),
+                &opt.liz_mint_address,
+            ),
+            stake_vault_account: LizStake::get_vault_address(&program_id),
+            stake_account,
+
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::StakeLiz {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+        })
+        .signer(&staker_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Stake account: {}", stake_account);
+
+    Ok(())
+}
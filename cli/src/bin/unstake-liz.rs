@@ -0,0 +1,93 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{LizStake, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    staker_wallet_keypair: String,
+
+    // the amount to start unstaking
+    #[structopt(long, env, required_unless = "withdraw")]
+    amount: Option<f64>,
+
+    // withdraw the LIZ whose cooldown is over instead
+    #[structopt(long, conflicts_with = "amount")]
+    withdraw: bool,
+
+    // required to withdraw
+    #[structopt(long, env)]
+    liz_mint_address: Option<Pubkey>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let staker_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "staker-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&staker_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program_id);
+    let stake_account = LizStake::get_address(&program_id, &staker_wallet_keypair.pubkey());
+
+    let tx = if opt.withdraw {
+        let liz_mint_address = opt
+            .liz_mint_address
+            .expect("--liz-mint-address is required");
+
+        program
+            .request()
+            .accounts(liqz::accounts::AccountsWithdrawUnstaked {
+                pool,
+                staker_wallet_account: staker_wallet_keypair.pubkey(),
+                stake_account,
+
+                stake_vault_account: LizStake::get_vault_address(&program_id),
+                staker_liz_account: get_associated_token_address(
+                    &staker_wallet_keypair.pubkey(),
+                    &liz_mint_address,
+                ),
+
+                spl_program: spl_token::id(),
+                clock: sysvar::clock::id(),
+            })
+            .args(liqz::instruction::WithdrawUnstaked {})
+            .signer(&staker_wallet_keypair)
+            .send()?
+    } else {
+        program
+            .request()
+            .accounts(liqz::accounts::AccountsRequestUnstake {
+                pool,
+                staker_wallet_account: staker_wallet_keypair.pubkey(),
+                stake_account,
+
+                clock: sysvar::clock::id(),
+            })
+            .args(liqz::instruction::RequestUnstake {
+                amount: (opt.amount.unwrap() * 10f64.powf(9.)) as u64,
+            })
+            .signer(&staker_wallet_keypair)
+            .send()?
+    };
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, get_collection, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{
+    get_metadata_address, Credential, Denylist, Exposure, LendingVault, NFTAppraisal, NFTDeposit,
+    NFTPool, PoolTokenAccount, Reputation, Treasury,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    borrower_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: u64,
+
+    #[structopt(long, env)]
+    amount: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let borrower_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
+    let program = client.program(program_id);
+
+    let vault = LendingVault::get_address(&program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsVaultBorrow {
+            pool: NFTPool::get_address(&program_id),
+            vault,
+            borrower_wallet_account: borrower_wallet_keypair.pubkey(),
+
+            nft_mint: opt.nft_mint_address,
+            nft_metadata_account: get_metadata_address(&opt.nft_mint_address),
+            denylist_account: Denylist::get_address(&program_id),
+            borrower_credential_account: Credential::get_address(
+                &program_id,
+                &borrower_wallet_keypair.pubkey(),
+            ),
+            appraisal_account: NFTAppraisal::get_address(&program_id, &opt.nft_mint_address),
+
+            treasury_dai_account: Treasury::get_address(&program_id, &opt.dai_mint_address),
+            vault_dai_account: get_associated_token_address(&vault, &opt.dai_mint_address),
+            borrower_dai_account: get_associated_token_address(
+                &borrower_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+
+            deposit_account: NFTDeposit::get_address(
+                &program_id,
+                &opt.nft_mint_address,
+                &borrower_wallet_keypair.pubkey(),
+                opt.deposit_id,
+            ),
+            collection_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::COLLECTION,
+                &get_collection(&program, &opt.nft_mint_address),
+            ),
+            borrower_exposure_account: Exposure::get_address(
+                &program_id,
+                Exposure::BORROWER,
+                &borrower_wallet_keypair.pubkey(),
+            ),
+            borrower_reputation_account: Reputation::get_address(
+                &program_id,
+                &borrower_wallet_keypair.pubkey(),
+            ),
+
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::VaultBorrow {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+        })
+        .signer(&borrower_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use liqz::{Credential, LendingVault, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long)]
+    amount: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let vault = LendingVault::get_address(&program_id);
+    let lp_mint = LendingVault::get_lp_mint_address(&program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsVaultDeposit {
+            pool: NFTPool::get_address(&program_id),
+            vault,
+            lender_wallet_account: lender_wallet_keypair.pubkey(),
+            lender_credential_account: Credential::get_address(
+                &program_id,
+                &lender_wallet_keypair.pubkey(),
+            ),
+
+            lp_mint,
+            lender_lp_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &lp_mint,
+            ),
+
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+            vault_dai_account: get_associated_token_address(&vault, &opt.dai_mint_address),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::VaultDeposit {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+        })
+        .signer(&lender_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use liqz::{LizStake, ProposalVote};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the liqz Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    liqz_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    voter_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    proposal_account: Pubkey,
+
+    // vote against the proposal instead
+    #[structopt(long)]
+    against: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .liqz_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let voter_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "voter-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&voter_wallet_keypair));
+    let program = client.program(program_id);
+
+    let tx = program
+        .request()
+        .accounts(liqz::accounts::AccountsVote {
+            voter_wallet_account: voter_wallet_keypair.pubkey(),
+            stake_account: LizStake::get_address(&program_id, &voter_wallet_keypair.pubkey()),
+            proposal_account: opt.proposal_account,
+            vote_account: ProposalVote::get_address(
+                &program_id,
+                &opt.proposal_account,
+                &voter_wallet_keypair.pubkey(),
+            ),
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(liqz::instruction::Vote {
+            support: !opt.against,
+        })
+        .signer(&voter_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
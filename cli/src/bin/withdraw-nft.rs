@@ -26,7 +26,7 @@ struct Opt {
     nft_mint_address: Pubkey,
 
     #[structopt(long, env)]
-    deposit_id: Pubkey,
+    deposit_id: u64,
 }
 
 fn main() -> Result<()> {
@@ -62,7 +62,7 @@ fn main() -> Result<()> {
                 &program_id,
                 &opt.nft_mint_address,
                 &borrower_wallet_keypair.pubkey(),
-                &opt.deposit_id
+                opt.deposit_id
             )),
 
             spl_program: spl_token::id(),
@@ -1,8 +1,15 @@
-use anchor_client::Cluster;
+use anchor_client::anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_client::{ClientError, Cluster, Program};
 use derive_more::Deref;
+use liqz::{
+    get_metadata_address, parse_verified_creator, AdminAction, AdminActionAccount, DepositCounter,
+    Multisig, NFTDeposit, Referrer,
+};
 use serde::Deserialize;
 use serde_json::from_reader;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
 use std::{fs::File, str::FromStr};
 
 #[derive(Debug, Deref)]
@@ -51,3 +58,82 @@ pub fn get_cluster() -> Cluster {
         "wss://api.devnet.solana.com".into(),
     )
 }
+
+// The referrer accounts of an active loan, passed as the remaining accounts of repay and liquidate
+pub fn referrer_account_metas(
+    program_id: &Pubkey,
+    deposit: &NFTDeposit,
+    dai_mint: &Pubkey,
+) -> Vec<AccountMeta> {
+    let referrers = deposit
+        .get_active_state()
+        .map(|loan| loan.referrers())
+        .unwrap_or_default();
+
+    referrers
+        .iter()
+        .flat_map(|referrer| {
+            vec![
+                AccountMeta::new(Referrer::get_address(program_id, referrer), false),
+                AccountMeta::new(get_associated_token_address(referrer, dai_mint), false),
+            ]
+        })
+        .collect()
+}
+
+// The collection a loan on the NFT counts against, the first verified creator or else the mint itself
+pub fn get_collection(program: &Program, nft_mint: &Pubkey) -> Pubkey {
+    program
+        .rpc()
+        .get_account_data(&get_metadata_address(nft_mint))
+        .ok()
+        .and_then(|data| parse_verified_creator(&data).ok().flatten())
+        .unwrap_or(*nft_mint)
+}
+
+// The id the program assigns to the next deposit of the borrower
+pub fn next_deposit_id(program: &Program, borrower: &Pubkey) -> u64 {
+    program
+        .account::<DepositCounter>(DepositCounter::get_address(&program.id(), borrower))
+        .map(|counter| counter.next_id)
+        .unwrap_or(0)
+}
+
+// Proposes an owner-only instruction to the multisig owning the pool, returns the admin action account
+pub fn propose_admin_action(
+    program: &Program,
+    member: &solana_sdk::signature::Keypair,
+    accounts: impl ToAccountMetas,
+    args: impl InstructionData,
+) -> Result<Pubkey, ClientError> {
+    let action_id = solana_sdk::signature::Keypair::new().pubkey();
+    let action_account = AdminAction::get_address(&program.id(), &action_id);
+
+    program
+        .request()
+        .accounts(liqz::accounts::AccountsProposeAdminAction {
+            member_wallet_account: member.pubkey(),
+            multisig_account: Multisig::get_address(&program.id()),
+            action_account,
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        })
+        .args(liqz::instruction::ProposeAdminAction {
+            action_id,
+            action_accounts: accounts
+                .to_account_metas(None)
+                .into_iter()
+                .map(|meta| AdminActionAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            action_data: args.data(),
+        })
+        .signer(member)
+        .send()?;
+
+    Ok(action_account)
+}
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
+
+use crate::{utils, Credential, DerivedAccountIdentifier, NFTPool, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for Credential {
+    const SEED: &'static [u8] = b"liqzCredential";
+}
+
+impl Credential {
+    // Issuing again renews a revoked or expired credential
+    #[throws(ProgramError)]
+    pub fn issue<'info>(
+        program_id: &Pubkey,
+        pool: &NFTPool,
+        authority: &AccountInfo<'info>,
+        wallet: &Pubkey,
+        credential_account: &AccountInfo<'info>,
+        expires_at: Option<UnixTimestamp>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        Self::verify_authority(pool, authority.key)?;
+
+        let (_, bump) = Self::get_address_with_bump(program_id, wallet);
+
+        Self::verify_address(program_id, wallet, bump, credential_account.key)?;
+
+        let instance = Credential {
+            authority: *authority.key,
+            expires_at,
+            revoked: false,
+        };
+
+        if !utils::is_program_account(credential_account, program_id) {
+            // size the account for an expiry so that it can be set later
+            let largest_instance = Credential {
+                expires_at: Some(0),
+                ..instance
+            };
+
+            let acc_size = 8 + largest_instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &wallet.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                authority,
+                seeds_with_bump,
+                credential_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+        }
+
+        {
+            let mut data = credential_account.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        ProgramAccount::try_from(credential_account)?
+    }
+
+    #[throws(liqzError)]
+    pub fn verify_authority(pool: &NFTPool, authority: &Pubkey) {
+        if pool.credential_authority.as_ref() != Some(authority) {
+            throw!(liqzError::NotCredentialAuthority);
+        }
+    }
+
+    // Any wallet is allowed unless the pool is in allowlist mode
+    #[throws(ProgramError)]
+    pub fn verify_allowed(
+        program_id: &Pubkey,
+        pool: &NFTPool,
+        wallet: &Pubkey,
+        credential_account: &AccountInfo,
+        now: UnixTimestamp,
+    ) {
+        let authority = match pool.credential_authority {
+            Some(authority) => authority,
+            None => return,
+        };
+
+        if credential_account.key != &Self::get_address(program_id, wallet) {
+            throw!(liqzError::CredentialAddressNotCorrect);
+        }
+
+        if !utils::is_program_account(credential_account, program_id) {
+            throw!(liqzError::CredentialMissing);
+        }
+
+        let credential: ProgramAccount<Credential> = ProgramAccount::try_from(credential_account)?;
+
+        // credentials of a former authority are no longer valid
+        if credential.revoked || credential.authority != authority {
+            throw!(liqzError::CredentialRevoked);
+        }
+
+        if matches!(credential.expires_at, Some(expires_at) if now >= expires_at) {
+            throw!(liqzError::CredentialExpired);
+        }
+    }
+
+    // An program derived account that allows a wallet to bid and borrow in a private pool
+    // The address of the account is computed as follow:
+    // address = find_program_address([Credential::SEED, wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, wallet: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, wallet).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &wallet.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, wallet: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr =
+            Pubkey::create_program_address(&[Self::SEED, &wallet.to_bytes(), &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::CredentialAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn credential_is_issued_at_an_address_funded_by_someone_else() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let authority = test_utils::wallet();
+        let wallet = Pubkey::new_unique();
+        let mut pool = test_utils::pool();
+        pool.credential_authority = Some(*authority.key);
+        let credential_account =
+            test_utils::funded_address(Credential::get_address(&program_id, &wallet));
+
+        assert!(
+            Credential::verify_allowed(&program_id, &pool, &wallet, &credential_account, 0)
+                .is_err()
+        );
+
+        let credential = Credential::issue(
+            &program_id,
+            &pool,
+            &authority,
+            &wallet,
+            &credential_account,
+            None,
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+        assert_eq!(credential.authority, *authority.key);
+    }
+}
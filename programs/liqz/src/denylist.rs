@@ -0,0 +1,152 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, Denylist, DerivedAccountIdentifier, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+pub const MAX_DENYLIST_ASSETS: usize = 100;
+
+impl DerivedAccountIdentifier for Denylist {
+    const SEED: &'static [u8] = b"liqzDenylist";
+}
+
+impl Denylist {
+    // Created with the pool, pools created before the denylist get it from create_denylist
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        funder: &AccountInfo<'info>,
+        denylist_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id);
+
+        Self::verify_address(program_id, bump, denylist_account.key)?;
+
+        if utils::is_program_account(denylist_account, program_id) {
+            return;
+        }
+
+        // size the account so that it can be filled later
+        let largest_instance = Denylist {
+            assets: vec![Pubkey::default(); MAX_DENYLIST_ASSETS],
+        };
+
+        let acc_size = 8 + largest_instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            funder,
+            &[Self::SEED, &[bump]],
+            denylist_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        let instance = Denylist { assets: vec![] };
+
+        let mut data = denylist_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // An asset is an NFT mint or a collection, i.e. the first verified creator
+    #[throws(liqzError)]
+    pub fn set(&mut self, asset: Pubkey, denied: bool) {
+        let listed = self.assets.iter().position(|a| a == &asset);
+
+        match (listed, denied) {
+            (None, true) => {
+                if self.assets.len() >= MAX_DENYLIST_ASSETS {
+                    throw!(liqzError::DenylistFull);
+                }
+                self.assets.push(asset);
+            }
+            (Some(i), false) => {
+                self.assets.swap_remove(i);
+            }
+            _ => {}
+        }
+    }
+
+    // Nothing is denied until the denylist is created
+    #[throws(ProgramError)]
+    pub fn verify_allowed(
+        program_id: &Pubkey,
+        denylist_account: &AccountInfo,
+        nft_mint: &Pubkey,
+        collection: &Pubkey,
+    ) {
+        if denylist_account.key != &Self::get_address(program_id) {
+            throw!(liqzError::DenylistAddressNotCorrect);
+        }
+
+        if !utils::is_program_account(denylist_account, program_id) {
+            return;
+        }
+
+        let denylist: ProgramAccount<Denylist> = ProgramAccount::try_from(denylist_account)?;
+
+        if denylist
+            .assets
+            .iter()
+            .any(|asset| asset == nft_mint || asset == collection)
+        {
+            throw!(liqzError::AssetDenied);
+        }
+    }
+
+    // The denylist is a program derived account managed by the pool owner
+    // The address of the account is computed as follow:
+    // address = find_program_address([Denylist::SEED], program_id)
+    pub fn get_address(program_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(&[Self::SEED, &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::DenylistAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn denylist_address_funded_by_someone_else_denies_nothing_until_created() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let nft_mint = Pubkey::new_unique();
+        let denylist_account = test_utils::funded_address(Denylist::get_address(&program_id));
+
+        Denylist::verify_allowed(&program_id, &denylist_account, &nft_mint, &nft_mint).unwrap();
+
+        Denylist::ensure(
+            &program_id,
+            &test_utils::wallet(),
+            &denylist_account,
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+
+        let denylist: ProgramAccount<Denylist> =
+            ProgramAccount::try_from(&denylist_account).unwrap();
+        assert!(denylist.assets.is_empty());
+    }
+}
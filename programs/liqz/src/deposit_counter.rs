@@ -0,0 +1,112 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DepositCounter, DerivedAccountIdentifier, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for DepositCounter {
+    const SEED: &'static [u8] = b"liqzDepositCounter";
+}
+
+impl DepositCounter {
+    // Creates the counter on the first deposit of the wallet
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        borrower_wallet: &Pubkey,
+        counter_account: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, borrower_wallet);
+
+        Self::verify_address(program_id, borrower_wallet, bump, counter_account.key)?;
+
+        if !utils::is_program_account(counter_account, program_id) {
+            let instance = DepositCounter { next_id: 0 };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &borrower_wallet.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                payer,
+                seeds_with_bump,
+                counter_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = counter_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(counter_account)?
+    }
+
+    // The ids of a borrower are 0, 1, 2, ... in the order of the deposits
+    pub fn take_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap();
+        id
+    }
+
+    // An program derived account that assigns the ids of the deposits of a borrower
+    // The address of the account is computed as follow:
+    // address = find_program_address([DepositCounter::SEED, borrower_wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, borrower: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, borrower).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, borrower: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &borrower.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, borrower: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &borrower.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::DepositCounterAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn counter_funded_by_someone_else_still_assigns_ids() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let counter_account =
+            test_utils::funded_address(DepositCounter::get_address(&program_id, &borrower));
+
+        let mut counter = DepositCounter::ensure(
+            &program_id,
+            &borrower,
+            &counter_account,
+            &test_utils::wallet(),
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+        assert_eq!(counter.take_id(), 0);
+        assert_eq!(counter.take_id(), 1);
+    }
+}
@@ -0,0 +1,270 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, Exposure, LoanActiveState, NFTPool, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for Exposure {
+    const SEED: &'static [u8] = b"liqzExposure";
+}
+
+impl Exposure {
+    // The exposure accounts of collections and borrowers are kept apart, a creator can be a borrower too
+    pub const COLLECTION: &'static [u8] = b"collection";
+    pub const BORROWER: &'static [u8] = b"borrower";
+
+    #[throws(ProgramError)]
+    fn ensure<'info>(
+        program_id: &Pubkey,
+        kind: &[u8],
+        key: &Pubkey,
+        exposure_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, kind, key);
+
+        Self::verify_address(program_id, kind, key, bump, exposure_account.key)?;
+
+        if !utils::is_program_account(exposure_account, program_id) {
+            let instance = Exposure {
+                outstanding_principal: 0,
+                open_loans: 0,
+            };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, kind, &key.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                funder,
+                seeds_with_bump,
+                exposure_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = exposure_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(exposure_account)?
+    }
+
+    // Records a new loan against the collection and the borrower, rejecting it if a cap of the pool is exceeded
+    #[throws(ProgramError)]
+    pub fn open_loan<'info>(
+        program_id: &Pubkey,
+        pool: &NFTPool,
+        loan: &LoanActiveState,
+        collection_exposure_account: &AccountInfo<'info>,
+        borrower_exposure_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let mut collection_exposure = Self::ensure(
+            program_id,
+            Self::COLLECTION,
+            &loan.collection,
+            collection_exposure_account,
+            funder,
+            rent,
+            system,
+        )?;
+
+        collection_exposure.open(loan.borrowed_amount);
+
+        if collection_exposure.outstanding_principal > pool.max_collection_principal {
+            throw!(liqzError::CollectionCapExceeded);
+        }
+
+        let mut borrower_exposure = Self::ensure(
+            program_id,
+            Self::BORROWER,
+            &loan.borrower,
+            borrower_exposure_account,
+            funder,
+            rent,
+            system,
+        )?;
+
+        borrower_exposure.open(loan.borrowed_amount);
+
+        if borrower_exposure.outstanding_principal > pool.max_borrower_principal
+            || borrower_exposure.open_loans > pool.max_borrower_open_loans
+        {
+            throw!(liqzError::BorrowerCapExceeded);
+        }
+
+        collection_exposure.exit(program_id)?;
+        borrower_exposure.exit(program_id)?;
+    }
+
+    // Releases a loan that is repaid or liquidated
+    #[throws(ProgramError)]
+    pub fn close_loan<'info>(
+        program_id: &Pubkey,
+        loan: &LoanActiveState,
+        collection_exposure_account: &AccountInfo<'info>,
+        borrower_exposure_account: &AccountInfo<'info>,
+    ) {
+        Self::release(
+            program_id,
+            Self::COLLECTION,
+            &loan.collection,
+            loan.borrowed_amount,
+            collection_exposure_account,
+        )?;
+        Self::release(
+            program_id,
+            Self::BORROWER,
+            &loan.borrower,
+            loan.borrowed_amount,
+            borrower_exposure_account,
+        )?;
+    }
+
+    // Loans opened before the caps were added have no exposure account, there is nothing to release then
+    #[throws(ProgramError)]
+    fn release<'info>(
+        program_id: &Pubkey,
+        kind: &[u8],
+        key: &Pubkey,
+        principal: u64,
+        exposure_account: &AccountInfo<'info>,
+    ) {
+        if exposure_account.key != &Self::get_address(program_id, kind, key) {
+            throw!(liqzError::ExposureAddressNotCorrect);
+        }
+
+        if !utils::is_program_account(exposure_account, program_id) {
+            return;
+        }
+
+        let mut exposure: ProgramAccount<Self> = ProgramAccount::try_from(exposure_account)?;
+        exposure.close(principal);
+        exposure.exit(program_id)?;
+    }
+
+    fn open(&mut self, principal: u64) {
+        self.outstanding_principal = self.outstanding_principal.checked_add(principal).unwrap();
+        self.open_loans = self.open_loans.checked_add(1).unwrap();
+    }
+
+    // A legacy loan may be released from an account that never counted it
+    fn close(&mut self, principal: u64) {
+        self.outstanding_principal = self.outstanding_principal.saturating_sub(principal);
+        self.open_loans = self.open_loans.saturating_sub(1);
+    }
+
+    // An program derived account that keeps the outstanding loans of a collection or a borrower
+    // The address of the account is computed as follow:
+    // address = find_program_address([Exposure::SEED, Exposure::COLLECTION or Exposure::BORROWER, key], program_id)
+    // where the key is the collection or the borrower wallet address
+    pub fn get_address(program_id: &Pubkey, kind: &[u8], key: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, kind, key).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        kind: &[u8],
+        key: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, kind, &key.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        kind: &[u8],
+        key: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, kind, &key.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::ExposureAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn open_and_close_balance_out() {
+        let mut exposure = Exposure {
+            outstanding_principal: 0,
+            open_loans: 0,
+        };
+        exposure.open(100);
+        exposure.open(50);
+        exposure.close(100);
+
+        assert_eq!(exposure.outstanding_principal, 50);
+        assert_eq!(exposure.open_loans, 1);
+    }
+
+    #[test]
+    fn closing_an_uncounted_loan_does_not_underflow() {
+        let mut exposure = Exposure {
+            outstanding_principal: 50,
+            open_loans: 1,
+        };
+        exposure.close(100);
+        exposure.close(100);
+
+        assert_eq!(exposure.outstanding_principal, 0);
+        assert_eq!(exposure.open_loans, 0);
+    }
+
+    #[test]
+    fn exposure_account_funded_by_someone_else_is_released_and_created() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+        let exposure_account = test_utils::funded_address(Exposure::get_address(
+            &program_id,
+            Exposure::COLLECTION,
+            &collection,
+        ));
+
+        // a loan opened before the caps has nothing to release
+        Exposure::release(
+            &program_id,
+            Exposure::COLLECTION,
+            &collection,
+            100,
+            &exposure_account,
+        )
+        .unwrap();
+
+        let exposure = Exposure::ensure(
+            &program_id,
+            Exposure::COLLECTION,
+            &collection,
+            &exposure_account,
+            &test_utils::wallet(),
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+        assert_eq!(exposure.open_loans, 0);
+    }
+}
@@ -0,0 +1,315 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
+
+use crate::{
+    utils, DerivedAccountIdentifier, LizStake, LoanSettings, NFTPool, Proposal, ProposalVote,
+    liqzError,
+};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for Proposal {
+    const SEED: &'static [u8] = b"liqzProposal";
+}
+
+impl Proposal {
+    // The total staked LIZ is snapshotted here, only LIZ staked before this moment can vote
+    #[throws(ProgramError)]
+    pub fn create<'info>(
+        program_id: &Pubkey,
+        proposal_id: &Pubkey,
+        pool: &NFTPool,
+        settings: LoanSettings,
+        proposer: &AccountInfo<'info>,
+        proposal_account: &AccountInfo<'info>,
+        now: UnixTimestamp,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, proposal_id);
+
+        Self::verify_address(program_id, proposal_id, bump, proposal_account.key)?;
+
+        if utils::is_account_allocated(proposal_account) {
+            throw!(liqzError::ProposalAlreadyExists);
+        }
+
+        if pool.liz_staked == 0 {
+            throw!(liqzError::NothingStaked);
+        }
+
+        let instance = Proposal {
+            proposer: *proposer.key,
+            settings,
+            created_at: now,
+            voting_ends_at: now.checked_add(pool.proposal_voting_period).unwrap(),
+            staked_snapshot: pool.liz_staked,
+            votes_for: 0,
+            votes_against: 0,
+            executed: false,
+        };
+
+        // size the account with every setting changed
+        let largest_instance = Proposal {
+            settings: LoanSettings::largest(),
+            ..instance
+        };
+
+        let acc_size = 8 + largest_instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &proposal_id.to_bytes(), &[bump]];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            proposer,
+            seeds_with_bump,
+            proposal_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        {
+            let mut data = proposal_account.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        ProgramAccount::try_from(proposal_account)?
+    }
+
+    // The weight of a vote is the LIZ staked by the voter before the proposal was created
+    #[throws(liqzError)]
+    pub fn vote(&mut self, stake: &LizStake, support: bool, now: UnixTimestamp) -> u64 {
+        if now > self.voting_ends_at {
+            throw!(liqzError::VotingEnded);
+        }
+
+        // stake added or removed after the proposal was created does not change the weight
+        let weight = stake
+            .amount_before(self.created_at)
+            .ok_or(liqzError::StakedAfterProposal)?;
+
+        if weight == 0 {
+            throw!(liqzError::NothingStaked);
+        }
+
+        if support {
+            self.votes_for = self.votes_for.checked_add(weight).unwrap();
+        } else {
+            self.votes_against = self.votes_against.checked_add(weight).unwrap();
+        }
+
+        weight
+    }
+
+    // A proposal passes when enough of the snapshotted stake voted and enough of the votes are for it
+    #[throws(liqzError)]
+    pub fn execute(&mut self, pool: &NFTPool, now: UnixTimestamp) -> LoanSettings {
+        if now <= self.voting_ends_at {
+            throw!(liqzError::VotingNotEnded);
+        }
+
+        if self.executed {
+            throw!(liqzError::ProposalAlreadyExecuted);
+        }
+
+        let votes = self.votes_for.checked_add(self.votes_against).unwrap();
+
+        if (votes as u128) * 10000 < (self.staked_snapshot as u128) * (pool.proposal_quorum as u128)
+        {
+            throw!(liqzError::QuorumNotReached);
+        }
+
+        if (self.votes_for as u128) * 10000 <= (votes as u128) * (pool.proposal_threshold as u128) {
+            throw!(liqzError::ProposalRejected);
+        }
+
+        self.executed = true;
+        self.settings
+    }
+
+    // An program derived account that holds a proposal to change the loan settings
+    // The address of the account is computed as follow:
+    // address = find_program_address([Proposal::SEED, proposal_id], program_id)
+    pub fn get_address(program_id: &Pubkey, proposal_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, proposal_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, proposal_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &proposal_id.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, proposal_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &proposal_id.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::ProposalAddressNotCorrect);
+        }
+    }
+}
+
+impl DerivedAccountIdentifier for ProposalVote {
+    const SEED: &'static [u8] = b"liqzProposalVote";
+}
+
+impl ProposalVote {
+    // Creates the vote account, so that a wallet votes only once on a proposal
+    #[throws(ProgramError)]
+    pub fn record<'info>(
+        program_id: &Pubkey,
+        proposal: &Pubkey,
+        voter: &AccountInfo<'info>,
+        vote_account: &AccountInfo<'info>,
+        weight: u64,
+        support: bool,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, proposal, voter.key);
+
+        Self::verify_address(program_id, proposal, voter.key, bump, vote_account.key)?;
+
+        if utils::is_account_allocated(vote_account) {
+            throw!(liqzError::AlreadyVoted);
+        }
+
+        let instance = ProposalVote { weight, support };
+
+        let acc_size = 8 + instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[
+            Self::SEED,
+            &proposal.to_bytes(),
+            &voter.key.to_bytes(),
+            &[bump],
+        ];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            voter,
+            seeds_with_bump,
+            vote_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        let mut data = vote_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // An program derived account that records the vote of a wallet on a proposal
+    // The address of the account is computed as follow:
+    // address = find_program_address([ProposalVote::SEED, proposal_address, voter_wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, proposal: &Pubkey, voter: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, proposal, voter).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        proposal: &Pubkey,
+        voter: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED, &proposal.to_bytes(), &voter.to_bytes()],
+            program_id,
+        )
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        proposal: &Pubkey,
+        voter: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &proposal.to_bytes(), &voter.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::ProposalVoteAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(created_at: UnixTimestamp) -> Proposal {
+        Proposal {
+            proposer: Pubkey::default(),
+            settings: LoanSettings::largest(),
+            created_at,
+            voting_ends_at: created_at + 100,
+            staked_snapshot: 1000,
+            votes_for: 0,
+            votes_against: 0,
+            executed: false,
+        }
+    }
+
+    fn stake(amount: u64, at: UnixTimestamp) -> LizStake {
+        let mut stake = LizStake {
+            amount: 0,
+            changed_at: 0,
+            previous_amount: 0,
+            previous_changed_at: 0,
+            unstaking: 0,
+            unstake_at: 0,
+        };
+        stake.stake(amount, at);
+        stake
+    }
+
+    #[test]
+    fn vote_weight_is_the_stake_at_creation() {
+        let mut proposal = proposal(50);
+        let mut voter = stake(100, 10);
+        voter.stake(400, 60);
+
+        assert_eq!(proposal.vote(&voter, true, 70).unwrap(), 100);
+        assert_eq!(proposal.votes_for, 100);
+    }
+
+    #[test]
+    fn stake_unstaked_after_creation_still_counts_once() {
+        let mut proposal = proposal(50);
+        let mut voter = stake(100, 10);
+        voter.request_unstake(100, 60, 1000).unwrap();
+
+        assert_eq!(proposal.vote(&voter, false, 70).unwrap(), 100);
+        assert_eq!(proposal.votes_against, 100);
+    }
+
+    #[test]
+    fn stake_added_after_creation_cannot_vote() {
+        let mut proposal = proposal(50);
+        let voter = stake(100, 60);
+
+        assert!(proposal.vote(&voter, true, 70).is_err());
+    }
+
+    #[test]
+    fn vote_after_the_voting_period_fails() {
+        let mut proposal = proposal(50);
+        let voter = stake(100, 10);
+
+        assert!(proposal.vote(&voter, true, 151).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{DerivedAccountIdentifier, PoolTokenAccount, liqzError};
+
+// The insurance fund is a token account per mint that keeps a cut of the fees.
+// It is owned by the pool and pays lenders back when a liquidated NFT is worth less than the principal.
+pub struct InsuranceFund;
+
+impl DerivedAccountIdentifier for InsuranceFund {
+    const SEED: &'static [u8] = b"liqzInsuranceFund";
+}
+
+impl PoolTokenAccount for InsuranceFund {
+    const ADDRESS_NOT_CORRECT: liqzError = liqzError::InsuranceFundAddressNotCorrect;
+}
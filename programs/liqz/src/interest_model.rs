@@ -0,0 +1,203 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::convert::TryInto;
+
+static SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+static BP: u128 = 10000;
+
+// 18 decimals fixed point
+static WAD: u128 = 1_000_000_000_000_000_000;
+
+// Compounding stops here, the interest on any principal is already more than a u64 can hold
+static MAX_GROWTH: u128 = u64::MAX as u128 * WAD;
+
+// All rates are in bp per year. Every division rounds up so that the lender never receives less than the rate.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum InterestModel {
+    SimpleApr(u64),     // interest grows linearly with time
+    CompoundedApr(u64), // interest compounds every second
+    // The rate follows the utilization of the pooled liquidity, it grows slowly up to the kink and fast after it.
+    // Resolved into a SimpleApr when the loan starts.
+    Kinked {
+        base_rate: u64,
+        slope: u64,      // added rate at the kink
+        jump_slope: u64, // added rate from the kink to full utilization
+        kink: u64,       // utilization in bp
+    },
+}
+
+impl InterestModel {
+    // Asks are priced in bp per day, the loan charges the same rate as a simple APR
+    pub fn from_daily_rate(bp_per_day: u64) -> InterestModel {
+        InterestModel::SimpleApr(bp_per_day.checked_mul(365).unwrap())
+    }
+
+    // The model a loan keeps for its whole life given the current utilization in bp
+    pub fn at_utilization(&self, utilization: u64) -> InterestModel {
+        match *self {
+            InterestModel::Kinked {
+                base_rate,
+                slope,
+                jump_slope,
+                kink,
+            } => {
+                let utilization = (utilization as u128).min(BP);
+                let kink = (kink as u128).min(BP);
+
+                let mut rate = base_rate as u128;
+                if kink > 0 {
+                    rate += div_ceil(slope as u128 * utilization.min(kink), kink);
+                }
+                if utilization > kink {
+                    rate += div_ceil(jump_slope as u128 * (utilization - kink), BP - kink);
+                }
+
+                InterestModel::SimpleApr(rate.try_into().unwrap())
+            }
+            model => model,
+        }
+    }
+
+    pub fn interest(&self, principal: u64, duration: i64) -> u64 {
+        let principal = principal as u128;
+        let duration: u128 = duration.max(0).try_into().unwrap();
+
+        // Loans never keep a kinked model, charge its base rate if it is used directly
+        let interest = match self.at_utilization(0) {
+            InterestModel::SimpleApr(apr) => div_ceil(
+                principal
+                    .checked_mul(apr as u128)
+                    .unwrap()
+                    .checked_mul(duration)
+                    .unwrap(),
+                BP * SECONDS_PER_YEAR,
+            ),
+            InterestModel::CompoundedApr(apr) => {
+                let rate_per_second = div_ceil(WAD * apr as u128, BP * SECONDS_PER_YEAR);
+                let growth = wad_pow_ceil(WAD + rate_per_second, duration);
+                div_ceil(principal.saturating_mul(growth - WAD), WAD)
+            }
+            InterestModel::Kinked { .. } => unreachable!(),
+        };
+
+        // a loan long enough to overflow can never be repaid, it is left to liquidation
+        interest.min(u64::MAX as u128) as u64
+    }
+
+    // Borsh does not support vary size structure, accounts holding a model are sized for this one
+    pub fn largest() -> InterestModel {
+        InterestModel::Kinked {
+            base_rate: 0,
+            slope: 0,
+            jump_slope: 0,
+            kink: 0,
+        }
+    }
+}
+
+fn div_ceil(a: u128, b: u128) -> u128 {
+    if a == 0 {
+        0
+    } else {
+        (a - 1) / b + 1
+    }
+}
+
+// a * b / WAD = a1 * b + a0 * b1 + a0 * b0 / WAD with a = a1 * WAD + a0 and b = b1 * WAD + b0,
+// only the last term is rounded and the product overflows only if the result does
+fn wad_mul_ceil(a: u128, b: u128) -> u128 {
+    let (a1, a0) = (a / WAD, a % WAD);
+    let (b1, b0) = (b / WAD, b % WAD);
+
+    a1.checked_mul(b)
+        .and_then(|x| x.checked_add(a0.checked_mul(b1)?))
+        .and_then(|x| x.checked_add(div_ceil(a0 * b0, WAD)))
+        .map_or(MAX_GROWTH, |x| x.min(MAX_GROWTH))
+}
+
+// base^exp by squaring, rounding up at every step, capped at MAX_GROWTH
+fn wad_pow_ceil(mut base: u128, mut exp: u128) -> u128 {
+    let mut result = WAD;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = wad_mul_ceil(result, base);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = wad_mul_ceil(base, base);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DAI: u64 = 1_000_000_000;
+
+    #[test]
+    fn wad_pow_ceil_matches_small_powers() {
+        assert_eq!(wad_pow_ceil(2 * WAD, 0), WAD);
+        assert_eq!(wad_pow_ceil(2 * WAD, 10), 1024 * WAD);
+        assert_eq!(wad_pow_ceil(WAD + WAD / 2, 2), 2 * WAD + WAD / 4);
+    }
+
+    #[test]
+    fn wad_pow_ceil_rounds_up() {
+        // 1.000...001 squared is 1.000...002 plus a fraction of a wei
+        assert_eq!(wad_pow_ceil(WAD + 1, 2), WAD + 3);
+    }
+
+    #[test]
+    fn wad_pow_ceil_caps_instead_of_overflowing() {
+        assert_eq!(wad_pow_ceil(2 * WAD, 200), MAX_GROWTH);
+        assert_eq!(wad_pow_ceil(u128::MAX, 3), MAX_GROWTH);
+    }
+
+    #[test]
+    fn simple_apr_interest() {
+        let model = InterestModel::SimpleApr(1000);
+        let year = SECONDS_PER_YEAR as i64;
+        assert_eq!(model.interest(100 * DAI, year), 10 * DAI);
+        assert_eq!(model.interest(100 * DAI, 0), 0);
+        assert_eq!(model.interest(100 * DAI, -year), 0);
+    }
+
+    #[test]
+    fn compounded_apr_interest_exceeds_simple_apr() {
+        let year = SECONDS_PER_YEAR as i64;
+        let simple = InterestModel::SimpleApr(1000).interest(100 * DAI, year);
+        let compounded = InterestModel::CompoundedApr(1000).interest(100 * DAI, year);
+        // e^0.1 - 1 = 10.517%
+        assert!(compounded > simple);
+        assert!(compounded < 10_600_000_000);
+    }
+
+    #[test]
+    fn long_compounded_loan_saturates() {
+        let model = InterestModel::CompoundedApr(1_000_000);
+        assert_eq!(model.interest(100 * DAI, i64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn kinked_rate_follows_utilization() {
+        let model = InterestModel::Kinked {
+            base_rate: 1000,
+            slope: 2000,
+            jump_slope: 10000,
+            kink: 8000,
+        };
+        assert_eq!(model.at_utilization(0), InterestModel::SimpleApr(1000));
+        assert_eq!(model.at_utilization(4000), InterestModel::SimpleApr(2000));
+        assert_eq!(model.at_utilization(8000), InterestModel::SimpleApr(3000));
+        assert_eq!(model.at_utilization(10000), InterestModel::SimpleApr(13000));
+    }
+
+    #[test]
+    fn daily_rate_is_converted_to_apr() {
+        assert_eq!(
+            InterestModel::from_daily_rate(10),
+            InterestModel::SimpleApr(3650)
+        );
+    }
+}
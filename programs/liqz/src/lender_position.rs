@@ -0,0 +1,136 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, NFTPool, liqzError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use fehler::{throw, throws};
+
+// The lender position is an NFT minted to the lender when a loan starts.
+// Whoever holds it is entitled to the repayment or to the collateral of that loan.
+pub struct LenderPosition;
+
+impl DerivedAccountIdentifier for LenderPosition {
+    const SEED: &'static [u8] = b"liqzLenderPosition";
+}
+
+impl LenderPosition {
+    #[throws(ProgramError)]
+    pub fn create_mint<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        deposit_account: &Pubkey,
+        position_mint: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) -> CpiAccount<'info, Mint> {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit_account);
+
+        Self::verify_address(program_id, deposit_account, bump, position_mint.key)?;
+
+        // One deposit can only be borrowed against once
+        if utils::is_account_allocated(position_mint) {
+            throw!(liqzError::LenderPositionAlreadyExist);
+        }
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &deposit_account.to_bytes(), &[bump]];
+
+        utils::create_derived_mint_with_seed(
+            funder,
+            seeds_with_bump,
+            position_mint,
+            &pool.to_account_info(),
+            0,
+            rent,
+            spl_program,
+            system_program,
+        )?;
+
+        CpiAccount::try_from(position_mint)?
+    }
+
+    // Mints the position to the lender and drops the mint authority so that the position stays unique
+    #[throws(ProgramError)]
+    pub fn issue<'info>(
+        pool: &ProgramAccount<'info, NFTPool>,
+        position_mint: &CpiAccount<'info, Mint>,
+        lender_position_account: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+    ) {
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::MintTo {
+                    mint: position_mint.to_account_info(),
+                    to: lender_position_account.clone(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        anchor_spl::token::set_authority(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::SetAuthority {
+                    current_authority: pool.to_account_info(),
+                    account_or_mint: position_mint.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            spl_token::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+    }
+
+    // Make sure the token account holds the position NFT of the deposit and is owned by the holder
+    #[throws(ProgramError)]
+    pub fn verify_holder(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+        position_account: &CpiAccount<TokenAccount>,
+        holder: &Pubkey,
+    ) {
+        if position_account.mint != Self::get_address(program_id, deposit_account) {
+            throw!(liqzError::LenderPositionAddressNotCorrect);
+        }
+
+        if position_account.amount != 1 || &position_account.owner != holder {
+            throw!(liqzError::NotLenderPositionHolder);
+        }
+    }
+
+    // An program derived mint that represents the lender side of a loan
+    // The address of the mint is computed as follow:
+    // address = find_program_address([LenderPosition::SEED, deposit_account_address], program_id)
+    // the mint authority is the pool and is dropped right after the single token is minted
+    pub fn get_address(program_id: &Pubkey, deposit_account: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, deposit_account).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &deposit_account.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &deposit_account.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::LenderPositionAddressNotCorrect);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, InterestModel, LendingVault, liqzError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use fehler::{throw, throws};
+use std::convert::TryInto;
+
+static LP_MINT_SEED: &[u8] = b"liqzLendingVaultLP";
+
+impl DerivedAccountIdentifier for LendingVault {
+    const SEED: &'static [u8] = b"liqzLendingVault";
+}
+
+impl LendingVault {
+    #[throws(ProgramError)]
+    pub fn new_checked<'info>(
+        program_id: &Pubkey,
+        vault: &AccountInfo<'info>,
+        lp_mint: &AccountInfo<'info>,
+        dai_mint: &CpiAccount<'info, Mint>,
+        funder: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id);
+        Self::verify_address(program_id, bump, vault.key)?;
+
+        let (_, lp_bump) = Self::get_lp_mint_address_with_bump(program_id);
+        Self::verify_lp_mint_address(program_id, lp_bump, lp_mint.key)?;
+
+        let instance = Self {
+            bump_seed: bump,
+            lp_mint: *lp_mint.key,
+            // 50%
+            ltv: 5000,
+            // 10% up to 30% at 80% utilization, 130% at full utilization
+            interest_model: InterestModel::Kinked {
+                base_rate: 1000,
+                slope: 2000,
+                jump_slope: 10000,
+                kink: 8000,
+            },
+            available: 0,
+            outstanding_principal: 0,
+            nft_held: 0,
+            nft_value: 0,
+        };
+
+        // size the account so that any interest model can be set later
+        let largest_instance = Self {
+            interest_model: InterestModel::largest(),
+            ..instance
+        };
+
+        let acc_size = 8 + largest_instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            funder,
+            &[Self::SEED, &[bump]],
+            vault,
+            acc_size,
+            rent,
+            system_program,
+        )?;
+
+        {
+            let mut data = vault.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        // LP shares use the same decimals as DAI so that the initial share price is 1
+        utils::create_derived_mint_with_seed(
+            funder,
+            &[LP_MINT_SEED, &[lp_bump]],
+            lp_mint,
+            vault,
+            dai_mint.decimals,
+            rent,
+            spl_program,
+            system_program,
+        )?;
+
+        ProgramAccount::try_from(vault)?
+    }
+
+    // DAI owned by the vault, either sitting in the vault or lent out.
+    // NFTs taken from liquidated loans count at the appraised value booked then until they are sold.
+    pub fn total_assets(&self) -> u64 {
+        self.available
+            .checked_add(self.outstanding_principal)
+            .unwrap()
+            .checked_add(self.nft_value)
+            .unwrap()
+    }
+
+    // Rounds down so that depositors never get more than they put in
+    #[throws(liqzError)]
+    pub fn shares_for_deposit(&self, amount: u64, lp_supply: u64) -> u64 {
+        if lp_supply == 0 {
+            return amount;
+        }
+
+        // the shares left are worth nothing, new DAI would be split with their holders
+        if self.total_assets() == 0 {
+            throw!(liqzError::VaultInsolvent);
+        }
+
+        (amount as u128)
+            .checked_mul(lp_supply as u128)
+            .unwrap()
+            .checked_div(self.total_assets() as u128)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    // Rounds down so that withdrawers never get more than they own
+    pub fn amount_for_shares(&self, shares: u64, lp_supply: u64) -> u64 {
+        if lp_supply == 0 {
+            return 0;
+        }
+
+        (shares as u128)
+            .checked_mul(self.total_assets() as u128)
+            .unwrap()
+            .checked_div(lp_supply as u128)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    // Share of the vault assets that is lent out after lending `amount` more, in bp
+    pub fn utilization_after(&self, amount: u64) -> u64 {
+        let total_assets = self.total_assets();
+        if total_assets == 0 {
+            return 0;
+        }
+
+        (self.outstanding_principal as u128)
+            .checked_add(amount as u128)
+            .unwrap()
+            .checked_mul(10000)
+            .unwrap()
+            .checked_div(total_assets as u128)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    pub fn max_borrow(&self, appraised_value: u64) -> u64 {
+        appraised_value
+            .checked_mul(self.ltv)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap()
+    }
+
+    pub fn deposit(&mut self, amount: u64) {
+        self.available = self.available.checked_add(amount).unwrap();
+    }
+
+    #[throws(liqzError)]
+    pub fn withdraw(&mut self, amount: u64) {
+        if amount > self.available {
+            throw!(liqzError::VaultInsufficientLiquidity);
+        }
+        self.available -= amount;
+    }
+
+    #[throws(liqzError)]
+    pub fn lend(&mut self, amount: u64) {
+        if amount > self.available {
+            throw!(liqzError::VaultInsufficientLiquidity);
+        }
+        self.available -= amount;
+        self.outstanding_principal = self.outstanding_principal.checked_add(amount).unwrap();
+    }
+
+    // The interest goes to the vault and raises the share price
+    pub fn settle(&mut self, principal: u64, interest: u64) {
+        self.outstanding_principal = self.outstanding_principal.checked_sub(principal).unwrap();
+        self.available = self
+            .available
+            .checked_add(principal)
+            .unwrap()
+            .checked_add(interest)
+            .unwrap();
+    }
+
+    // The principal is lost and the vault gets the NFT, valued at its appraisal, instead
+    pub fn write_off(&mut self, principal: u64, appraised_value: u64) {
+        self.outstanding_principal = self.outstanding_principal.checked_sub(principal).unwrap();
+        self.nft_held = self.nft_held.checked_add(1).unwrap();
+        self.nft_value = self.nft_value.checked_add(appraised_value).unwrap();
+    }
+
+    // The NFT is replaced by the DAI it was sold for, the appraisal may have changed since it was booked
+    #[throws(liqzError)]
+    pub fn sell_nft(&mut self, price: u64, booked_value: u64) {
+        if self.nft_held == 0 {
+            throw!(liqzError::VaultNFTNotForSale);
+        }
+        self.nft_held -= 1;
+        self.nft_value = self.nft_value.checked_sub(booked_value).unwrap();
+        self.available = self.available.checked_add(price).unwrap();
+    }
+
+    // The contract account should have address find_program_address(&[LendingVault::SEED], program_id)
+    pub fn get_address(program_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(&[Self::SEED, &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::LendingVaultAddressNotCorrect);
+        }
+    }
+
+    // The LP mint should have address find_program_address(&[LP_MINT_SEED], program_id)
+    pub fn get_lp_mint_address(program_id: &Pubkey) -> Pubkey {
+        Self::get_lp_mint_address_with_bump(program_id).0
+    }
+
+    pub(crate) fn get_lp_mint_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LP_MINT_SEED], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_lp_mint_address(program_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(&[LP_MINT_SEED, &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::LendingVaultAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> LendingVault {
+        LendingVault {
+            bump_seed: 255,
+            lp_mint: Pubkey::new_unique(),
+            ltv: 5000,
+            interest_model: InterestModel::SimpleApr(1000),
+            available: 0,
+            outstanding_principal: 0,
+            nft_held: 0,
+            nft_value: 0,
+        }
+    }
+
+    #[test]
+    fn first_deposit_gets_one_share_per_dai() {
+        let vault = vault();
+        assert_eq!(vault.shares_for_deposit(1000, 0).unwrap(), 1000);
+        assert_eq!(vault.amount_for_shares(1000, 0), 0);
+    }
+
+    #[test]
+    fn shares_and_amounts_round_down() {
+        let mut vault = vault();
+        vault.deposit(1000);
+        vault.lend(600).unwrap();
+        vault.settle(600, 200); // 1200 DAI for 1000 shares
+
+        assert_eq!(vault.shares_for_deposit(100, 1000).unwrap(), 83);
+        assert_eq!(vault.amount_for_shares(83, 1000), 99);
+        assert_eq!(vault.amount_for_shares(1000, 1000), 1200);
+    }
+
+    #[test]
+    fn deposit_into_an_insolvent_vault_fails() {
+        let mut vault = vault();
+        vault.deposit(1000);
+        vault.lend(1000).unwrap();
+        vault.write_off(1000, 0);
+
+        assert_eq!(vault.total_assets(), 0);
+        assert!(vault.shares_for_deposit(100, 1000).is_err());
+    }
+
+    #[test]
+    fn written_off_nft_counts_at_its_booked_value() {
+        let mut vault = vault();
+        vault.deposit(1000);
+        vault.lend(500).unwrap();
+        vault.write_off(500, 300);
+
+        assert_eq!(vault.outstanding_principal, 0);
+        assert_eq!(vault.nft_held, 1);
+        assert_eq!(vault.total_assets(), 800);
+    }
+
+    #[test]
+    fn sale_replaces_the_booked_value_by_the_price() {
+        let mut vault = vault();
+        vault.deposit(1000);
+        vault.lend(1000).unwrap();
+        vault.write_off(500, 300);
+        vault.write_off(500, 200);
+
+        // the appraisal of the first NFT went up since it was booked
+        vault.sell_nft(400, 300).unwrap();
+        assert_eq!(vault.nft_held, 1);
+        assert_eq!(vault.nft_value, 200);
+        assert_eq!(vault.total_assets(), 600);
+
+        vault.sell_nft(150, 200).unwrap();
+        assert_eq!(vault.nft_value, 0);
+        assert_eq!(vault.total_assets(), 550);
+        assert!(vault.sell_nft(100, 0).is_err());
+    }
+}
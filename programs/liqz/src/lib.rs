@@ -1,16 +1,57 @@
+mod credential;
+mod denylist;
+mod deposit_counter;
+mod exposure;
+mod governance;
+mod insurance_fund;
+mod interest_model;
+mod lender_position;
+mod lending_vault;
+mod liz_stake;
+mod loan_offer;
+mod multisig;
+mod nft_appraisal;
+mod nft_ask;
 mod nft_bid;
 mod nft_deposit;
 mod nft_pool;
+mod pool_token_account;
+mod referrer;
+mod reputation;
+#[cfg(test)]
+mod test_utils;
+mod treasury;
 mod utils;
-
-pub use nft_deposit::{DepositState, LoanActiveState, LoanRepayedState};
+mod vault_write_off;
+mod versioning;
+
+pub use insurance_fund::InsuranceFund;
+pub use interest_model::InterestModel;
+pub use lender_position::LenderPosition;
+pub use liz_stake::StakeTier;
+pub use loan_offer::{LoanOffer, OfferTarget};
+pub use multisig::AdminActionAccount;
+pub use nft_bid::{BidRestriction, LegacyNFTBid};
+pub use nft_deposit::{
+    DepositState, Installments, LegacyNFTDeposit, LoanActiveState, LoanLiquidatedState,
+    LoanRepayedState, LoanTerm, LoanTerms,
+};
+pub use nft_pool::{LegacyNFTPool, LoanSettings};
+pub use pool_token_account::PoolTokenAccount;
+pub use treasury::Treasury;
+pub use utils::{get_metadata_address, parse_verified_creator};
+pub use versioning::{Legacy, MigrationBuffer, Versioned, RESERVED_SPACE};
 
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, TokenAccount};
 use fehler::throw;
+use solana_program::program_option::COption;
 use solana_program::pubkey::Pubkey;
 use std::u64;
 
+// in bp, the share of the rent paid to whoever closes an expired bid
+pub const BID_CLOSE_BOUNTY_RATE: u64 = 1000;
+
 pub trait DerivedAccountIdentifier {
     const SEED: &'static [u8];
 }
@@ -19,6 +60,7 @@ pub trait DerivedAccountIdentifier {
 #[account]
 #[derive(Debug)]
 pub struct NFTPool {
+    pub version: u8, // layout version, see migrate_pool
     pub bump_seed: u8,
     pub owner: Pubkey,
     pub liz_mint: Pubkey,
@@ -27,25 +69,204 @@ pub struct NFTPool {
     pub incentive: u64,         // incentive amount when user mortgage their NFT
     pub max_loan_duration: i64, // max loan duration before liquidation, secs
     pub service_fee_rate: u64,  // in bp, one ten thousandth, fee rate charged by liqz
-    pub interest_rate: u64,     // in bp, one ten thousandth
-    pub mortgage_rate: u64,     // in bp, mortgage rate to calculate real borrow amount
+    pub interest_model: InterestModel,
+    pub mortgage_rate: u64, // in bp, mortgage rate to calculate real borrow amount
+    pub min_interest_duration: i64, // secs, interest is charged for at least this long
+    pub origination_fee: u64, // flat DAI fee charged when a loan starts
+    pub dai_fees_accrued: u64, // all the DAI fees ever collected into the treasury
+    pub referral_fee_rate: u64, // in bp of the fee, paid to each referrer of a loan
+    pub insurance_fee_rate: u64, // in bp of the fee, kept in the insurance fund
+    pub insurance_paid_out: u64, // all the DAI the insurance fund ever paid to lenders
+    pub stake_tiers: [StakeTier; 3], // LIZ stake tiers, a tier with min_stake 0 is disabled
+    pub max_mortgage_rate: u64, // in bp, cap of the mortgage rate boosted by staking and reputation
+    pub unstake_cooldown: i64, // secs, before unstaked LIZ can be withdrawn
+    pub liz_staked: u64,    // all the LIZ staked and not unstaking
+    pub proposal_voting_period: i64, // secs
+    pub proposal_quorum: u64, // in bp of the staked LIZ, votes needed for a proposal to pass
+    pub proposal_threshold: u64, // in bp of the votes, a proposal passes with more votes for it
+    pub max_collection_principal: u64, // DAI lent against one collection
+    pub max_borrower_principal: u64, // DAI lent to one borrower
+    pub max_borrower_open_loans: u64, // active loans of one borrower
+    pub credential_authority: Option<Pubkey>, // issues the credentials of a private pool, None for a public pool
+    pub reputation_min_repaid: u64, // loans repaid on time to get the reputation boost, 0 disables it
+    pub reputation_mortgage_boost: u64, // in bp, added to the mortgage rate of reputable borrowers
+    pub total_deposits: u64,        // all the NFTs ever deposited
+    pub active_loans: u64,          // loans neither repaid nor liquidated yet
+    pub outstanding_principal: u64, // DAI lent in the active loans
+    pub interest_paid: u64,         // all the DAI interest borrowers ever paid, fees included
+    pub liquidations: u64,          // loans liquidated after they expired
+    pub default_volume: u64,        // DAI principal of the liquidated loans
+    pub dai_locked: u64, // DAI in the pool owed to lenders, the margins of active loans and unclaimed repayments
+    pub reputation_min_principal: u64, // DAI, smaller loans repaid on time do not count towards the reputation
+    pub reputation_min_duration: i64, // secs, loans repaid sooner do not count towards the reputation
 }
 
 #[account]
 #[derive(Debug)]
 pub struct NFTBid {
-    pub price: u64, // DAI Price
+    pub version: u8, // layout version, see migrate_bid
+    pub price: u64,  // DAI Price
     pub qty: u64,
+    pub restriction: Option<BidRestriction>, // None if anyone can take the bid
+    pub expires_at: Option<i64>,             // None if the bid never expires
+    pub call_notice: Option<i64>, // secs, Some if the bid lends callable open-ended loans
+    pub installment_period: Option<i64>, // secs, Some if the interest is paid in installments
+    pub referrer: Option<Pubkey>, // who brought the lender
 }
 
 // One NFTDeposit corresponds to one token
 #[account]
 #[derive(Debug)]
 pub struct NFTDeposit {
-    deposit_id: Pubkey,
+    version: u8, // layout version, see migrate_deposit
+    deposit_id: u64,
     state: DepositState,
 }
 
+// Assigns the ids of the deposits of a borrower
+#[account]
+#[derive(Debug)]
+pub struct DepositCounter {
+    pub next_id: u64, // the id of the next deposit, also the count of the deposits so far
+}
+
+// The lending vault should have address find_program_address(&[seed], program_id)
+// Lenders deposit DAI into the vault for LP shares, borrowers borrow from the vault directly
+#[account]
+#[derive(Debug)]
+pub struct LendingVault {
+    pub bump_seed: u8,
+    pub lp_mint: Pubkey,
+    pub ltv: u64,                      // in bp, max loan amount to the appraised value
+    pub interest_model: InterestModel, // resolved with the utilization when a loan starts
+    pub available: u64,                // DAI in the vault that can be lent or withdrawn
+    pub outstanding_principal: u64,    // DAI lent out and not returned yet
+    pub nft_held: u64,                 // NFTs the vault received from liquidated loans
+    pub nft_value: u64,                // DAI value the NFTs held were booked at
+}
+
+#[account]
+#[derive(Debug)]
+pub struct NFTAppraisal {
+    pub value: u64, // DAI value of the NFT used by the lending vault
+}
+
+// The loan terms the borrower asks for a pending deposit, lenders can accept them as they are
+#[account]
+#[derive(Debug)]
+pub struct NFTAsk {
+    pub amount: u64,        // DAI amount, 0 if there is no ask
+    pub interest_rate: u64, // in bp per day, charged as the equivalent simple APR
+    pub duration: i64,      // loan duration, secs
+}
+
+#[account]
+#[derive(Debug)]
+pub struct VaultWriteOff {
+    pub booked_value: u64, // appraised DAI value of the NFT when the vault took it
+}
+
+#[account]
+#[derive(Debug)]
+pub struct LoanOfferNonce {
+    pub used_at: i64, // when the offer was taken or cancelled
+}
+
+#[account]
+#[derive(Debug)]
+pub struct Referrer {
+    pub loans_settled: u64, // referred loans that were repaid or liquidated
+    pub fees_earned: u64,   // DAI
+}
+
+#[account]
+#[derive(Debug)]
+pub struct LizStake {
+    pub amount: u64,              // staked LIZ, counts towards the stake tier
+    pub changed_at: i64,          // when the staked amount last changed
+    pub previous_amount: u64,     // staked before the last change, votes on older proposals use it
+    pub previous_changed_at: i64, // when the previous amount started
+    pub unstaking: u64,           // LIZ waiting for the cooldown
+    pub unstake_at: i64,          // when the unstaking LIZ can be withdrawn
+}
+
+// The outstanding loans of one collection or one borrower, capped by the pool
+#[account]
+#[derive(Debug)]
+pub struct Exposure {
+    pub outstanding_principal: u64, // DAI
+    pub open_loans: u64,
+}
+
+// The credit history of a borrower, kept across deposits
+#[account]
+#[derive(Debug)]
+pub struct Reputation {
+    pub loans_taken: u64,
+    pub repaid_on_time: u64,
+    pub repaid_late: u64,
+    pub liquidated: u64,
+    pub principal_borrowed: u64, // DAI
+}
+
+// Allows a wallet to bid and borrow in a private pool
+#[account]
+#[derive(Debug)]
+pub struct Credential {
+    pub authority: Pubkey, // who issued the credential
+    pub expires_at: Option<i64>,
+    pub revoked: bool,
+}
+
+// NFT mints and collections that cannot be deposited, borrowed against or bid on
+#[account]
+#[derive(Debug)]
+pub struct Denylist {
+    pub assets: Vec<Pubkey>,
+}
+
+// A change of the loan settings voted by the LIZ stakers
+#[account]
+#[derive(Debug)]
+pub struct Proposal {
+    pub proposer: Pubkey,
+    pub settings: LoanSettings,
+    pub created_at: i64,
+    pub voting_ends_at: i64,
+    pub staked_snapshot: u64, // the staked LIZ when the proposal was created
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub executed: bool,
+}
+
+// The pool admin as an M-of-N set of keys, the pool owner is set to this account
+#[account]
+#[derive(Debug)]
+pub struct Multisig {
+    pub members: Vec<Pubkey>,
+    pub threshold: u64, // approvals needed to execute an admin action
+    pub bump_seed: u8,
+    pub members_seqno: u32, // bumped when the members change
+}
+
+// An admin instruction proposed by a multisig member, executed with the multisig as the pool owner
+#[account]
+#[derive(Debug)]
+pub struct AdminAction {
+    pub accounts: Vec<AdminActionAccount>,
+    pub data: Vec<u8>,
+    pub approvals: Vec<bool>, // in the order of the multisig members
+    pub members_seqno: u32,   // the action is dropped if the members change
+    pub executed: bool,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct ProposalVote {
+    pub weight: u64, // staked LIZ
+    pub support: bool,
+}
+
 #[program]
 pub mod liqz {
     use super::*;
@@ -62,6 +283,10 @@ pub mod liqz {
             pool_liz_account,
             pool_tai_account,
             pool_dai_account,
+            treasury_dai_account,
+            insurance_dai_account,
+            denylist_account,
+            migration_buffer,
 
             ata_program,
             spl_program,
@@ -69,6 +294,9 @@ pub mod liqz {
             rent,
         } = ctx.accounts;
 
+        // a pool closed by migrate_pool is only restored by its owner
+        MigrationBuffer::verify_not_stashed(ctx.program_id, pool.key, migration_buffer)?;
+
         let pool = NFTPool::new_checked(
             ctx.program_id,
             pool,
@@ -98,6 +326,30 @@ pub mod liqz {
             )?;
         }
 
+        // The fees and the insurance fund are kept in DAI
+        Treasury::create_account(
+            ctx.program_id,
+            &pool,
+            dai_mint,
+            treasury_dai_account,
+            pool_owner,
+            spl_program,
+            system,
+            rent,
+        )?;
+        InsuranceFund::create_account(
+            ctx.program_id,
+            &pool,
+            dai_mint,
+            insurance_dai_account,
+            pool_owner,
+            spl_program,
+            system,
+            rent,
+        )?;
+
+        Denylist::ensure(ctx.program_id, pool_owner, denylist_account, rent, system)?;
+
         emit!(EventInitialized {
             account: *pool.to_account_info().key
         });
@@ -108,745 +360,4323 @@ pub mod liqz {
     pub fn change_loan_settings(
         ctx: Context<AccountsChangeLoanSetting>,
         incentive: Option<u64>,
-        interest_rate: Option<u64>,
+        interest_model: Option<InterestModel>,
         service_fee_rate: Option<u64>,
         max_loan_duration: Option<i64>,
         mortgage_rate: Option<u64>,
+        min_interest_duration: Option<i64>,
+        origination_fee: Option<u64>,
+        referral_fee_rate: Option<u64>,
+        insurance_fee_rate: Option<u64>,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+        ctx.accounts.pool.change_loan_settings(&LoanSettings {
+            incentive,
+            interest_model,
+            service_fee_rate,
+            max_loan_duration,
+            mortgage_rate,
+            min_interest_duration,
+            origination_fee,
+            referral_fee_rate,
+            insurance_fee_rate,
+        })?;
 
-        incentive.map(|v| pool.incentive = v);
-        interest_rate.map(|v| pool.interest_rate = v);
-        service_fee_rate.map(|v| pool.service_fee_rate = v);
-        max_loan_duration.map(|v| pool.max_loan_duration = v);
-        mortgage_rate.map(|v| pool.mortgage_rate = v);
-
-        emit!(EventLoanSettingChanged {
-            incentive: pool.incentive,
-            interest_rate: pool.interest_rate,
-            service_fee_rate: pool.service_fee_rate,
-            max_loan_duration: pool.max_loan_duration,
-            mortgage_rate: pool.mortgage_rate,
-        });
         Ok(())
     }
 
-    // Deposits NFT asset into the pool, creating an entry of NFTListing
-    pub fn deposit_nft(ctx: Context<AccountsDepositNFT>, deposit_id: Pubkey) -> Result<()> {
-        let AccountsDepositNFT {
+    // Only the owner can take the fees out of the treasury
+    pub fn claim_fees(ctx: Context<AccountsClaimFees>, amount: u64) -> Result<()> {
+        let AccountsClaimFees {
             pool,
-            borrower_wallet_account,
-
-            nft_mint,
-            liz_mint,
-
-            pool_nft_account,
-            borrower_nft_account,
-
-            pool_liz_account,
-            borrower_liz_account,
-
-            deposit_account,
-
-            rent,
-
-            ata_program,
+            treasury_dai_account,
+            destination_dai_account,
             spl_program,
-            system_program,
+            ..
         } = ctx.accounts;
 
-        assert_eq!(liz_mint.to_account_info().key, &pool.liz_mint);
-        assert_eq!(pool_liz_account.mint, pool.liz_mint);
-        assert_eq!(nft_mint.decimals, 0);
-
-        // allocate the NFT ATA for the pool if not allocated
-        NFTPool::ensure_pool_token_account(
-            pool,
-            nft_mint,
-            pool_nft_account,
-            borrower_wallet_account,
-            ata_program,
-            spl_program,
-            system_program,
-            rent,
-        )?;
-
-        // allocate the liz ATA for the user if not allocated
-        NFTPool::ensure_user_token_account(
-            borrower_wallet_account,
-            liz_mint,
-            borrower_liz_account,
-            ata_program,
-            spl_program,
-            system_program,
-            rent,
-        )?;
-
-        // create and deposit to the deposit account
-        // error out if the account exists
-        let deposit_account = NFTDeposit::deposit(
-            ctx.program_id,
-            &deposit_id,
-            nft_mint.to_account_info().key,
-            borrower_wallet_account,
-            deposit_account,
-            rent,
-            system_program,
-        )?;
+        Treasury::verify_account(ctx.program_id, &pool.dai_mint, treasury_dai_account)?;
 
-        // Transfer NFT to the pool
         anchor_spl::token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: borrower_nft_account.to_account_info(),
-                    to: pool_nft_account.clone(),
-                    authority: borrower_wallet_account.clone(),
+                    from: treasury_dai_account.to_account_info(),
+                    to: destination_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
                 },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            1,
+            amount,
+        )?;
+
+        emit!(EventFeesClaimed {
+            mint: pool.dai_mint,
+            destination: *destination_dai_account.to_account_info().key,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    // Only the owner can sweep the tokens sent to a pool ATA by mistake.
+    // The DAI owed to lenders and the NFTs are never swept.
+    pub fn sweep(ctx: Context<AccountsSweep>) -> Result<()> {
+        let AccountsSweep {
+            pool,
+            mint,
+            pool_token_account,
+            destination_token_account,
+            spl_program,
+            ..
+        } = ctx.accounts;
+
+        // the treasury, the insurance fund and the stake vault are not ATAs of the pool
+        if pool_token_account.to_account_info().key
+            != &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                mint.to_account_info().key,
+            )
+        {
+            throw!(liqzError::SweepAccountNotCorrect);
+        }
+
+        let amount = pool.sweepable(
+            mint.to_account_info().key,
+            mint.decimals,
+            mint.mint_authority == COption::Some(*pool.to_account_info().key),
+            pool_token_account.amount,
         )?;
 
-        // Transfer incentive liz to the borrower
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_liz_account.to_account_info(),
-                    to: borrower_liz_account.clone(),
+                    from: pool_token_account.to_account_info(),
+                    to: destination_token_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
                 &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            pool.incentive,
+            amount,
         )?;
 
-        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
-        deposit_account.exit(ctx.program_id)?;
-
-        emit!(EventNFTDeposited {
-            mint: *nft_mint.to_account_info().key,
-            from: *borrower_wallet_account.key,
+        emit!(EventSwept {
+            mint: *mint.to_account_info().key,
+            destination: *destination_token_account.to_account_info().key,
+            amount,
         });
 
         Ok(())
     }
 
-    // withdraw the deposited NFT
-    pub fn withdraw_nft(ctx: Context<AccountsWithdrawNFT>, deposit_id: Pubkey) -> Result<()> {
-        // TODO: Do we set the minimal nft lock in time?
-        let AccountsWithdrawNFT {
+    // Pays a lender back from the insurance fund when the NFT taken in a liquidation is appraised below the principal.
+    // The pool owner sets the appraisals, so the payout goes through a multisig admin action instead.
+    pub fn insurance_payout(ctx: Context<AccountsInsurancePayout>) -> Result<()> {
+        let AccountsInsurancePayout {
             pool,
-            borrower_wallet_account,
+            insurance_dai_account,
+            lender_dai_account,
             nft_mint,
-            borrower_nft_account,
-            pool_nft_account,
+            borrower_wallet_account,
+            appraisal_account,
             deposit_account,
             spl_program,
+            ..
         } = ctx.accounts;
 
-        // verify the deposit account indeed belongs to the user
+        InsuranceFund::verify_account(ctx.program_id, &pool.dai_mint, insurance_dai_account)?;
+
+        let nft_mint = nft_mint.to_account_info().key;
+
+        let (_, bump) = NFTAppraisal::get_address_with_bump(ctx.program_id, nft_mint);
+        NFTAppraisal::verify_address(
+            ctx.program_id,
+            nft_mint,
+            bump,
+            appraisal_account.to_account_info().key,
+        )?;
+
         let (_, bump) = NFTDeposit::get_address_with_bump(
             ctx.program_id,
-            nft_mint.to_account_info().key,
+            nft_mint,
             borrower_wallet_account.key,
-            &deposit_id,
+            deposit_account.deposit_id,
         );
         NFTDeposit::verify_address(
             ctx.program_id,
-            nft_mint.to_account_info().key,
+            nft_mint,
             borrower_wallet_account.key,
-            &deposit_id,
+            deposit_account.deposit_id,
             bump,
             deposit_account.to_account_info().key,
         )?;
 
-        // withdraw also verifies the count
-        deposit_account.withdraw()?;
+        let liquidated = deposit_account.get_liquidated_state()?;
+
+        assert_eq!(lender_dai_account.owner, liquidated.lender);
+
+        let shortfall = liquidated
+            .borrowed_amount
+            .saturating_sub(appraisal_account.value);
+        let owed = shortfall.saturating_sub(liquidated.compensated);
+        if owed == 0 {
+            throw!(liqzError::NoShortfall);
+        }
+
+        // the fund pays what it can, the rest can be paid out once it is topped up
+        let amount = owed.min(insurance_dai_account.amount);
+
+        deposit_account.compensate(amount)?;
 
-        // transfer the NFT back to the user
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_nft_account.to_account_info(),
-                    to: borrower_nft_account.to_account_info(),
+                    from: insurance_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
                 &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            1,
+            amount,
         )?;
 
-        emit!(EventNFTWithdrawn {
-            mint: *nft_mint.to_account_info().key,
-            to: *borrower_wallet_account.key,
+        pool.insurance_paid_out = pool.insurance_paid_out.checked_add(amount).unwrap();
+
+        emit!(EventInsurancePaidOut {
+            loan_id: deposit_account.deposit_id,
+            lender: liquidated.lender,
+            appraised_value: appraisal_account.value,
+            shortfall,
+            amount,
         });
 
         Ok(())
     }
 
-    pub fn place_bid(ctx: Context<AccountsPlaceBid>, price: u64, qty: u64) -> Result<()> {
-        if qty == 0 {
-            return Ok(());
-        }
-
-        let AccountsPlaceBid {
-            pool,
-            lender_wallet_account,
-            nft_mint,
-            lender_dai_account,
-            bid_account,
-            spl_program,
+    // Integrators register once to be set as the referrer of borrows and bids
+    pub fn register_referrer(ctx: Context<AccountsRegisterReferrer>) -> Result<()> {
+        let AccountsRegisterReferrer {
+            referrer_wallet_account,
+            referrer_account,
             system_program,
             rent,
         } = ctx.accounts;
 
-        if qty > nft_mint.supply {
-            throw!(liqzError::NFTBidQtyLargerThanSupply);
-        }
-
-        assert_eq!(nft_mint.decimals, 0);
-
-        anchor_spl::token::approve(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Approve {
-                    to: lender_dai_account.to_account_info(),
-                    delegate: pool.to_account_info(),
-                    authority: lender_wallet_account.to_account_info(),
-                },
-            ),
-            price * qty,
-        )?;
-
-        // create the bid account if not created
-        let mut bid_account = NFTBid::ensure(
+        Referrer::register(
             ctx.program_id,
-            nft_mint.to_account_info().key,
-            lender_wallet_account,
-            bid_account,
+            referrer_wallet_account,
+            referrer_account,
             rent,
             system_program,
         )?;
-        bid_account.set(price, qty);
-
-        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
-        bid_account.exit(ctx.program_id)?;
 
-        emit!(EventNFTBidPlaced {
-            mint: *nft_mint.to_account_info().key,
-            from: *lender_wallet_account.key,
-            price,
-            qty,
+        emit!(EventReferrerRegistered {
+            referrer: *referrer_wallet_account.key,
         });
 
         Ok(())
     }
 
-    pub fn cancel_bid(ctx: Context<AccountsCancelBid>, revoke: bool) -> Result<()> {
-        let AccountsCancelBid {
-            lender_wallet_account,
-            nft_mint,
-            lender_dai_account,
-            bid_account,
-            spl_program,
-        } = ctx.accounts;
-
-        assert_eq!(nft_mint.decimals, 0);
+    pub fn change_stake_settings(
+        ctx: Context<AccountsChangeLoanSetting>,
+        stake_tiers: Option<[StakeTier; 3]>,
+        max_mortgage_rate: Option<u64>,
+        unstake_cooldown: Option<i64>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
 
-        let (_, bump) = NFTBid::get_address_with_bump(
-            ctx.program_id,
-            nft_mint.to_account_info().key,
-            lender_wallet_account.key,
-        );
+        if matches!(stake_tiers, Some(tiers) if tiers.iter().any(|tier| tier.fee_discount > 10000))
+            || matches!(max_mortgage_rate, Some(rate) if rate > 10000)
+            || matches!(unstake_cooldown, Some(cooldown) if cooldown < 0)
+        {
+            throw!(liqzError::StakeSettingsNotAllowed);
+        }
 
-        NFTBid::verify_address(
-            ctx.program_id,
-            nft_mint.to_account_info().key,
-            lender_wallet_account.key,
-            bump,
-            bid_account.to_account_info().key,
-        )?;
+        stake_tiers.map(|v| pool.stake_tiers = v);
+        max_mortgage_rate.map(|v| pool.max_mortgage_rate = v);
+        unstake_cooldown.map(|v| pool.unstake_cooldown = v);
 
-        emit!(EventNFTBidCancelled {
-            mint: *nft_mint.to_account_info().key,
-            from: *lender_wallet_account.key,
-            price: bid_account.price,
-            qty: bid_account.qty,
+        emit!(EventStakeSettingChanged {
+            stake_tiers: pool.stake_tiers,
+            max_mortgage_rate: pool.max_mortgage_rate,
+            unstake_cooldown: pool.unstake_cooldown,
         });
-
-        bid_account.cancel();
-
-        if revoke {
-            solana_program::program::invoke(
-                &spl_token::instruction::revoke(
-                    &spl_token::id(),
-                    lender_dai_account.to_account_info().key,
-                    lender_wallet_account.to_account_info().key,
-                    &[lender_wallet_account.key],
-                )?,
-                &[
-                    lender_dai_account.to_account_info(),
-                    lender_wallet_account.to_account_info(),
-                    spl_program.clone(),
-                ],
-            )?;
-        }
-
         Ok(())
     }
 
-    pub fn borrow(ctx: Context<AccountsBorrow>, amount: u64) -> Result<()> {
-        let AccountsBorrow {
+    // Stakes LIZ so that the borrows of the staker get a lower fee and a higher mortgage rate
+    pub fn stake_liz(ctx: Context<AccountsStakeLiz>, amount: u64) -> Result<()> {
+        let AccountsStakeLiz {
             pool,
-            borrower_wallet_account,
-            lender_wallet_account,
-
-            nft_mint,
-
-            pool_dai_account,
-            borrower_dai_account,
-            lender_dai_account,
-
-            pool_tai_account,
-            lender_tai_account,
-
-            bid_account,
-            deposit_account,
-
+            staker_wallet_account,
+            liz_mint,
+            staker_liz_account,
+            stake_vault_account,
+            stake_account,
             spl_program,
+            system_program,
+            rent,
             clock,
         } = ctx.accounts;
 
-        if amount > bid_account.price {
-            throw!(liqzError::NFTBorrowExceedBidAmount)
-        }
-
-        assert_eq!(lender_tai_account.mint, pool.tai_mint);
-        assert_eq!(pool_tai_account.mint, pool.tai_mint);
-        assert_eq!(lender_dai_account.mint, pool.dai_mint);
-        assert_eq!(borrower_dai_account.mint, pool.dai_mint);
-
-        let (_, bump) = NFTDeposit::get_address_with_bump(
-            ctx.program_id,
-            nft_mint.to_account_info().key,
-            borrower_wallet_account.key,
-            &deposit_account.deposit_id,
-        );
+        assert_eq!(liz_mint.to_account_info().key, &pool.liz_mint);
+        assert_eq!(staker_liz_account.mint, pool.liz_mint);
 
-        NFTDeposit::verify_address(
+        LizStake::ensure_vault(
             ctx.program_id,
-            nft_mint.to_account_info().key,
-            borrower_wallet_account.key,
-            &deposit_account.deposit_id,
-            bump,
-            deposit_account.to_account_info().key,
+            pool,
+            liz_mint,
+            stake_vault_account,
+            staker_wallet_account,
+            spl_program,
+            system_program,
+            rent,
         )?;
 
-        // set related records
-        let total_amount = amount;
-        let borrowed_amount = total_amount
-            .checked_mul(pool.mortgage_rate)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
-
-        if borrowed_amount <= 0 {
-            throw!(liqzError::BorrowedAmountTooSmall)
-        }
-
-        deposit_account.start_borrow(
-            *lender_wallet_account.key,
-            total_amount,
-            borrowed_amount,
-            clock.unix_timestamp,
-            pool.max_loan_duration,
+        let mut stake_account = LizStake::ensure(
+            ctx.program_id,
+            staker_wallet_account,
+            stake_account,
+            rent,
+            system_program,
         )?;
 
-        // decrease the bid qty by 1;
-        bid_account.trade(1)?;
-
-        // transfer DAI to the pool
         anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
+            CpiContext::new(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: lender_dai_account.to_account_info(),
-                    to: pool_dai_account.to_account_info(),
-                    authority: pool.to_account_info(), // The pool is the delegate
+                    from: staker_liz_account.to_account_info(),
+                    to: stake_vault_account.clone(),
+                    authority: staker_wallet_account.clone(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            total_amount,
+            amount,
         )?;
 
-        // transfer DAI to the borrower
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: pool_dai_account.to_account_info(),
-                    to: borrower_dai_account.to_account_info(),
-                    authority: pool.to_account_info(), // The pool is the delegate
-                },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
-            ),
-            borrowed_amount,
-        )?;
+        stake_account.stake(amount, clock.unix_timestamp);
+        stake_account.exit(ctx.program_id)?;
 
-        // transfer TAI to the lender
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: pool_tai_account.to_account_info(),
-                    to: lender_tai_account.to_account_info(),
-                    authority: pool.to_account_info(), // The pool is the delegate
-                },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
-            ),
-            borrowed_amount,
-        )?;
+        pool.liz_staked = pool.liz_staked.checked_add(amount).unwrap();
 
-        emit!(EventBorrowed {
-            borrower: *borrower_wallet_account.key,
-            lender: *lender_wallet_account.key,
-            amount: borrowed_amount,
-            length: pool.max_loan_duration
+        emit!(EventLizStaked {
+            staker: *staker_wallet_account.key,
+            amount,
+            staked: stake_account.amount,
         });
 
         Ok(())
     }
 
-    pub fn repay(ctx: Context<AccountsRepay>) -> Result<()> {
-        let AccountsRepay {
+    pub fn request_unstake(ctx: Context<AccountsRequestUnstake>, amount: u64) -> Result<()> {
+        let AccountsRequestUnstake {
             pool,
-            borrower_wallet_account,
-            pool_owner_dai_account,
-            borrower_dai_account,
-            lender_dai_account,
-
-            borrower_nft_account,
-            pool_nft_account,
-
-            deposit_account,
-            spl_program,
+            staker_wallet_account,
+            stake_account,
             clock,
         } = ctx.accounts;
 
-        let loan = deposit_account.get_active_state()?;
+        let (_, bump) = LizStake::get_address_with_bump(ctx.program_id, staker_wallet_account.key);
+        LizStake::verify_address(
+            ctx.program_id,
+            staker_wallet_account.key,
+            bump,
+            stake_account.to_account_info().key,
+        )?;
 
-        if clock.unix_timestamp > loan.expired_at {
-            throw!(liqzError::LoanLiquidated)
-        }
+        stake_account.request_unstake(amount, clock.unix_timestamp, pool.unstake_cooldown)?;
 
-        assert!(pool_owner_dai_account.owner == pool.owner);
+        pool.liz_staked = pool
+            .liz_staked
+            .checked_sub(amount)
+            .ok_or(liqzError::UnstakeExceedStake)?;
 
-        let (interest, fee) = pool.calculate_interest_and_fee(
-            loan.borrowed_amount,
-            clock.unix_timestamp.saturating_sub(loan.started_at),
-        );
+        emit!(EventUnstakeRequested {
+            staker: *staker_wallet_account.key,
+            amount,
+            unstake_at: stake_account.unstake_at,
+        });
 
-        // transfer fee to the owner
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: borrower_dai_account.to_account_info(),
-                    to: pool_owner_dai_account.to_account_info(),
-                    authority: borrower_wallet_account.to_account_info(),
-                },
-            ),
-            fee,
-        )?;
+        Ok(())
+    }
 
-        let lender_income = interest.checked_sub(fee).unwrap();
-        let repayed_amount = loan.total_amount.checked_add(lender_income).unwrap();
+    // Withdraws all the unstaked LIZ once the cooldown is over
+    pub fn withdraw_unstaked(ctx: Context<AccountsWithdrawUnstaked>) -> Result<()> {
+        let AccountsWithdrawUnstaked {
+            pool,
+            staker_wallet_account,
+            stake_account,
+            stake_vault_account,
+            staker_liz_account,
+            spl_program,
+            clock,
+        } = ctx.accounts;
 
-        // transfer the DAI to the pool, waiting for the lender to withdraw
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: borrower_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
-                    authority: borrower_wallet_account.to_account_info(),
-                },
-            ),
-            repayed_amount,
+        let (_, bump) = LizStake::get_address_with_bump(ctx.program_id, staker_wallet_account.key);
+        LizStake::verify_address(
+            ctx.program_id,
+            staker_wallet_account.key,
+            bump,
+            stake_account.to_account_info().key,
         )?;
 
-        // transfer the NFT to the borrower
+        let (_, vault_bump) = LizStake::get_vault_address_with_bump(ctx.program_id);
+        LizStake::verify_vault_address(
+            ctx.program_id,
+            vault_bump,
+            stake_vault_account.to_account_info().key,
+        )?;
+
+        let amount = stake_account.withdraw(clock.unix_timestamp)?;
+
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_nft_account.to_account_info(),
-                    to: borrower_nft_account.to_account_info(),
+                    from: stake_vault_account.to_account_info(),
+                    to: staker_liz_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
                 &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            1,
+            amount,
         )?;
 
-        // set corresponding records
-        deposit_account.repay(loan.borrowed_amount.checked_add(lender_income).unwrap())?;
+        emit!(EventUnstakeWithdrawn {
+            staker: *staker_wallet_account.key,
+            amount,
+        });
 
-        emit!(EventRepayed {
-            borrower: *borrower_wallet_account.key,
-            lender: loan.lender,
-            amount: repayed_amount,
-            fee,
-            lender_income
+        Ok(())
+    }
+
+    pub fn change_governance_settings(
+        ctx: Context<AccountsChangeLoanSetting>,
+        proposal_voting_period: Option<i64>,
+        proposal_quorum: Option<u64>,
+        proposal_threshold: Option<u64>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        if matches!(proposal_voting_period, Some(period) if period <= 0)
+            || matches!(proposal_quorum, Some(quorum) if quorum > 10000)
+            || matches!(proposal_threshold, Some(threshold) if threshold >= 10000)
+        {
+            throw!(liqzError::GovernanceSettingsNotAllowed);
+        }
+
+        proposal_voting_period.map(|v| pool.proposal_voting_period = v);
+        proposal_quorum.map(|v| pool.proposal_quorum = v);
+        proposal_threshold.map(|v| pool.proposal_threshold = v);
+
+        emit!(EventGovernanceSettingChanged {
+            proposal_voting_period: pool.proposal_voting_period,
+            proposal_quorum: pool.proposal_quorum,
+            proposal_threshold: pool.proposal_threshold,
         });
+        Ok(())
+    }
+
+    pub fn change_risk_caps(
+        ctx: Context<AccountsChangeLoanSetting>,
+        max_collection_principal: Option<u64>,
+        max_borrower_principal: Option<u64>,
+        max_borrower_open_loans: Option<u64>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        max_collection_principal.map(|v| pool.max_collection_principal = v);
+        max_borrower_principal.map(|v| pool.max_borrower_principal = v);
+        max_borrower_open_loans.map(|v| pool.max_borrower_open_loans = v);
 
+        emit!(EventRiskCapsChanged {
+            max_collection_principal: pool.max_collection_principal,
+            max_borrower_principal: pool.max_borrower_principal,
+            max_borrower_open_loans: pool.max_borrower_open_loans,
+        });
         Ok(())
     }
 
-    pub fn liquidate(ctx: Context<AccountsLiquidate>) -> Result<()> {
-        let AccountsLiquidate {
-            pool,
-            lender_wallet_account,
+    pub fn change_reputation_settings(
+        ctx: Context<AccountsChangeLoanSetting>,
+        reputation_min_repaid: Option<u64>,
+        reputation_mortgage_boost: Option<u64>,
+        reputation_min_principal: Option<u64>,
+        reputation_min_duration: Option<i64>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
 
-            pool_owner_dai_account,
-            pool_dai_account,
-            lender_dai_account,
+        if matches!(reputation_mortgage_boost, Some(boost) if boost > 10000)
+            || matches!(reputation_min_duration, Some(duration) if duration < 0)
+        {
+            throw!(liqzError::ReputationSettingsNotAllowed);
+        }
 
-            nft_mint,
-            pool_nft_account,
-            lender_nft_account,
+        reputation_min_repaid.map(|v| pool.reputation_min_repaid = v);
+        reputation_mortgage_boost.map(|v| pool.reputation_mortgage_boost = v);
+        reputation_min_principal.map(|v| pool.reputation_min_principal = v);
+        reputation_min_duration.map(|v| pool.reputation_min_duration = v);
 
-            lender_tai_account,
-            pool_tai_account,
+        emit!(EventReputationSettingsChanged {
+            reputation_min_repaid: pool.reputation_min_repaid,
+            reputation_mortgage_boost: pool.reputation_mortgage_boost,
+            reputation_min_principal: pool.reputation_min_principal,
+            reputation_min_duration: pool.reputation_min_duration,
+        });
+        Ok(())
+    }
 
-            deposit_account,
+    // Turns the pool private, only wallets with a credential of the authority can bid and borrow.
+    // None makes the pool public again.
+    pub fn set_credential_authority(
+        ctx: Context<AccountsChangeLoanSetting>,
+        credential_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
 
-            ata_program,
-            spl_program,
+        pool.credential_authority = credential_authority;
+
+        emit!(EventCredentialAuthorityChanged {
+            credential_authority
+        });
+        Ok(())
+    }
+
+    pub fn issue_credential(
+        ctx: Context<AccountsIssueCredential>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let AccountsIssueCredential {
+            authority,
+            pool,
+            wallet_account,
+            credential_account,
             system_program,
             rent,
-            clock,
         } = ctx.accounts;
 
-        let loan = deposit_account.get_active_state()?;
-
-        if clock.unix_timestamp <= loan.expired_at {
-            throw!(liqzError::LoanNotExpired)
-        }
+        let credential_account = Credential::issue(
+            ctx.program_id,
+            pool,
+            authority,
+            wallet_account.key,
+            credential_account,
+            expires_at,
+            rent,
+            system_program,
+        )?;
+
+        credential_account.exit(ctx.program_id)?;
+
+        emit!(EventCredentialIssued {
+            wallet: *wallet_account.key,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_credential(ctx: Context<AccountsRevokeCredential>) -> Result<()> {
+        let AccountsRevokeCredential {
+            authority,
+            pool,
+            wallet_account,
+            credential_account,
+        } = ctx.accounts;
+
+        Credential::verify_authority(pool, authority.key)?;
+
+        let (_, bump) = Credential::get_address_with_bump(ctx.program_id, wallet_account.key);
+        Credential::verify_address(
+            ctx.program_id,
+            wallet_account.key,
+            bump,
+            credential_account.to_account_info().key,
+        )?;
+
+        credential_account.revoked = true;
+
+        emit!(EventCredentialRevoked {
+            wallet: *wallet_account.key,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can create the denylist, only the owner can change it
+    pub fn create_denylist(ctx: Context<AccountsCreateDenylist>) -> Result<()> {
+        let AccountsCreateDenylist {
+            payer,
+            denylist_account,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        Denylist::ensure(
+            ctx.program_id,
+            payer,
+            denylist_account,
+            rent,
+            system_program,
+        )?;
+
+        Ok(())
+    }
+
+    // Loans already open on a denied asset are still repaid or liquidated as usual
+    pub fn change_denylist(
+        ctx: Context<AccountsChangeDenylist>,
+        asset: Pubkey,
+        denied: bool,
+    ) -> Result<()> {
+        let AccountsChangeDenylist {
+            denylist_account, ..
+        } = ctx.accounts;
+
+        let (_, bump) = Denylist::get_address_with_bump(ctx.program_id);
+        Denylist::verify_address(ctx.program_id, bump, denylist_account.to_account_info().key)?;
+
+        denylist_account.set(asset, denied)?;
+
+        emit!(EventDenylistChanged { asset, denied });
+
+        Ok(())
+    }
+
+    // Anyone can propose a change of the loan settings for the LIZ stakers to vote on
+    pub fn propose(
+        ctx: Context<AccountsPropose>,
+        proposal_id: Pubkey,
+        settings: LoanSettings,
+    ) -> Result<()> {
+        let AccountsPropose {
+            pool,
+            proposer_wallet_account,
+            proposal_account,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        let proposal = Proposal::create(
+            ctx.program_id,
+            &proposal_id,
+            pool,
+            settings,
+            proposer_wallet_account,
+            proposal_account,
+            clock.unix_timestamp,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventProposalCreated {
+            proposal: *proposal_account.key,
+            proposer: *proposer_wallet_account.key,
+            settings,
+            voting_ends_at: proposal.voting_ends_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote(ctx: Context<AccountsVote>, support: bool) -> Result<()> {
+        let AccountsVote {
+            voter_wallet_account,
+            stake_account,
+            proposal_account,
+            vote_account,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        let (_, bump) = LizStake::get_address_with_bump(ctx.program_id, voter_wallet_account.key);
+        LizStake::verify_address(
+            ctx.program_id,
+            voter_wallet_account.key,
+            bump,
+            stake_account.to_account_info().key,
+        )?;
+
+        let weight = proposal_account.vote(stake_account, support, clock.unix_timestamp)?;
+
+        ProposalVote::record(
+            ctx.program_id,
+            proposal_account.to_account_info().key,
+            voter_wallet_account,
+            vote_account,
+            weight,
+            support,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventVoted {
+            proposal: *proposal_account.to_account_info().key,
+            voter: *voter_wallet_account.key,
+            support,
+            weight,
+        });
+
+        Ok(())
+    }
+
+    // Hands the pool administration over to an M-of-N set of keys
+    pub fn create_multisig(
+        ctx: Context<AccountsCreateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u64,
+    ) -> Result<()> {
+        let AccountsCreateMultisig {
+            owner,
+            pool,
+            multisig_account,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        Multisig::create(
+            ctx.program_id,
+            members.clone(),
+            threshold,
+            owner,
+            multisig_account,
+            rent,
+            system_program,
+        )?;
+
+        pool.owner = *multisig_account.key;
+
+        emit!(EventMultisigChanged { members, threshold });
+
+        Ok(())
+    }
+
+    // Only the multisig itself can change its members, through an admin action
+    pub fn change_multisig_members(
+        ctx: Context<AccountsChangeMultisigMembers>,
+        members: Vec<Pubkey>,
+        threshold: u64,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig_account;
+
+        multisig.set_members(members.clone(), threshold)?;
+
+        emit!(EventMultisigChanged { members, threshold });
+
+        Ok(())
+    }
+
+    pub fn propose_admin_action(
+        ctx: Context<AccountsProposeAdminAction>,
+        action_id: Pubkey,
+        action_accounts: Vec<AdminActionAccount>,
+        action_data: Vec<u8>,
+    ) -> Result<()> {
+        let AccountsProposeAdminAction {
+            member_wallet_account,
+            multisig_account,
+            action_account,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        AdminAction::create(
+            ctx.program_id,
+            &action_id,
+            multisig_account,
+            action_accounts,
+            action_data,
+            member_wallet_account,
+            action_account,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventAdminActionProposed {
+            action: *action_account.key,
+            proposer: *member_wallet_account.key,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_admin_action(ctx: Context<AccountsApproveAdminAction>) -> Result<()> {
+        let AccountsApproveAdminAction {
+            member_wallet_account,
+            multisig_account,
+            action_account,
+        } = ctx.accounts;
+
+        let approvals = action_account.approve(multisig_account, member_wallet_account.key)?;
+
+        emit!(EventAdminActionApproved {
+            action: *action_account.to_account_info().key,
+            member: *member_wallet_account.key,
+            approvals,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can execute an approved admin action.
+    // The accounts of the instruction, and the liqz program itself, are passed in the remaining accounts.
+    pub fn execute_admin_action<'info>(
+        ctx: Context<'_, '_, '_, 'info, AccountsExecuteAdminAction<'info>>,
+    ) -> Result<()> {
+        let AccountsExecuteAdminAction {
+            multisig_account,
+            action_account,
+        } = ctx.accounts;
+
+        let (_, bump) = Multisig::get_address_with_bump(ctx.program_id);
+        Multisig::verify_address(ctx.program_id, bump, multisig_account.to_account_info().key)?;
+
+        let ix = action_account.execute(ctx.program_id, multisig_account)?;
+
+        solana_program::program::invoke_signed(
+            &ix,
+            ctx.remaining_accounts,
+            &[&[Multisig::SEED, &[multisig_account.bump_seed]]],
+        )?;
+
+        emit!(EventAdminActionExecuted {
+            action: *action_account.to_account_info().key,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can execute a passed proposal once the voting ends
+    pub fn execute_proposal(ctx: Context<AccountsExecuteProposal>) -> Result<()> {
+        let AccountsExecuteProposal {
+            pool,
+            proposal_account,
+            clock,
+        } = ctx.accounts;
+
+        let settings = proposal_account.execute(pool, clock.unix_timestamp)?;
+
+        pool.change_loan_settings(&settings)?;
+
+        emit!(EventProposalExecuted {
+            proposal: *proposal_account.to_account_info().key,
+            votes_for: proposal_account.votes_for,
+            votes_against: proposal_account.votes_against,
+        });
+
+        Ok(())
+    }
+
+    // Deposits NFT asset into the pool, creating an entry of NFTListing
+    pub fn deposit_nft(ctx: Context<AccountsDepositNFT>) -> Result<()> {
+        ctx.accounts.deposit(ctx.program_id)?;
+
+        Ok(())
+    }
 
-        // Transfer the corresponding TAI to the pool
+    // withdraw the deposited NFT
+    pub fn withdraw_nft(ctx: Context<AccountsWithdrawNFT>, deposit_id: u64) -> Result<()> {
+        // TODO: Do we set the minimal nft lock in time?
+        let AccountsWithdrawNFT {
+            pool,
+            borrower_wallet_account,
+            nft_mint,
+            borrower_nft_account,
+            pool_nft_account,
+            deposit_account,
+            spl_program,
+        } = ctx.accounts;
+
+        // verify the deposit account indeed belongs to the user
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_id,
+        );
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        assert_eq!(&borrower_nft_account.owner, borrower_wallet_account.key);
+        assert_eq!(
+            pool_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        // withdraw also verifies the count
+        deposit_account.withdraw()?;
+
+        // transfer the NFT back to the user
         anchor_spl::token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: lender_tai_account.to_account_info(),
-                    to: pool_tai_account.to_account_info(),
+                    from: pool_nft_account.to_account_info(),
+                    to: borrower_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        emit!(EventNFTWithdrawn {
+            mint: *nft_mint.to_account_info().key,
+            to: *borrower_wallet_account.key,
+        });
+
+        Ok(())
+    }
+
+    pub fn place_bid(
+        ctx: Context<AccountsPlaceBid>,
+        price: u64,
+        qty: u64,
+        restriction: Option<BidRestriction>,
+        expires_at: Option<i64>,
+        call_notice: Option<i64>,
+        installment_period: Option<i64>,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        if qty == 0 {
+            return Ok(());
+        }
+
+        let AccountsPlaceBid {
+            pool,
+            lender_wallet_account,
+            nft_mint,
+            nft_metadata_account,
+            denylist_account,
+            lender_credential_account,
+            lender_dai_account,
+            bid_account,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        if qty > nft_mint.supply {
+            throw!(liqzError::NFTBidQtyLargerThanSupply);
+        }
+
+        Credential::verify_allowed(
+            ctx.program_id,
+            pool,
+            lender_wallet_account.key,
+            lender_credential_account,
+            clock.unix_timestamp,
+        )?;
+
+        Denylist::verify_allowed(
+            ctx.program_id,
+            denylist_account,
+            nft_mint.to_account_info().key,
+            &utils::get_collection(nft_mint.to_account_info().key, nft_metadata_account)?,
+        )?;
+
+        if matches!(call_notice, Some(notice) if notice <= 0 || notice > pool.max_loan_duration) {
+            throw!(liqzError::CallNoticeNotAllowed);
+        }
+
+        if matches!(installment_period, Some(period) if period <= 0 || period > pool.max_loan_duration)
+        {
+            throw!(liqzError::InstallmentPeriodNotAllowed);
+        }
+
+        if let Some(referrer) = referrer {
+            if &referrer == lender_wallet_account.key {
+                throw!(liqzError::SelfReferral);
+            }
+            Referrer::verify_registered(ctx.program_id, &referrer, ctx.remaining_accounts.first())?;
+        }
+
+        assert_eq!(nft_mint.decimals, 0);
+
+        anchor_spl::token::approve(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Approve {
+                    to: lender_dai_account.to_account_info(),
+                    delegate: pool.to_account_info(),
                     authority: lender_wallet_account.to_account_info(),
                 },
             ),
-            loan.borrowed_amount,
+            price * qty,
+        )?;
+
+        // create the bid account if not created
+        let mut bid_account = NFTBid::ensure(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            lender_wallet_account,
+            bid_account,
+            rent,
+            system_program,
+        )?;
+        bid_account.set(
+            price,
+            qty,
+            restriction,
+            expires_at,
+            call_notice,
+            installment_period,
+            referrer,
+        );
+
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        bid_account.exit(ctx.program_id)?;
+
+        emit!(EventNFTBidPlaced {
+            mint: *nft_mint.to_account_info().key,
+            from: *lender_wallet_account.key,
+            price,
+            qty,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_bid(ctx: Context<AccountsCancelBid>, revoke: bool) -> Result<()> {
+        let AccountsCancelBid {
+            lender_wallet_account,
+            nft_mint,
+            lender_dai_account,
+            bid_account,
+            spl_program,
+        } = ctx.accounts;
+
+        assert_eq!(nft_mint.decimals, 0);
+
+        let (_, bump) = NFTBid::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            lender_wallet_account.key,
+        );
+
+        NFTBid::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            lender_wallet_account.key,
+            bump,
+            bid_account.to_account_info().key,
+        )?;
+
+        emit!(EventNFTBidCancelled {
+            mint: *nft_mint.to_account_info().key,
+            from: *lender_wallet_account.key,
+            price: bid_account.price,
+            qty: bid_account.qty,
+        });
+
+        bid_account.cancel();
+
+        if revoke {
+            solana_program::program::invoke(
+                &spl_token::instruction::revoke(
+                    &spl_token::id(),
+                    lender_dai_account.to_account_info().key,
+                    lender_wallet_account.to_account_info().key,
+                    &[lender_wallet_account.key],
+                )?,
+                &[
+                    lender_dai_account.to_account_info(),
+                    lender_wallet_account.to_account_info(),
+                    spl_program.clone(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Anyone can close an expired bid, the closer gets part of the rent and the lender the rest
+    pub fn close_expired_bid(ctx: Context<AccountsCloseExpiredBid>) -> Result<()> {
+        let AccountsCloseExpiredBid {
+            closer_wallet_account,
+            lender_wallet_account,
+            nft_mint,
+            bid_account,
+            clock,
+        } = ctx.accounts;
+
+        if bid_account.owner != ctx.program_id {
+            throw!(ProgramError::IncorrectProgramId);
+        }
+
+        let (_, bump) = NFTBid::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            lender_wallet_account.key,
+        );
+
+        NFTBid::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            lender_wallet_account.key,
+            bump,
+            bid_account.key,
+        )?;
+
+        let bid: ProgramAccount<NFTBid> = ProgramAccount::try_from(bid_account)?;
+
+        if !bid.is_expired(clock.unix_timestamp) {
+            throw!(liqzError::BidNotExpired)
+        }
+
+        let rent_lamports = bid_account.lamports();
+        let bounty = rent_lamports
+            .checked_mul(BID_CLOSE_BOUNTY_RATE)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap();
+
+        **closer_wallet_account.try_borrow_mut_lamports()? += bounty;
+        **lender_wallet_account.try_borrow_mut_lamports()? += rent_lamports - bounty;
+        **bid_account.try_borrow_mut_lamports()? = 0;
+
+        // wipe the discriminator so that the account cannot be used again in this transaction
+        bid_account.try_borrow_mut_data()?.fill(0);
+
+        emit!(EventNFTBidClosed {
+            mint: *nft_mint.to_account_info().key,
+            from: *lender_wallet_account.key,
+            closer: *closer_wallet_account.key,
+            bounty,
+        });
+
+        Ok(())
+    }
+
+    pub fn borrow(
+        ctx: Context<AccountsBorrow>,
+        amount: u64,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        let AccountsBorrow { loan, bid_account } = ctx.accounts;
+
+        if let Some(referrer) = referrer {
+            if &referrer == loan.borrower_wallet_account.key {
+                throw!(liqzError::SelfReferral);
+            }
+            Referrer::verify_registered(ctx.program_id, &referrer, ctx.remaining_accounts.first())?;
+        }
+
+        loan.fill_bid(ctx.program_id, bid_account, amount)?;
+
+        let mut terms = bid_account.loan_terms(&loan.pool);
+        terms.borrow_referrer = referrer;
+        let payer = loan.borrower_wallet_account.clone();
+        loan.start_loan(ctx.program_id, amount, terms, &payer)
+    }
+
+    // Deposits the NFT and borrows against the bid in one go, so that the NFT never sits idle in the pool
+    pub fn deposit_and_borrow(ctx: Context<AccountsDepositAndBorrow>, amount: u64) -> Result<()> {
+        let AccountsDepositAndBorrow {
+            deposit,
+            borrower_stake_account,
+            borrower_credential_account,
+            lender_wallet_account,
+            lender_credential_account,
+
+            treasury_dai_account,
+            pool_dai_account,
+            borrower_dai_account,
+            lender_dai_account,
+
+            position_mint,
+            lender_position_account,
+
+            bid_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+            clock,
+        } = ctx.accounts;
+
+        let deposit_account = deposit.deposit(ctx.program_id)?;
+
+        if bid_account.owner != ctx.program_id {
+            throw!(ProgramError::IncorrectProgramId);
+        }
+
+        let mut loan = AccountsStartLoan {
+            pool: deposit.pool.clone(),
+            borrower_wallet_account: deposit.borrower_wallet_account.clone(),
+            borrower_stake_account: borrower_stake_account.clone(),
+            borrower_credential_account: borrower_credential_account.clone(),
+            lender_wallet_account: lender_wallet_account.clone(),
+            lender_credential_account: lender_credential_account.clone(),
+
+            nft_mint: deposit.nft_mint.clone(),
+            nft_metadata_account: deposit.nft_metadata_account.clone(),
+            denylist_account: deposit.denylist_account.clone(),
+
+            treasury_dai_account: treasury_dai_account.clone(),
+            pool_dai_account: pool_dai_account.clone(),
+            borrower_dai_account: borrower_dai_account.clone(),
+            lender_dai_account: lender_dai_account.clone(),
+
+            position_mint: position_mint.clone(),
+            lender_position_account: lender_position_account.clone(),
+
+            deposit_account,
+            collection_exposure_account: collection_exposure_account.clone(),
+            borrower_exposure_account: borrower_exposure_account.clone(),
+            borrower_reputation_account: borrower_reputation_account.clone(),
+
+            ata_program: deposit.ata_program.clone(),
+            spl_program: deposit.spl_program.clone(),
+            system_program: deposit.system_program.clone(),
+            rent: deposit.rent.clone(),
+            clock: clock.clone(),
+        };
+
+        let mut bid_account: ProgramAccount<NFTBid> = ProgramAccount::try_from(bid_account)?;
+
+        loan.fill_bid(ctx.program_id, &mut bid_account, amount)?;
+
+        let terms = bid_account.loan_terms(&loan.pool);
+        let payer = loan.borrower_wallet_account.clone();
+        loan.start_loan(ctx.program_id, amount, terms, &payer)?;
+
+        // Persistent back the deposit and the bid. Since we created the ProgramAccounts by ourselves, we need to do this manually.
+        // The pool is persisted again with the outer accounts, keep it up to date.
+        loan.exit(ctx.program_id)?;
+        *deposit.pool = (*loan.pool).clone();
+        bid_account.exit(ctx.program_id)?;
+
+        Ok(())
+    }
+
+    // Borrows against an offer the lender signed off-chain instead of an on-chain bid.
+    // The transaction must carry an ed25519 instruction verifying the signature right before this one.
+    pub fn borrow_with_offer(
+        ctx: Context<AccountsBorrowWithOffer>,
+        offer: LoanOffer,
+        amount: u64,
+    ) -> Result<()> {
+        let AccountsBorrowWithOffer {
+            loan,
+            nonce_account,
+            instructions,
+        } = ctx.accounts;
+
+        offer.verify_signature(
+            ctx.program_id,
+            loan.pool.to_account_info().key,
+            instructions,
+        )?;
+
+        if *loan.lender_wallet_account.key != offer.lender {
+            throw!(liqzError::OfferSignatureNotVerified)
+        }
+
+        offer.verify_target(
+            loan.nft_mint.to_account_info().key,
+            &loan.nft_metadata_account,
+        )?;
+
+        if loan.clock.unix_timestamp > offer.expires_at {
+            throw!(liqzError::OfferExpired)
+        }
+
+        if amount > offer.price {
+            throw!(liqzError::NFTBorrowExceedBidAmount)
+        }
+
+        if offer.duration <= 0 || offer.duration > loan.pool.max_loan_duration {
+            throw!(liqzError::OfferDurationNotAllowed)
+        }
+
+        LoanOfferNonce::consume(
+            ctx.program_id,
+            &offer.lender,
+            offer.nonce,
+            &loan.borrower_wallet_account,
+            nonce_account,
+            loan.clock.unix_timestamp,
+            &loan.rent,
+            &loan.system_program,
+        )?;
+
+        let terms = LoanTerms {
+            term: LoanTerm::Fixed(offer.duration),
+            interest_model: loan.pool.interest_model,
+            service_fee_rate: loan.pool.service_fee_rate,
+            installment_period: None,
+            min_interest_duration: loan.pool.min_interest_duration,
+            borrow_referrer: None,
+            bid_referrer: None,
+        };
+        let payer = loan.borrower_wallet_account.clone();
+        loan.start_loan(ctx.program_id, amount, terms, &payer)?;
+
+        emit!(EventOfferTaken {
+            lender: offer.lender,
+            nonce: offer.nonce,
+            loan_id: loan.deposit_account.deposit_id,
+        });
+
+        Ok(())
+    }
+
+    // Burns the nonce of an offer so that it can no longer be taken
+    pub fn cancel_offer(ctx: Context<AccountsCancelOffer>, nonce: u64) -> Result<()> {
+        let AccountsCancelOffer {
+            lender_wallet_account,
+            nonce_account,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        LoanOfferNonce::consume(
+            ctx.program_id,
+            lender_wallet_account.key,
+            nonce,
+            lender_wallet_account,
+            nonce_account,
+            clock.unix_timestamp,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventOfferCancelled {
+            lender: *lender_wallet_account.key,
+            nonce,
+        });
+
+        Ok(())
+    }
+
+    pub fn place_ask(
+        ctx: Context<AccountsPlaceAsk>,
+        amount: u64,
+        interest_rate: u64,
+        duration: i64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let AccountsPlaceAsk {
+            pool,
+            borrower_wallet_account,
+            nft_mint,
+            deposit_account,
+            ask_account,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if !deposit_account.is_pending_loan() {
+            throw!(liqzError::BorrowAlreadyStarted)
+        }
+
+        if duration <= 0 || duration > pool.max_loan_duration {
+            throw!(liqzError::AskDurationNotAllowed)
+        }
+
+        NFTAsk::verify_interest_rate(interest_rate)?;
+
+        // create the ask account if not created
+        let mut ask_account = NFTAsk::ensure(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            borrower_wallet_account,
+            ask_account,
+            rent,
+            system_program,
+        )?;
+        ask_account.set(amount, interest_rate, duration);
+
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        ask_account.exit(ctx.program_id)?;
+
+        emit!(EventNFTAskPlaced {
+            mint: *nft_mint.to_account_info().key,
+            from: *borrower_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+            amount,
+            interest_rate,
+            duration,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_ask(ctx: Context<AccountsCancelAsk>) -> Result<()> {
+        let AccountsCancelAsk {
+            borrower_wallet_account,
+            nft_mint,
+            deposit_account,
+            ask_account,
+        } = ctx.accounts;
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        let (_, bump) =
+            NFTAsk::get_address_with_bump(ctx.program_id, deposit_account.to_account_info().key);
+
+        NFTAsk::verify_address(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            bump,
+            ask_account.to_account_info().key,
+        )?;
+
+        emit!(EventNFTAskCancelled {
+            mint: *nft_mint.to_account_info().key,
+            from: *borrower_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+        });
+
+        ask_account.cancel();
+
+        Ok(())
+    }
+
+    // The lender accepts the ask of a pending deposit and starts the loan on the borrower's terms.
+    // The terms are passed in again so that the borrower cannot change them under the lender.
+    pub fn accept_ask(
+        ctx: Context<AccountsAcceptAsk>,
+        amount: u64,
+        interest_rate: u64,
+        duration: i64,
+    ) -> Result<()> {
+        let AccountsAcceptAsk {
+            pool,
+            borrower_wallet_account,
+            borrower_stake_account,
+            borrower_credential_account,
+            lender_wallet_account,
+            lender_credential_account,
+
+            nft_mint,
+            nft_metadata_account,
+            denylist_account,
+
+            treasury_dai_account,
+            pool_dai_account,
+            borrower_dai_account,
+            lender_dai_account,
+
+            position_mint,
+            lender_position_account,
+
+            deposit_account,
+            ask_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        if deposit_account.owner != ctx.program_id {
+            throw!(ProgramError::IncorrectProgramId);
+        }
+
+        let (_, bump) = NFTAsk::get_address_with_bump(ctx.program_id, deposit_account.key);
+
+        NFTAsk::verify_address(
+            ctx.program_id,
+            deposit_account.key,
+            bump,
+            ask_account.to_account_info().key,
+        )?;
+
+        ask_account.take(amount, interest_rate, duration)?;
+
+        // the pool moves the DAI as a delegate, keep whatever the lender already approved for bids
+        let allowance = if lender_dai_account.delegate == COption::Some(*pool.to_account_info().key)
+        {
+            lender_dai_account
+                .delegated_amount
+                .checked_add(amount)
+                .unwrap()
+        } else {
+            amount
+        };
+
+        anchor_spl::token::approve(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Approve {
+                    to: lender_dai_account.to_account_info(),
+                    delegate: pool.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            allowance,
+        )?;
+
+        let mut loan = AccountsStartLoan {
+            pool: pool.clone(),
+            borrower_wallet_account: borrower_wallet_account.clone(),
+            borrower_stake_account: borrower_stake_account.clone(),
+            borrower_credential_account: borrower_credential_account.clone(),
+            lender_wallet_account: lender_wallet_account.clone(),
+            lender_credential_account: lender_credential_account.clone(),
+
+            nft_mint: nft_mint.clone(),
+            nft_metadata_account: nft_metadata_account.clone(),
+            denylist_account: denylist_account.clone(),
+
+            treasury_dai_account: treasury_dai_account.clone(),
+            pool_dai_account: pool_dai_account.clone(),
+            borrower_dai_account: borrower_dai_account.clone(),
+            lender_dai_account: lender_dai_account.clone(),
+
+            position_mint: position_mint.clone(),
+            lender_position_account: lender_position_account.clone(),
+
+            deposit_account: ProgramAccount::try_from(deposit_account)?,
+            collection_exposure_account: collection_exposure_account.clone(),
+            borrower_exposure_account: borrower_exposure_account.clone(),
+            borrower_reputation_account: borrower_reputation_account.clone(),
+
+            ata_program: ata_program.clone(),
+            spl_program: spl_program.clone(),
+            system_program: system_program.clone(),
+            rent: rent.clone(),
+            clock: clock.clone(),
+        };
+
+        // the lender signs here, so the lender pays for the position
+        let terms = LoanTerms {
+            term: LoanTerm::Fixed(duration),
+            interest_model: InterestModel::from_daily_rate(interest_rate),
+            service_fee_rate: pool.service_fee_rate,
+            installment_period: None,
+            min_interest_duration: pool.min_interest_duration,
+            borrow_referrer: None,
+            bid_referrer: None,
+        };
+        loan.start_loan(ctx.program_id, amount, terms, lender_wallet_account)?;
+
+        // Persistent back the deposit. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        // The pool is persisted again with the outer accounts, keep it up to date.
+        loan.exit(ctx.program_id)?;
+        **pool = (*loan.pool).clone();
+
+        emit!(EventNFTAskAccepted {
+            mint: *nft_mint.to_account_info().key,
+            lender: *lender_wallet_account.key,
+            loan_id: loan.deposit_account.deposit_id,
+            amount,
+            interest_rate,
+            duration,
+        });
+
+        Ok(())
+    }
+
+    // The lender position holder calls an open-ended loan, liquidation opens after the notice period
+    pub fn call_loan(ctx: Context<AccountsCallLoan>) -> Result<()> {
+        let AccountsCallLoan {
+            lender_wallet_account,
+            lender_position_account,
+            deposit_account,
+            clock,
+        } = ctx.accounts;
+
+        LenderPosition::verify_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            lender_position_account,
+            lender_wallet_account.key,
+        )?;
+
+        let expired_at = deposit_account.call(clock.unix_timestamp)?;
+
+        emit!(EventLoanCalled {
+            loan_id: deposit_account.deposit_id,
+            lender: *lender_wallet_account.key,
+            expired_at,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_loan_call(ctx: Context<AccountsCallLoan>) -> Result<()> {
+        let AccountsCallLoan {
+            lender_wallet_account,
+            lender_position_account,
+            deposit_account,
+            clock,
+        } = ctx.accounts;
+
+        LenderPosition::verify_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            lender_position_account,
+            lender_wallet_account.key,
+        )?;
+
+        // a called loan past its notice can only be repaid or liquidated
+        if clock.unix_timestamp > deposit_account.get_active_state()?.expired_at {
+            throw!(liqzError::LoanLiquidated)
+        }
+
+        deposit_account.cancel_call()?;
+
+        emit!(EventLoanCallCancelled {
+            loan_id: deposit_account.deposit_id,
+            lender: *lender_wallet_account.key,
+        });
+
+        Ok(())
+    }
+
+    // Pays the interest of the next installment period to the lender position holder
+    pub fn pay_installment(ctx: Context<AccountsPayInstallment>) -> Result<()> {
+        let AccountsPayInstallment {
+            pool,
+            borrower_wallet_account,
+            treasury_dai_account,
+            borrower_dai_account,
+            lender_dai_account,
+            lender_position_account,
+            deposit_account,
+            spl_program,
+            clock,
+        } = ctx.accounts;
+
+        let loan = deposit_account.get_active_state()?;
+
+        if clock.unix_timestamp > loan.expired_at {
+            throw!(liqzError::LoanLiquidated)
+        }
+
+        Treasury::verify_account(ctx.program_id, &pool.dai_mint, treasury_dai_account)?;
+
+        // the installment goes to whoever holds the lender position now
+        LenderPosition::verify_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            lender_position_account,
+            &lender_dai_account.owner,
+        )?;
+
+        let span = deposit_account.pay_installment()?;
+
+        let (interest, fee) = loan.interest_and_fee(span);
+
+        // transfer fee to the treasury
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: treasury_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+        pool.accrue_fee(fee);
+        pool.record_interest(interest);
+
+        let lender_income = interest.checked_sub(fee).unwrap();
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            lender_income,
+        )?;
+
+        let paid_until = deposit_account.get_active_state()?.interest_accrued_since();
+
+        emit!(EventInstallmentPaid {
+            loan_id: deposit_account.deposit_id,
+            borrower: *borrower_wallet_account.key,
+            lender: lender_dai_account.owner,
+            fee,
+            lender_income,
+            paid_until,
+        });
+
+        Ok(())
+    }
+
+    pub fn repay<'info>(ctx: Context<'_, '_, '_, 'info, AccountsRepay<'info>>) -> Result<()> {
+        let AccountsRepay {
+            pool,
+            borrower_wallet_account,
+            treasury_dai_account,
+            insurance_dai_account,
+            borrower_dai_account,
+            pool_dai_account,
+
+            nft_mint,
+            borrower_nft_account,
+            pool_nft_account,
+
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+            spl_program,
+            clock,
+        } = ctx.accounts;
+
+        let loan = deposit_account.get_active_state()?;
+
+        if clock.unix_timestamp > loan.expired_at {
+            throw!(liqzError::LoanLiquidated)
+        }
+
+        Treasury::verify_account(ctx.program_id, &pool.dai_mint, treasury_dai_account)?;
+        InsuranceFund::verify_account(ctx.program_id, &pool.dai_mint, insurance_dai_account)?;
+        NFTPool::verify_dai_account(pool, pool_dai_account)?;
+
+        // the NFT goes back to the borrower of this deposit only
+        assert_eq!(borrower_wallet_account.key, &loan.borrower);
+        assert_eq!(borrower_nft_account.owner, loan.borrower);
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        assert_eq!(
+            pool_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        let (interest, fee) = loan.interest_and_fee(loan.interest_duration(clock.unix_timestamp));
+
+        // each referrer of the loan gets a share of the fee, the rest goes to the treasury
+        let referrers = Referrer::load_all(
+            ctx.program_id,
+            &loan.referrers(),
+            ctx.remaining_accounts,
+            &pool.dai_mint,
+        )?;
+        let referral_share = pool.referral_share(fee);
+        let referral_fee = referral_share * referrers.len() as u64;
+
+        for (mut referrer_account, referrer_dai_account) in referrers {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: borrower_dai_account.to_account_info(),
+                        to: referrer_dai_account.to_account_info(),
+                        authority: borrower_wallet_account.to_account_info(),
+                    },
+                ),
+                referral_share,
+            )?;
+            referrer_account.record(referral_share);
+            referrer_account.exit(ctx.program_id)?;
+        }
+
+        // a cut of the fee goes to the insurance fund
+        let insurance_fee = pool.insurance_share(fee);
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: insurance_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            insurance_fee,
+        )?;
+
+        // transfer fee to the treasury
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: treasury_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            fee - referral_fee - insurance_fee,
+        )?;
+        pool.accrue_fee(fee - referral_fee - insurance_fee);
+
+        let lender_income = interest.checked_sub(fee).unwrap();
+        let repayed_amount = loan.borrowed_amount.checked_add(lender_income).unwrap();
+
+        // transfer the DAI to the pool, the holder of the lender position withdraws it with the margin
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            repayed_amount,
+        )?;
+
+        // transfer the NFT to the borrower
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: borrower_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        // set corresponding records
+        Exposure::close_loan(
+            ctx.program_id,
+            &loan,
+            collection_exposure_account,
+            borrower_exposure_account,
+        )?;
+
+        Reputation::update(
+            ctx.program_id,
+            &loan.borrower,
+            borrower_reputation_account,
+            |reputation| reputation.record_repay(pool, &loan, clock.unix_timestamp),
+        )?;
+
+        pool.record_loan_repaid(loan.borrowed_amount, interest);
+
+        // the margin stays locked, the lender can withdraw it with the repayment
+        let lender_withdrawable = loan.total_amount.checked_add(lender_income).unwrap();
+        pool.lock_dai(repayed_amount);
+
+        deposit_account.repay(lender_withdrawable)?;
+
+        emit!(EventRepayed {
+            borrower: *borrower_wallet_account.key,
+            lender: loan.lender,
+            amount: repayed_amount,
+            fee,
+            lender_income,
+            referral_fee,
+            insurance_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn liquidate<'info>(
+        ctx: Context<'_, '_, '_, 'info, AccountsLiquidate<'info>>,
+    ) -> Result<()> {
+        let AccountsLiquidate {
+            pool,
+            lender_wallet_account,
+
+            treasury_dai_account,
+            insurance_dai_account,
+            pool_dai_account,
+            lender_dai_account,
+
+            nft_mint,
+            pool_nft_account,
+            lender_nft_account,
+
+            position_mint,
+            lender_position_account,
+
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        let loan = deposit_account.get_active_state()?;
+
+        if !loan.is_defaulted(clock.unix_timestamp) {
+            throw!(liqzError::LoanNotExpired)
+        }
+
+        Treasury::verify_account(ctx.program_id, &pool.dai_mint, treasury_dai_account)?;
+        InsuranceFund::verify_account(ctx.program_id, &pool.dai_mint, insurance_dai_account)?;
+        NFTPool::verify_dai_account(pool, pool_dai_account)?;
+
+        LenderPosition::verify_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            lender_position_account,
+            lender_wallet_account.key,
+        )?;
+
+        // the NFT has to be the one the loan was taken against
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        assert_eq!(
+            pool_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        // Burn the lender position in exchange of the NFT
+        anchor_spl::token::burn(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Burn {
+                    mint: position_mint.clone(),
+                    to: lender_position_account.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        // charge service fee using max_borrow_duration, the lender gets the rest of the margin
+        let (fee, withdrawable) = loan.liquidation_fee(pool.max_loan_duration);
+
+        // each referrer of the loan gets a share of the fee, the rest goes to the treasury
+        let referrers = Referrer::load_all(
+            ctx.program_id,
+            &loan.referrers(),
+            ctx.remaining_accounts,
+            &pool.dai_mint,
+        )?;
+        let referral_share = pool.referral_share(fee);
+        let referral_fee = referral_share * referrers.len() as u64;
+
+        for (mut referrer_account, referrer_dai_account) in referrers {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: pool_dai_account.to_account_info(),
+                        to: referrer_dai_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                ),
+                referral_share,
+            )?;
+            referrer_account.record(referral_share);
+            referrer_account.exit(ctx.program_id)?;
+        }
+
+        // a cut of the fee goes to the insurance fund
+        let insurance_fee = pool.insurance_share(fee);
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: insurance_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            insurance_fee,
+        )?;
+
+        // transfer fee to the treasury
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: treasury_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            fee - referral_fee - insurance_fee,
+        )?;
+        pool.accrue_fee(fee - referral_fee - insurance_fee);
+
+        // Transfer the remaining DAI to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            withdrawable,
+        )?;
+
+        // allocate the NFT ATA for the lender if not allocate
+        NFTPool::ensure_user_token_account(
+            lender_wallet_account,
+            nft_mint,
+            lender_nft_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        // Transfer the NFT to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: lender_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        // set corresponding records
+        Exposure::close_loan(
+            ctx.program_id,
+            &loan,
+            collection_exposure_account,
+            borrower_exposure_account,
+        )?;
+
+        Reputation::update(
+            ctx.program_id,
+            &loan.borrower,
+            borrower_reputation_account,
+            |reputation| reputation.record_liquidation(),
+        )?;
+
+        pool.record_liquidation(loan.borrowed_amount);
+        pool.unlock_dai(fee.checked_add(withdrawable).unwrap())?;
+
+        deposit_account.liquidate(*lender_wallet_account.key)?;
+
+        emit!(EventLiquidated {
+            lender: *lender_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+            withdrawable,
+            referral_fee,
+            insurance_fee,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_locked_asset(ctx: Context<AccountsWithdrawLockedAsset>) -> Result<()> {
+        let AccountsWithdrawLockedAsset {
+            pool,
+            lender_wallet_account,
+
+            position_mint,
+            lender_position_account,
+
+            lender_dai_account,
+            pool_dai_account,
+
+            deposit_account,
+
+            spl_program,
+        } = ctx.accounts;
+
+        let repay = deposit_account.get_repayed_state()?;
+
+        NFTPool::verify_dai_account(pool, pool_dai_account)?;
+
+        LenderPosition::verify_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            lender_position_account,
+            lender_wallet_account.key,
+        )?;
+
+        // Burn the lender position to unlock the DAI
+        anchor_spl::token::burn(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Burn {
+                    mint: position_mint.clone(),
+                    to: lender_position_account.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        // Transfer the DAI to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            repay.lender_withdrawable,
+        )?;
+        pool.unlock_dai(repay.lender_withdrawable)?;
+
+        deposit_account.clear()?;
+
+        emit!(EventWithDrawLockedAsset {
+            lender: *lender_wallet_account.key,
+            amount: repay.lender_withdrawable,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_vault(ctx: Context<AccountsInitializeVault>) -> Result<()> {
+        let AccountsInitializeVault {
+            owner: _,
+            pool,
+            payer,
+            vault,
+            lp_mint,
+            dai_mint,
+            vault_dai_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        assert_eq!(dai_mint.to_account_info().key, &pool.dai_mint);
+
+        let vault = LendingVault::new_checked(
+            ctx.program_id,
+            vault,
+            lp_mint,
+            dai_mint,
+            payer,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        utils::create_associated_token_account(
+            &vault.to_account_info(),
+            payer,
+            dai_mint,
+            vault_dai_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        emit!(EventVaultInitialized {
+            account: *vault.to_account_info().key,
+            lp_mint: vault.lp_mint,
+        });
+
+        Ok(())
+    }
+
+    pub fn change_vault_settings(
+        ctx: Context<AccountsChangeVaultSetting>,
+        ltv: Option<u64>,
+        interest_model: Option<InterestModel>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        ltv.map(|v| vault.ltv = v);
+        interest_model.map(|v| vault.interest_model = v);
+
+        emit!(EventVaultSettingChanged {
+            ltv: vault.ltv,
+            interest_model: vault.interest_model,
+        });
+        Ok(())
+    }
+
+    // Sets the DAI value the lending vault lends against, zero disables vault borrowing for the NFT
+    pub fn appraise_nft(ctx: Context<AccountsAppraiseNFT>, value: u64) -> Result<()> {
+        let AccountsAppraiseNFT {
+            owner: _,
+            pool: _,
+            payer,
+            nft_mint,
+            appraisal_account,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        assert_eq!(nft_mint.decimals, 0);
+
+        let mut appraisal_account = NFTAppraisal::ensure(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            payer,
+            appraisal_account,
+            rent,
+            system_program,
+        )?;
+        appraisal_account.value = value;
+
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        appraisal_account.exit(ctx.program_id)?;
+
+        emit!(EventNFTAppraised {
+            mint: *nft_mint.to_account_info().key,
+            value,
+        });
+
+        Ok(())
+    }
+
+    pub fn vault_deposit(ctx: Context<AccountsVaultDeposit>, amount: u64) -> Result<()> {
+        let AccountsVaultDeposit {
+            pool,
+            vault,
+            lender_wallet_account,
+            lender_credential_account,
+            lp_mint,
+            lender_lp_account,
+            lender_dai_account,
+            vault_dai_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        assert_eq!(lp_mint.to_account_info().key, &vault.lp_mint);
+        assert_eq!(&vault_dai_account.owner, vault.to_account_info().key);
+        assert_eq!(vault_dai_account.mint, pool.dai_mint);
+
+        // vault deposits fund loans like bids do, the same credential gate applies
+        Credential::verify_allowed(
+            ctx.program_id,
+            pool,
+            lender_wallet_account.key,
+            lender_credential_account,
+            clock.unix_timestamp,
+        )?;
+
+        let shares = vault.shares_for_deposit(amount, lp_mint.supply)?;
+        if shares == 0 {
+            throw!(liqzError::VaultDepositTooSmall);
+        }
+
+        NFTPool::ensure_user_token_account(
+            lender_wallet_account,
+            lp_mint,
+            lender_lp_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_dai_account.to_account_info(),
+                    to: vault_dai_account.to_account_info(),
+                    authority: lender_wallet_account.clone(),
+                },
+            ),
+            amount,
+        )?;
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::MintTo {
+                    mint: lp_mint.to_account_info(),
+                    to: lender_lp_account.clone(),
+                    authority: vault.to_account_info(),
+                },
+                &[&[LendingVault::SEED, &[vault.bump_seed]]],
+            ),
+            shares,
+        )?;
+
+        vault.deposit(amount);
+
+        emit!(EventVaultDeposited {
+            lender: *lender_wallet_account.key,
+            amount,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    pub fn vault_withdraw(ctx: Context<AccountsVaultWithdraw>, shares: u64) -> Result<()> {
+        let AccountsVaultWithdraw {
+            pool,
+            vault,
+            lender_wallet_account,
+            lp_mint,
+            lender_lp_account,
+            lender_dai_account,
+            vault_dai_account,
+            spl_program,
+        } = ctx.accounts;
+
+        assert_eq!(lp_mint.to_account_info().key, &vault.lp_mint);
+        assert_eq!(&vault_dai_account.owner, vault.to_account_info().key);
+        assert_eq!(vault_dai_account.mint, pool.dai_mint);
+
+        let amount = vault.amount_for_shares(shares, lp_mint.supply);
+
+        // fails if the DAI is lent out
+        vault.withdraw(amount)?;
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Burn {
+                    mint: lp_mint.to_account_info(),
+                    to: lender_lp_account.to_account_info(),
+                    authority: lender_wallet_account.clone(),
+                },
+            ),
+            shares,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: vault_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[&[LendingVault::SEED, &[vault.bump_seed]]],
+            ),
+            amount,
+        )?;
+
+        emit!(EventVaultWithdrawn {
+            lender: *lender_wallet_account.key,
+            amount,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    pub fn vault_borrow(ctx: Context<AccountsVaultBorrow>, amount: u64) -> Result<()> {
+        let AccountsVaultBorrow {
+            pool,
+            vault,
+            borrower_wallet_account,
+            nft_mint,
+            nft_metadata_account,
+            denylist_account,
+            borrower_credential_account,
+            appraisal_account,
+            treasury_dai_account,
+            vault_dai_account,
+            borrower_dai_account,
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        assert_eq!(borrower_dai_account.mint, pool.dai_mint);
+        Treasury::verify_account(ctx.program_id, &pool.dai_mint, treasury_dai_account)?;
+
+        let (_, bump) =
+            NFTAppraisal::get_address_with_bump(ctx.program_id, nft_mint.to_account_info().key);
+        NFTAppraisal::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            bump,
+            appraisal_account.to_account_info().key,
+        )?;
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+        );
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if amount <= pool.origination_fee {
+            throw!(liqzError::BorrowedAmountTooSmall)
+        }
+
+        if amount > vault.max_borrow(appraisal_account.value) {
+            throw!(liqzError::VaultBorrowExceedLTV)
+        }
+
+        let collection =
+            utils::get_collection(nft_mint.to_account_info().key, nft_metadata_account)?;
+
+        Denylist::verify_allowed(
+            ctx.program_id,
+            denylist_account,
+            nft_mint.to_account_info().key,
+            &collection,
+        )?;
+
+        Credential::verify_allowed(
+            ctx.program_id,
+            pool,
+            borrower_wallet_account.key,
+            borrower_credential_account,
+            clock.unix_timestamp,
+        )?;
+
+        // the vault is the lender and there is no margin kept in the pool
+        deposit_account.start_borrow(
+            *vault.to_account_info().key,
+            *borrower_wallet_account.key,
+            collection,
+            amount,
+            amount,
+            clock.unix_timestamp,
+            LoanTerms {
+                term: LoanTerm::Fixed(pool.max_loan_duration),
+                interest_model: vault
+                    .interest_model
+                    .at_utilization(vault.utilization_after(amount)),
+                service_fee_rate: pool.service_fee_rate,
+                installment_period: None,
+                min_interest_duration: pool.min_interest_duration,
+                borrow_referrer: None,
+                bid_referrer: None,
+            },
+        )?;
+
+        Exposure::open_loan(
+            ctx.program_id,
+            pool,
+            &deposit_account.get_active_state()?,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_wallet_account,
+            rent,
+            system_program,
+        )?;
+
+        let mut reputation = Reputation::ensure(
+            ctx.program_id,
+            borrower_wallet_account.key,
+            borrower_reputation_account,
+            borrower_wallet_account,
+            rent,
+            system_program,
+        )?;
+        reputation.record_borrow(amount);
+        reputation.exit(ctx.program_id)?;
+
+        pool.record_loan_started(amount);
+
+        vault.lend(amount)?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: vault_dai_account.to_account_info(),
+                    to: borrower_dai_account.to_account_info(),
+                    authority: vault.to_account_info(),
+                },
+                &[&[LendingVault::SEED, &[vault.bump_seed]]],
+            ),
+            amount - pool.origination_fee,
+        )?;
+
+        // transfer the origination fee to the treasury, the borrower still owes the full amount to the vault
+        if pool.origination_fee > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: vault_dai_account.to_account_info(),
+                        to: treasury_dai_account.to_account_info(),
+                        authority: vault.to_account_info(),
+                    },
+                    &[&[LendingVault::SEED, &[vault.bump_seed]]],
+                ),
+                pool.origination_fee,
+            )?;
+            let origination_fee = pool.origination_fee;
+            pool.accrue_fee(origination_fee);
+        }
+
+        emit!(EventVaultBorrowed {
+            borrower: *borrower_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+            amount,
+            length: pool.max_loan_duration,
+        });
+
+        Ok(())
+    }
+
+    pub fn vault_repay(ctx: Context<AccountsVaultRepay>) -> Result<()> {
+        let AccountsVaultRepay {
+            pool,
+            vault,
+            borrower_wallet_account,
+            borrower_dai_account,
+            vault_dai_account,
+            nft_mint,
+            borrower_nft_account,
+            pool_nft_account,
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+            spl_program,
+            clock,
+        } = ctx.accounts;
+
+        let loan = deposit_account.get_active_state()?;
+
+        if loan.lender != *vault.to_account_info().key {
+            throw!(liqzError::NotVaultLoan)
+        }
+
+        if clock.unix_timestamp > loan.expired_at {
+            throw!(liqzError::LoanLiquidated)
+        }
+
+        assert_eq!(&vault_dai_account.owner, vault.to_account_info().key);
+        assert_eq!(vault_dai_account.mint, pool.dai_mint);
+
+        // the NFT goes back to the borrower of this deposit only
+        assert_eq!(borrower_wallet_account.key, &loan.borrower);
+        assert_eq!(borrower_nft_account.owner, loan.borrower);
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        assert_eq!(
+            pool_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        // the whole interest accrues to the vault
+        let (interest, _) = loan.interest_and_fee(loan.interest_duration(clock.unix_timestamp));
+        let repayed_amount = loan.borrowed_amount.checked_add(interest).unwrap();
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: vault_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            repayed_amount,
+        )?;
+
+        // transfer the NFT to the borrower
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: borrower_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        vault.settle(loan.borrowed_amount, interest);
+
+        // nothing is locked for the vault, the loan is cleared right away
+        Exposure::close_loan(
+            ctx.program_id,
+            &loan,
+            collection_exposure_account,
+            borrower_exposure_account,
+        )?;
+
+        Reputation::update(
+            ctx.program_id,
+            &loan.borrower,
+            borrower_reputation_account,
+            |reputation| reputation.record_repay(pool, &loan, clock.unix_timestamp),
+        )?;
+
+        pool.record_loan_repaid(loan.borrowed_amount, interest);
+
+        deposit_account.repay(0)?;
+        deposit_account.clear()?;
+
+        emit!(EventVaultRepayed {
+            borrower: *borrower_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+            amount: repayed_amount,
+            interest,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can liquidate an expired vault loan, the NFT goes to the vault
+    pub fn vault_liquidate(ctx: Context<AccountsVaultLiquidate>) -> Result<()> {
+        let AccountsVaultLiquidate {
+            pool,
+            vault,
+            liquidator_wallet_account,
+            nft_mint,
+            appraisal_account,
+            write_off_account,
+            pool_nft_account,
+            vault_nft_account,
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        let loan = deposit_account.get_active_state()?;
+
+        if loan.lender != *vault.to_account_info().key {
+            throw!(liqzError::NotVaultLoan)
+        }
+
+        if !loan.is_defaulted(clock.unix_timestamp) {
+            throw!(liqzError::LoanNotExpired)
+        }
+
+        // the NFT has to be the one the loan was taken against
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            &loan.borrower,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        assert_eq!(
+            pool_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+        assert_eq!(
+            vault_nft_account.key,
+            &spl_associated_token_account::get_associated_token_address(
+                vault.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        // allocate the NFT ATA for the vault if not allocated
+        if !utils::is_account_allocated(vault_nft_account) {
+            utils::create_associated_token_account(
+                &vault.to_account_info(),
+                liquidator_wallet_account,
+                nft_mint,
+                vault_nft_account,
+                ata_program,
+                spl_program,
+                system_program,
+                rent,
+            )?;
+        }
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: vault_nft_account.clone(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        let (_, bump) =
+            NFTAppraisal::get_address_with_bump(ctx.program_id, nft_mint.to_account_info().key);
+        NFTAppraisal::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            bump,
+            appraisal_account.to_account_info().key,
+        )?;
+
+        // the NFT is sold later against the value booked now
+        VaultWriteOff::create(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            appraisal_account.value,
+            liquidator_wallet_account,
+            write_off_account,
+            rent,
+            system_program,
+        )?;
+        vault.write_off(loan.borrowed_amount, appraisal_account.value);
+
+        Exposure::close_loan(
+            ctx.program_id,
+            &loan,
+            collection_exposure_account,
+            borrower_exposure_account,
+        )?;
+
+        Reputation::update(
+            ctx.program_id,
+            &loan.borrower,
+            borrower_reputation_account,
+            |reputation| reputation.record_liquidation(),
+        )?;
+
+        pool.record_liquidation(loan.borrowed_amount);
+
+        deposit_account.liquidate(*vault.to_account_info().key)?;
+
+        emit!(EventVaultLiquidated {
+            loan_id: deposit_account.deposit_id,
+            mint: *nft_mint.to_account_info().key,
+            principal: loan.borrowed_amount,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can buy an NFT the vault took from a liquidated loan at its appraised value
+    pub fn vault_sell_nft(ctx: Context<AccountsVaultSellNFT>) -> Result<()> {
+        let AccountsVaultSellNFT {
+            pool,
+            vault,
+            buyer_wallet_account,
+            nft_mint,
+            appraisal_account,
+            write_off_account,
+            buyer_dai_account,
+            vault_dai_account,
+            buyer_nft_account,
+            vault_nft_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        assert_eq!(&vault_dai_account.owner, vault.to_account_info().key);
+        assert_eq!(vault_dai_account.mint, pool.dai_mint);
+        assert_eq!(
+            vault_nft_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(
+                vault.to_account_info().key,
+                nft_mint.to_account_info().key,
+            )
+        );
+
+        let (_, bump) =
+            NFTAppraisal::get_address_with_bump(ctx.program_id, nft_mint.to_account_info().key);
+        NFTAppraisal::verify_address(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            bump,
+            appraisal_account.to_account_info().key,
+        )?;
+
+        let write_off = VaultWriteOff::load(
+            ctx.program_id,
+            nft_mint.to_account_info().key,
+            write_off_account,
+        )?;
+
+        let price = appraisal_account.value;
+        if price == 0 || vault_nft_account.amount == 0 {
+            throw!(liqzError::VaultNFTNotForSale);
+        }
+
+        NFTPool::ensure_user_token_account(
+            buyer_wallet_account,
+            nft_mint,
+            buyer_nft_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: buyer_dai_account.to_account_info(),
+                    to: vault_dai_account.to_account_info(),
+                    authority: buyer_wallet_account.clone(),
+                },
+            ),
+            price,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: vault_nft_account.to_account_info(),
+                    to: buyer_nft_account.clone(),
+                    authority: vault.to_account_info(),
+                },
+                &[&[LendingVault::SEED, &[vault.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        vault.sell_nft(price, write_off.booked_value)?;
+
+        // the NFT is no longer booked, the buyer gets the rent of the write-off back
+        utils::close_account(write_off_account, buyer_wallet_account)?;
+
+        emit!(EventVaultNFTSold {
+            mint: *nft_mint.to_account_info().key,
+            buyer: *buyer_wallet_account.key,
+            price,
+        });
+
+        Ok(())
+    }
+
+    // Accounts created before versioning are too small for the current layout.
+    // The pool and the bids keep their address, they are stashed into a buffer here and restored in another transaction.
+    pub fn migrate_pool(ctx: Context<AccountsMigratePool>) -> Result<()> {
+        let AccountsMigratePool {
+            owner,
+            pool,
+            liz_mint,
+            dai_mint,
+            pool_dai_account,
+            migration_buffer,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        let legacy: LegacyNFTPool = versioning::decode_legacy::<NFTPool, _>(ctx.program_id, pool)?;
+        NFTPool::verify_address(ctx.program_id, legacy.bump_seed, pool.key)?;
+
+        if &legacy.owner != owner.key {
+            throw!(liqzError::NotAuhorized);
+        }
+        assert_eq!(liz_mint.to_account_info().key, &legacy.liz_mint);
+        assert_eq!(dai_mint.to_account_info().key, &legacy.dai_mint);
+        assert_eq!(
+            pool_dai_account.to_account_info().key,
+            &spl_associated_token_account::get_associated_token_address(pool.key, &legacy.dai_mint)
+        );
+
+        let instance = legacy.upgrade(
+            liz_mint.decimals,
+            dai_mint.decimals,
+            pool_dai_account.amount,
+        );
+
+        MigrationBuffer::stash(
+            ctx.program_id,
+            &instance,
+            pool,
+            migration_buffer,
+            owner,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventMigrated {
+            account: *pool.key,
+            from: 0,
+            to: NFTPool::VERSION,
+        });
+
+        Ok(())
+    }
+
+    pub fn restore_pool(ctx: Context<AccountsRestorePool>) -> Result<()> {
+        let AccountsRestorePool {
+            owner,
+            pool,
+            dai_mint,
+            treasury_dai_account,
+            insurance_dai_account,
+            denylist_account,
+            migration_buffer,
+            spl_program,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        let instance: NFTPool = MigrationBuffer::load(ctx.program_id, pool.key, migration_buffer)?;
+
+        if &instance.owner != owner.key {
+            throw!(liqzError::NotAuhorized);
+        }
+        assert_eq!(dai_mint.to_account_info().key, &instance.dai_mint);
+
+        MigrationBuffer::restore::<NFTPool>(
+            ctx.program_id,
+            pool,
+            &[NFTPool::SEED, &[instance.bump_seed]],
+            migration_buffer,
+            owner,
+            rent,
+            system_program,
+        )?;
+
+        // legacy pools were created without the fee accounts
+        let pool: ProgramAccount<NFTPool> = ProgramAccount::try_from(pool)?;
+        Treasury::ensure_account(
+            ctx.program_id,
+            &pool,
+            dai_mint,
+            treasury_dai_account,
+            owner,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+        InsuranceFund::ensure_account(
+            ctx.program_id,
+            &pool,
+            dai_mint,
+            insurance_dai_account,
+            owner,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+        Denylist::ensure(
+            ctx.program_id,
+            owner,
+            denylist_account,
+            rent,
+            system_program,
+        )?;
+
+        Ok(())
+    }
+
+    // Only the bidder migrates a bid, the rent of the legacy account goes back to them
+    pub fn migrate_bid(ctx: Context<AccountsMigrateBid>) -> Result<()> {
+        let AccountsMigrateBid {
+            lender_wallet_account,
+            nft_mint,
+            bid_account,
+            migration_buffer,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        let nft_mint = nft_mint.to_account_info().key;
+        let (_, bump) =
+            NFTBid::get_address_with_bump(ctx.program_id, nft_mint, lender_wallet_account.key);
+        NFTBid::verify_address(
+            ctx.program_id,
+            nft_mint,
+            lender_wallet_account.key,
+            bump,
+            bid_account.key,
+        )?;
+
+        let legacy: LegacyNFTBid =
+            versioning::decode_legacy::<NFTBid, _>(ctx.program_id, bid_account)?;
+
+        MigrationBuffer::stash(
+            ctx.program_id,
+            &legacy.upgrade(),
+            bid_account,
+            migration_buffer,
+            lender_wallet_account,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventMigrated {
+            account: *bid_account.key,
+            from: 0,
+            to: NFTBid::VERSION,
+        });
+
+        Ok(())
+    }
+
+    pub fn restore_bid(ctx: Context<AccountsMigrateBid>) -> Result<()> {
+        let AccountsMigrateBid {
+            lender_wallet_account,
+            nft_mint,
+            bid_account,
+            migration_buffer,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        let nft_mint = nft_mint.to_account_info().key;
+        let (_, bump) =
+            NFTBid::get_address_with_bump(ctx.program_id, nft_mint, lender_wallet_account.key);
+
+        MigrationBuffer::restore::<NFTBid>(
+            ctx.program_id,
+            bid_account,
+            &[
+                NFTBid::SEED,
+                &nft_mint.to_bytes(),
+                &lender_wallet_account.key.to_bytes(),
+                &[bump],
+            ],
+            migration_buffer,
+            lender_wallet_account,
+            rent,
+            system_program,
+        )?;
+
+        Ok(())
+    }
+
+    // A legacy deposit moves to the address of a new id taken from the counter of the borrower.
+    // Anyone can migrate it, the rent of the legacy account goes back to the borrower.
+    // The pool has to be migrated first.
+    pub fn migrate_deposit(ctx: Context<AccountsMigrateDeposit>) -> Result<()> {
+        let program_id = ctx.program_id;
+        let AccountsMigrateDeposit {
+            pool,
+            payer,
+            borrower_wallet_account,
+            nft_mint,
+            legacy_deposit_account,
+            deposit_account,
+            deposit_counter_account,
+            lender_wallet_account,
+            position_mint,
+            lender_position_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        } = ctx.accounts;
+
+        let nft_mint = nft_mint.to_account_info().key;
+
+        let legacy: LegacyNFTDeposit =
+            versioning::decode_legacy::<NFTDeposit, _>(program_id, legacy_deposit_account)?;
+        legacy.verify_address(
+            program_id,
+            nft_mint,
+            borrower_wallet_account.key,
+            legacy_deposit_account.key,
+        )?;
+
+        let mut counter = DepositCounter::ensure(
+            program_id,
+            borrower_wallet_account.key,
+            deposit_counter_account,
+            payer,
+            rent,
+            system_program,
+        )?;
+        let deposit_id = counter.take_id();
+        counter.exit(program_id)?;
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            program_id,
+            nft_mint,
+            borrower_wallet_account.key,
+            deposit_id,
+        );
+        NFTDeposit::verify_address(
+            program_id,
+            nft_mint,
+            borrower_wallet_account.key,
+            deposit_id,
+            bump,
+            deposit_account.key,
+        )?;
+
+        NFTDeposit::create(
+            program_id,
+            legacy.upgrade(deposit_id, *borrower_wallet_account.key, pool),
+            &[
+                NFTDeposit::SEED,
+                &nft_mint.to_bytes(),
+                &borrower_wallet_account.key.to_bytes(),
+                &deposit_id.to_le_bytes(),
+                &[bump],
+            ],
+            deposit_account,
+            payer,
+            rent,
+            system_program,
+        )?;
+
+        // the lender of a loan not settled yet is paid through a lender position from now on
+        if let Some(lender) = legacy.lender() {
+            if &lender != lender_wallet_account.key {
+                throw!(liqzError::NotLenderPositionHolder);
+            }
+
+            let position_mint = LenderPosition::create_mint(
+                program_id,
+                pool,
+                deposit_account.key,
+                position_mint,
+                payer,
+                spl_program,
+                system_program,
+                rent,
+            )?;
+
+            utils::create_associated_token_account(
+                lender_wallet_account,
+                payer,
+                &position_mint,
+                lender_position_account,
+                ata_program,
+                spl_program,
+                system_program,
+                rent,
+            )?;
+
+            LenderPosition::issue(pool, &position_mint, lender_position_account, spl_program)?;
+        }
+
+        if let Some(principal) = legacy.active_principal() {
+            pool.record_loan_started(principal);
+        }
+
+        utils::close_account(legacy_deposit_account, borrower_wallet_account)?;
+
+        emit!(EventMigrated {
+            account: *deposit_account.key,
+            from: 0,
+            to: NFTDeposit::VERSION,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AccountsInitialize<'info> {
+    #[account(signer)]
+    pub pool_owner: AccountInfo<'info>, // also the funder
+    #[account(mut)]
+    pub pool: AccountInfo<'info>, // We cannot use  ProgramAccount<'info, liqzContract> here because it is not allocated yet
+
+    pub liz_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_liz_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub tai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_tai_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_dai_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub treasury_dai_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub insurance_dai_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub denylist_account: AccountInfo<'info>, // this is not allocated yet
+    pub migration_buffer: AccountInfo<'info>, // allocated while the pool is being migrated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+#[derive(Accounts)]
+pub struct AccountsChangeLoanSetting<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only owner can change the setting
+    #[account(mut, has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsIssueCredential<'info> {
+    #[account(signer)]
+    pub authority: AccountInfo<'info>, // the credential authority of the pool
+    pub pool: ProgramAccount<'info, NFTPool>,
+    pub wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub credential_account: AccountInfo<'info>, // possibly not allocated
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRevokeCredential<'info> {
+    #[account(signer)]
+    pub authority: AccountInfo<'info>, // the credential authority of the pool
+    pub pool: ProgramAccount<'info, NFTPool>,
+    pub wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub credential_account: ProgramAccount<'info, Credential>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCreateDenylist<'info> {
+    #[account(signer)]
+    pub payer: AccountInfo<'info>,
+    #[account(mut)]
+    pub denylist_account: AccountInfo<'info>, // possibly not allocated
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsChangeDenylist<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only owner can change the denylist
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub denylist_account: ProgramAccount<'info, Denylist>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsInsurancePayout<'info> {
+    #[account(signer)]
+    pub multisig_account: ProgramAccount<'info, Multisig>, // signed by an admin action
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+
+    #[account(mut)]
+    pub insurance_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>, // owned by the lender who took the NFT
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub borrower_wallet_account: AccountInfo<'info>,
+    pub appraisal_account: ProgramAccount<'info, NFTAppraisal>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRegisterReferrer<'info> {
+    #[account(signer)]
+    pub referrer_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub referrer_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsStakeLiz<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub staker_wallet_account: AccountInfo<'info>,
+
+    pub liz_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub staker_liz_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub stake_vault_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub stake_account: AccountInfo<'info>, // possibly not allocated
+
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRequestUnstake<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub staker_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub stake_account: ProgramAccount<'info, LizStake>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsWithdrawUnstaked<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub staker_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub stake_account: ProgramAccount<'info, LizStake>,
+
+    #[account(mut)]
+    pub stake_vault_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub staker_liz_account: CpiAccount<'info, TokenAccount>,
+
+    pub spl_program: AccountInfo<'info>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPropose<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub proposer_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub proposal_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsVote<'info> {
+    #[account(signer)]
+    pub voter_wallet_account: AccountInfo<'info>,
+    pub stake_account: ProgramAccount<'info, LizStake>,
+    #[account(mut)]
+    pub proposal_account: ProgramAccount<'info, Proposal>,
+    #[account(mut)]
+    pub vote_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsExecuteProposal<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub proposal_account: ProgramAccount<'info, Proposal>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCreateMultisig<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // the single owner hands over the pool
+    #[account(mut, has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub multisig_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsChangeMultisigMembers<'info> {
+    #[account(mut, signer)]
+    pub multisig_account: ProgramAccount<'info, Multisig>, // signed by an admin action
+}
+
+#[derive(Accounts)]
+pub struct AccountsProposeAdminAction<'info> {
+    #[account(signer)]
+    pub member_wallet_account: AccountInfo<'info>,
+    pub multisig_account: ProgramAccount<'info, Multisig>,
+    #[account(mut)]
+    pub action_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsApproveAdminAction<'info> {
+    #[account(signer)]
+    pub member_wallet_account: AccountInfo<'info>,
+    pub multisig_account: ProgramAccount<'info, Multisig>,
+    #[account(mut)]
+    pub action_account: ProgramAccount<'info, AdminAction>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsExecuteAdminAction<'info> {
+    pub multisig_account: ProgramAccount<'info, Multisig>,
+    #[account(mut)]
+    pub action_account: ProgramAccount<'info, AdminAction>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsClaimFees<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only owner can claim the fees
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub destination_dai_account: CpiAccount<'info, TokenAccount>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsSweep<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only owner can sweep
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+
+    pub mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_token_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub destination_token_account: CpiAccount<'info, TokenAccount>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsDepositNFT<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub nft_metadata_account: AccountInfo<'info>, // identifies the collection, possibly not allocated
+    pub liz_mint: CpiAccount<'info, Mint>,
+    pub denylist_account: AccountInfo<'info>, // possibly not allocated
+
+    #[account(mut)]
+    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_nft_account: AccountInfo<'info>, // potentially this is not allocated yet
+
+    #[account(mut)]
+    pub borrower_liz_account: AccountInfo<'info>, // potentially this is not allocated yet
+    #[account(mut)]
+    pub pool_liz_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTDeposit>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+    #[account(mut)]
+    pub deposit_counter_account: AccountInfo<'info>, // possibly not allocated
+
+    pub rent: Sysvar<'info, Rent>,
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> AccountsDepositNFT<'info> {
+    // Shared by deposit_nft and deposit_and_borrow
+    fn deposit(&mut self, program_id: &Pubkey) -> Result<ProgramAccount<'info, NFTDeposit>> {
+        let AccountsDepositNFT {
+            pool,
+            borrower_wallet_account,
+
+            nft_mint,
+            nft_metadata_account,
+            liz_mint,
+            denylist_account,
+
+            pool_nft_account,
+            borrower_nft_account,
+
+            pool_liz_account,
+            borrower_liz_account,
+
+            deposit_account,
+            deposit_counter_account,
+
+            rent,
+
+            ata_program,
+            spl_program,
+            system_program,
+        } = self;
+
+        assert_eq!(liz_mint.to_account_info().key, &pool.liz_mint);
+        assert_eq!(pool_liz_account.mint, pool.liz_mint);
+        assert_eq!(nft_mint.decimals, 0);
+
+        Denylist::verify_allowed(
+            program_id,
+            denylist_account,
+            nft_mint.to_account_info().key,
+            &utils::get_collection(nft_mint.to_account_info().key, nft_metadata_account)?,
+        )?;
+
+        // allocate the NFT ATA for the pool if not allocated
+        NFTPool::ensure_pool_token_account(
+            pool,
+            nft_mint,
+            pool_nft_account,
+            borrower_wallet_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        // allocate the liz ATA for the user if not allocated
+        NFTPool::ensure_user_token_account(
+            borrower_wallet_account,
+            liz_mint,
+            borrower_liz_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        // the program assigns the id, the next one of the borrower
+        let mut counter = DepositCounter::ensure(
+            program_id,
+            borrower_wallet_account.key,
+            deposit_counter_account,
+            borrower_wallet_account,
+            rent,
+            system_program,
+        )?;
+        let deposit_id = counter.take_id();
+        counter.exit(program_id)?;
+
+        // create and deposit to the deposit account
+        // error out if the account exists
+        let deposit_account = NFTDeposit::deposit(
+            program_id,
+            deposit_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account,
+            deposit_account,
+            rent,
+            system_program,
+        )?;
+
+        // Transfer NFT to the pool
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_nft_account.to_account_info(),
+                    to: pool_nft_account.clone(),
+                    authority: borrower_wallet_account.clone(),
+                },
+            ),
+            1,
+        )?;
+
+        // Transfer incentive liz to the borrower
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_liz_account.to_account_info(),
+                    to: borrower_liz_account.clone(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+            ),
+            pool.incentive,
+        )?;
+
+        pool.record_deposit();
+
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        deposit_account.exit(program_id)?;
+
+        emit!(EventNFTDeposited {
+            mint: *nft_mint.to_account_info().key,
+            from: *borrower_wallet_account.key,
+            deposit_id,
+        });
+
+        Ok(deposit_account)
+    }
+}
+
+#[derive(Accounts)]
+pub struct AccountsWithdrawNFT<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPlaceBid<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub nft_metadata_account: AccountInfo<'info>, // identifies the collection, possibly not allocated
+    pub denylist_account: AccountInfo<'info>,     // possibly not allocated
+    pub lender_credential_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCloseExpiredBid<'info> {
+    #[account(signer, mut)]
+    pub closer_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub lender_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub bid_account: AccountInfo<'info>, // closed by the instruction, so it is not a ProgramAccount
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelBid<'info> {
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsStartLoan<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+    pub borrower_stake_account: AccountInfo<'info>, // possibly not allocated
+    pub borrower_credential_account: AccountInfo<'info>, // possibly not allocated
+    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_credential_account: AccountInfo<'info>, // possibly not allocated
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub nft_metadata_account: AccountInfo<'info>, // identifies the collection, possibly not allocated
+    pub denylist_account: AccountInfo<'info>,     // possibly not allocated
+
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub lender_position_account: AccountInfo<'info>, // this is not allocated yet
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub collection_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsBorrow<'info> {
+    pub loan: AccountsStartLoan<'info>,
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsBorrowWithOffer<'info> {
+    pub loan: AccountsStartLoan<'info>,
+    #[account(mut)]
+    pub nonce_account: AccountInfo<'info>, // this is not allocated yet
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPlaceAsk<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+
+    #[account(mut)]
+    pub ask_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTAsk>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelAsk<'info> {
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+
+    #[account(mut)]
+    pub ask_account: ProgramAccount<'info, NFTAsk>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsAcceptAsk<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    pub borrower_wallet_account: AccountInfo<'info>,
+    pub borrower_stake_account: AccountInfo<'info>, // possibly not allocated
+    pub borrower_credential_account: AccountInfo<'info>, // possibly not allocated
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_credential_account: AccountInfo<'info>, // possibly not allocated
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub nft_metadata_account: AccountInfo<'info>, // identifies the collection, possibly not allocated
+    pub denylist_account: AccountInfo<'info>,     // possibly not allocated
+
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub lender_position_account: AccountInfo<'info>, // this is not allocated yet
+
+    #[account(mut)]
+    pub deposit_account: AccountInfo<'info>, // loaded by the instruction so that it is persisted only once
+    #[account(mut)]
+    pub ask_account: ProgramAccount<'info, NFTAsk>,
+    #[account(mut)]
+    pub collection_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelOffer<'info> {
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub nonce_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+impl<'info> AccountsStartLoan<'info> {
+    // The bid must be placed by the lender of this loan on this NFT, and open to this borrower
+    fn fill_bid(
+        &self,
+        program_id: &Pubkey,
+        bid_account: &mut ProgramAccount<'info, NFTBid>,
+        amount: u64,
+    ) -> Result<()> {
+        let (_, bump) = NFTBid::get_address_with_bump(
+            program_id,
+            self.nft_mint.to_account_info().key,
+            self.lender_wallet_account.key,
+        );
+
+        NFTBid::verify_address(
+            program_id,
+            self.nft_mint.to_account_info().key,
+            self.lender_wallet_account.key,
+            bump,
+            bid_account.to_account_info().key,
+        )?;
+
+        bid_account.fill(
+            amount,
+            self.borrower_wallet_account.key,
+            self.deposit_account.to_account_info().key,
+            self.clock.unix_timestamp,
+        )?;
+
+        Ok(())
+    }
+
+    // Shared by every instruction that starts a loan from a lender wallet, the lender terms are checked by the caller.
+    // The payer is the signing side and pays the rent of the lender position.
+    fn start_loan(
+        &mut self,
+        program_id: &Pubkey,
+        amount: u64,
+        mut terms: LoanTerms,
+        payer: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let AccountsStartLoan {
+            pool,
+            borrower_wallet_account,
+            borrower_stake_account,
+            borrower_credential_account,
+            lender_wallet_account,
+            lender_credential_account,
+
+            nft_mint,
+            nft_metadata_account,
+            denylist_account,
+
+            treasury_dai_account,
+            pool_dai_account,
+            borrower_dai_account,
+            lender_dai_account,
+
+            position_mint,
+            lender_position_account,
+
+            deposit_account,
+            collection_exposure_account,
+            borrower_exposure_account,
+            borrower_reputation_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = self;
+
+        assert_eq!(lender_dai_account.mint, pool.dai_mint);
+        // every lender delegates to the pool, the DAI must come from the lender of this loan
+        assert_eq!(&lender_dai_account.owner, lender_wallet_account.key);
+        assert_eq!(borrower_dai_account.mint, pool.dai_mint);
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            program_id,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            deposit_account.deposit_id,
+            bump,
+            deposit_account.to_account_info().key,
+        )?;
+
+        // stakers borrow more and pay a lower service fee
+        let tier = LizStake::tier_of(
+            program_id,
+            borrower_wallet_account.key,
+            borrower_stake_account,
+            pool,
+        )?;
+        terms.service_fee_rate = pool.staked_service_fee_rate(tier);
+
+        let mut reputation = Reputation::ensure(
+            program_id,
+            borrower_wallet_account.key,
+            borrower_reputation_account,
+            payer,
+            rent,
+            system_program,
+        )?;
+
+        // set related records
+        let total_amount = amount;
+        let borrowed_amount = total_amount
+            .checked_mul(pool.borrower_mortgage_rate(tier, reputation.mortgage_boost(pool)))
+            .unwrap()
+            .checked_div(10000)
+            .unwrap();
+
+        if borrowed_amount <= pool.origination_fee {
+            throw!(liqzError::BorrowedAmountTooSmall)
+        }
+
+        Treasury::verify_account(program_id, &pool.dai_mint, treasury_dai_account)?;
+        NFTPool::verify_dai_account(pool, pool_dai_account)?;
+
+        let collection =
+            utils::get_collection(nft_mint.to_account_info().key, nft_metadata_account)?;
+
+        Denylist::verify_allowed(
+            program_id,
+            denylist_account,
+            nft_mint.to_account_info().key,
+            &collection,
+        )?;
+
+        // checked again here in case the credential of the bidder was revoked since
+        Credential::verify_allowed(
+            program_id,
+            pool,
+            borrower_wallet_account.key,
+            borrower_credential_account,
+            clock.unix_timestamp,
+        )?;
+        Credential::verify_allowed(
+            program_id,
+            pool,
+            lender_wallet_account.key,
+            lender_credential_account,
+            clock.unix_timestamp,
+        )?;
+
+        deposit_account.start_borrow(
+            *lender_wallet_account.key,
+            *borrower_wallet_account.key,
+            collection,
+            total_amount,
+            borrowed_amount,
+            clock.unix_timestamp,
+            terms,
+        )?;
+
+        Exposure::open_loan(
+            program_id,
+            pool,
+            &deposit_account.get_active_state()?,
+            collection_exposure_account,
+            borrower_exposure_account,
+            payer,
+            rent,
+            system_program,
         )?;
 
-        // charge service fee using max_borrow_duration
-        let (_, fee) =
-            pool.calculate_interest_and_fee(loan.borrowed_amount, pool.max_loan_duration);
+        reputation.record_borrow(borrowed_amount);
+        reputation.exit(program_id)?;
 
-        // transfer fee to the owner
+        pool.record_loan_started(borrowed_amount);
+        pool.lock_dai(total_amount - borrowed_amount);
+
+        // transfer DAI to the pool
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_dai_account.to_account_info(),
-                    to: pool_owner_dai_account.to_account_info(),
-                    authority: pool.to_account_info(),
+                    from: lender_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: pool.to_account_info(), // The pool is the delegate
                 },
                 &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            fee,
+            total_amount,
         )?;
 
-        let withdrawable = loan.total_amount - loan.borrowed_amount - fee;
-
-        // Transfer the remaining DAI to the lender
+        // transfer DAI to the borrower
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
                     from: pool_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
-                    authority: pool.to_account_info(),
+                    to: borrower_dai_account.to_account_info(),
+                    authority: pool.to_account_info(), // The pool is the delegate
                 },
                 &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            withdrawable,
+            borrowed_amount - pool.origination_fee,
         )?;
 
-        // allocate the NFT ATA for the lender if not allocate
-        NFTPool::ensure_user_token_account(
-            lender_wallet_account,
-            nft_mint,
-            lender_nft_account,
-            ata_program,
+        // transfer the origination fee to the treasury
+        if pool.origination_fee > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: pool_dai_account.to_account_info(),
+                        to: treasury_dai_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                ),
+                pool.origination_fee,
+            )?;
+            let origination_fee = pool.origination_fee;
+            pool.accrue_fee(origination_fee);
+        }
+
+        // create the lender position NFT of this loan
+        let position_mint = LenderPosition::create_mint(
+            program_id,
+            pool,
+            deposit_account.to_account_info().key,
+            position_mint,
+            payer,
             spl_program,
             system_program,
             rent,
         )?;
 
-        // Transfer the NFT to the lender
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: pool_nft_account.to_account_info(),
-                    to: lender_nft_account.to_account_info(),
-                    authority: pool.to_account_info(),
-                },
-            ),
-            1,
+        utils::create_associated_token_account(
+            lender_wallet_account,
+            payer,
+            &position_mint,
+            lender_position_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
         )?;
 
-        // set corresponding records
-        deposit_account.liquidate()?;
+        LenderPosition::issue(pool, &position_mint, lender_position_account, spl_program)?;
 
-        emit!(EventLiquidated {
+        emit!(EventBorrowed {
+            borrower: *borrower_wallet_account.key,
             lender: *lender_wallet_account.key,
-            loan_id: deposit_account.deposit_id,
-            withdrawable,
+            position_mint: *position_mint.to_account_info().key,
+            amount: borrowed_amount,
+            term: terms.term
         });
 
         Ok(())
     }
+}
 
-    pub fn withdraw_locked_asset(ctx: Context<AccountsWithdrawLockedAsset>) -> Result<()> {
-        let AccountsWithdrawLockedAsset {
-            pool,
-            lender_wallet_account,
-
-            lender_tai_account,
-            pool_tai_account,
-
-            lender_dai_account,
-            pool_dai_account,
+#[derive(Accounts)]
+pub struct AccountsDepositAndBorrow<'info> {
+    pub deposit: AccountsDepositNFT<'info>,
 
-            deposit_account,
+    pub borrower_stake_account: AccountInfo<'info>, // possibly not allocated
+    pub borrower_credential_account: AccountInfo<'info>, // possibly not allocated
+    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_credential_account: AccountInfo<'info>, // possibly not allocated
 
-            spl_program,
-        } = ctx.accounts;
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
 
-        let repay = deposit_account.get_repayed_state()?;
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub lender_position_account: AccountInfo<'info>, // this is not allocated yet
 
-        // Transfer the TAI to the pool
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: lender_tai_account.to_account_info(),
-                    to: pool_tai_account.to_account_info(),
-                    authority: lender_wallet_account.to_account_info(),
-                },
-            ),
-            repay.tai_required_to_unlock,
-        )?;
+    #[account(mut)]
+    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, we load it ourselves so that it is persisted only once
+    #[account(mut)]
+    pub collection_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated
 
-        // Transfer the DAI to the lender
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: pool_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
-                    authority: pool.to_account_info(),
-                },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
-            ),
-            repay.lender_withdrawable,
-        )?;
+    pub clock: Sysvar<'info, Clock>,
+}
 
-        deposit_account.clear()?;
+#[derive(Accounts)]
+pub struct AccountsCallLoan<'info> {
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_position_account: CpiAccount<'info, TokenAccount>,
 
-        emit!(EventWithDrawLockedAsset {
-            lender: *lender_wallet_account.key,
-            amount: repay.lender_withdrawable,
-        });
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
-        Ok(())
-    }
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsInitialize<'info> {
-    #[account(signer)]
-    pub pool_owner: AccountInfo<'info>, // also the funder and the fee collector
+pub struct AccountsPayInstallment<'info> {
     #[account(mut)]
-    pub pool: AccountInfo<'info>, // We cannot use  ProgramAccount<'info, liqzContract> here because it is not allocated yet
+    pub pool: ProgramAccount<'info, NFTPool>,
 
-    pub liz_mint: CpiAccount<'info, Mint>,
-    #[account(mut)]
-    pub pool_liz_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
 
-    pub tai_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub pool_tai_account: AccountInfo<'info>, // this is not allocated yet
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub lender_position_account: CpiAccount<'info, TokenAccount>, // the owner of the lender DAI account should hold the position
 
-    pub dai_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub pool_dai_account: AccountInfo<'info>, // this is not allocated yet
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
-    pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
-    pub system_program: AccountInfo<'info>,
-    pub rent: Sysvar<'info, Rent>,
-}
-#[derive(Accounts)]
-pub struct AccountsChangeLoanSetting<'info> {
-    #[account(signer)]
-    pub owner: AccountInfo<'info>, // only owner can change the setting
-    #[account(mut, has_one = owner)]
-    pub pool: ProgramAccount<'info, NFTPool>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsDepositNFT<'info> {
+pub struct AccountsRepay<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
+
     #[account(signer)]
     pub borrower_wallet_account: AccountInfo<'info>,
 
-    pub nft_mint: CpiAccount<'info, Mint>,
-    pub liz_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub insurance_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>, // holds the repayment until the lender withdraws it
 
+    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
     pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub pool_nft_account: AccountInfo<'info>, // potentially this is not allocated yet
+    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub borrower_liz_account: AccountInfo<'info>, // potentially this is not allocated yet
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
     #[account(mut)]
-    pub pool_liz_account: CpiAccount<'info, TokenAccount>,
+    pub collection_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated for older loans
 
     #[account(mut)]
-    pub deposit_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTDeposit>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+    pub spl_program: AccountInfo<'info>,
+    pub clock: Sysvar<'info, Clock>,
+}
 
-    pub rent: Sysvar<'info, Rent>,
+#[derive(Accounts)]
+pub struct AccountsLiquidate<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    #[account(mut)]
+    pub insurance_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_nft_account: AccountInfo<'info>, // Possibly not allocated
+
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>,
+    #[account(mut)]
+    pub lender_position_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub collection_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated for older loans
 
     pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
     pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsWithdrawNFT<'info> {
+pub struct AccountsWithdrawLockedAsset<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
+
     #[account(signer)]
-    pub borrower_wallet_account: AccountInfo<'info>,
+    pub lender_wallet_account: AccountInfo<'info>,
 
-    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
+    pub position_mint: AccountInfo<'info>,
     #[account(mut)]
-    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+    pub lender_position_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
@@ -855,82 +4685,155 @@ pub struct AccountsWithdrawNFT<'info> {
 }
 
 #[derive(Accounts)]
-pub struct AccountsPlaceBid<'info> {
+pub struct AccountsInitializeVault<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only pool owner can initialize the vault
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>, // funds the accounts, the owner may be the multisig which cannot pay
+
+    #[account(mut)]
+    pub vault: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub lp_mint: AccountInfo<'info>, // this is not allocated yet
+
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub vault_dai_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsChangeVaultSetting<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>, // only pool owner can change the setting
+    #[account(has_one = owner)]
     pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub vault: ProgramAccount<'info, LendingVault>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsAppraiseNFT<'info> {
     #[account(signer)]
-    pub lender_wallet_account: AccountInfo<'info>,
+    pub owner: AccountInfo<'info>, // only pool owner can appraise
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>, // funds the appraisal account, the owner may be the multisig which cannot pay
 
     pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub appraisal_account: AccountInfo<'info>, // Possibly not allocated
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
 
+#[derive(Accounts)]
+pub struct AccountsVaultDeposit<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
     #[account(mut)]
-    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+    pub vault: ProgramAccount<'info, LendingVault>,
+    #[account(signer)]
+    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_credential_account: AccountInfo<'info>, // possibly not allocated
 
+    #[account(mut)]
+    pub lp_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_lp_account: AccountInfo<'info>, // Possibly not allocated
+
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_dai_account: CpiAccount<'info, TokenAccount>,
+
+    pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
     pub system_program: AccountInfo<'info>,
     pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsCancelBid<'info> {
+pub struct AccountsVaultWithdraw<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub vault: ProgramAccount<'info, LendingVault>,
     #[account(signer)]
     pub lender_wallet_account: AccountInfo<'info>,
 
-    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub lp_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_lp_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub bid_account: ProgramAccount<'info, NFTBid>,
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_dai_account: CpiAccount<'info, TokenAccount>,
 
     pub spl_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsBorrow<'info> {
+pub struct AccountsVaultBorrow<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
-    #[account(signer)]
+    #[account(mut)]
+    pub vault: ProgramAccount<'info, LendingVault>,
+    #[account(mut, signer)]
     pub borrower_wallet_account: AccountInfo<'info>,
-    pub lender_wallet_account: AccountInfo<'info>,
 
     pub nft_mint: CpiAccount<'info, Mint>,
+    pub nft_metadata_account: AccountInfo<'info>, // identifies the collection, possibly not allocated
+    pub denylist_account: AccountInfo<'info>,     // possibly not allocated
+    pub borrower_credential_account: AccountInfo<'info>, // possibly not allocated
+    pub appraisal_account: ProgramAccount<'info, NFTAppraisal>,
 
     #[account(mut)]
-    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    pub treasury_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
     #[account(mut)]
-    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    pub vault_dai_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
     #[account(mut)]
-    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
-
+    pub collection_exposure_account: AccountInfo<'info>, // possibly not allocated
     #[account(mut)]
-    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    pub borrower_exposure_account: AccountInfo<'info>, // possibly not allocated
     #[account(mut)]
-    pub bid_account: ProgramAccount<'info, NFTBid>,
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated
 
     pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
     pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsRepay<'info> {
+pub struct AccountsVaultRepay<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
-
+    #[account(mut)]
+    pub vault: ProgramAccount<'info, LendingVault>,
     #[account(signer)]
     pub borrower_wallet_account: AccountInfo<'info>,
 
-    #[account(mut)]
-    pub pool_owner_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
     #[account(mut)]
     pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub vault_dai_account: CpiAccount<'info, TokenAccount>,
 
+    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
     pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
@@ -938,132 +4841,509 @@ pub struct AccountsRepay<'info> {
 
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
-
     #[account(mut)]
+    pub collection_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated for older loans
+
     pub spl_program: AccountInfo<'info>,
     pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsLiquidate<'info> {
+pub struct AccountsVaultLiquidate<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub vault: ProgramAccount<'info, LendingVault>,
     #[account(signer)]
-    pub lender_wallet_account: AccountInfo<'info>,
+    pub liquidator_wallet_account: AccountInfo<'info>, // anyone, also the funder of the vault NFT account
 
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub appraisal_account: ProgramAccount<'info, NFTAppraisal>, // values the NFT the vault takes
     #[account(mut)]
-    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    pub write_off_account: AccountInfo<'info>, // this is not allocated yet
     #[account(mut)]
-    pub pool_owner_dai_account: CpiAccount<'info, TokenAccount>,
+    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub vault_nft_account: AccountInfo<'info>, // Possibly not allocated
 
-    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
     #[account(mut)]
-    pub lender_nft_account: AccountInfo<'info>, // Possibly not allocated
+    pub collection_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_exposure_account: AccountInfo<'info>, // not allocated for loans opened before the caps
+    #[account(mut)]
+    pub borrower_reputation_account: AccountInfo<'info>, // possibly not allocated for older loans
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
 
+#[derive(Accounts)]
+pub struct AccountsVaultSellNFT<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
     #[account(mut)]
-    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    pub vault: ProgramAccount<'info, LendingVault>,
+    #[account(mut, signer)]
+    pub buyer_wallet_account: AccountInfo<'info>, // also the funder of the buyer NFT account
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub appraisal_account: ProgramAccount<'info, NFTAppraisal>,
     #[account(mut)]
-    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+    pub write_off_account: AccountInfo<'info>, // closed by the sale
 
     #[account(mut)]
-    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    pub buyer_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub vault_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub buyer_nft_account: AccountInfo<'info>, // Possibly not allocated
+    #[account(mut)]
+    pub vault_nft_account: CpiAccount<'info, TokenAccount>,
 
     pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
     pub system_program: AccountInfo<'info>,
     pub rent: Sysvar<'info, Rent>,
-    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsWithdrawLockedAsset<'info> {
-    pub pool: ProgramAccount<'info, NFTPool>,
+pub struct AccountsMigratePool<'info> {
+    #[account(signer, mut)]
+    pub owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub pool: AccountInfo<'info>, // the legacy pool, the layout is checked by the instruction
 
-    #[account(signer)]
+    pub liz_mint: CpiAccount<'info, Mint>,
+    pub dai_mint: CpiAccount<'info, Mint>,
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>, // all of it is locked for the lenders
+
+    #[account(mut)]
+    pub migration_buffer: AccountInfo<'info>, // this is not allocated yet
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRestorePool<'info> {
+    #[account(signer, mut)]
+    pub owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub pool: AccountInfo<'info>, // closed by migrate_pool, not allocated
+
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub treasury_dai_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub insurance_dai_account: AccountInfo<'info>, // possibly not allocated
+    #[account(mut)]
+    pub denylist_account: AccountInfo<'info>, // possibly not allocated
+
+    #[account(mut)]
+    pub migration_buffer: AccountInfo<'info>,
+
+    pub spl_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsMigrateBid<'info> {
+    #[account(signer, mut)]
     pub lender_wallet_account: AccountInfo<'info>,
 
+    pub nft_mint: CpiAccount<'info, Mint>,
+
     #[account(mut)]
-    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    pub bid_account: AccountInfo<'info>, // the legacy bid when migrating, not allocated when restoring
     #[account(mut)]
-    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+    pub migration_buffer: AccountInfo<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: AccountInfo<'info>,
+}
 
+#[derive(Accounts)]
+pub struct AccountsMigrateDeposit<'info> {
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub payer: AccountInfo<'info>,
     #[account(mut)]
-    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    pub borrower_wallet_account: AccountInfo<'info>, // gets the rent of the legacy deposit back
+
+    pub nft_mint: CpiAccount<'info, Mint>,
 
     #[account(mut)]
-    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    pub legacy_deposit_account: AccountInfo<'info>, // the layout is checked by the instruction
+    #[account(mut)]
+    pub deposit_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub deposit_counter_account: AccountInfo<'info>, // possibly not allocated
+
+    pub lender_wallet_account: AccountInfo<'info>, // the lender of a loan not settled yet, unused otherwise
+    #[account(mut)]
+    pub position_mint: AccountInfo<'info>, // this is not allocated yet, unused without a lender
+    #[account(mut)]
+    pub lender_position_account: AccountInfo<'info>, // this is not allocated yet, unused without a lender
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[error]
+pub enum liqzError {
+    #[msg("Not Authorized")]
+    NotAuhorized = 0,
+    #[msg("Contract address not correct")]
+    ContractAddressNotCorrect,
+
+    #[msg("NFT listing address not correct")]
+    NFTListingAddressNotCorrect,
+
+    #[msg("NFT bid address not correct")]
+    NFTBidAddressNotCorrect,
+
+    #[msg("NFT loan address not correct")]
+    NFTLoanAddressNotCorrect,
+
+    #[msg("NFT overdrawn")]
+    NFTOverdrawn,
+
+    #[msg("Empty NFT Reserve")]
+    EmptyNFTReserve,
+
+    #[msg("NFT overtrade")]
+    NFTOvertrade,
+
+    #[msg("NFT bid qty larger than NFT supply")]
+    NFTBidQtyLargerThanSupply,
+
+    #[msg("NFT borrow amount larger than bid amount")]
+    NFTBorrowExceedBidAmount,
+
+    #[msg("NFT borrow already started")]
+    BorrowAlreadyStarted,
+
+    #[msg("Loan is liquidated")]
+    LoanLiquidated,
+
+    #[msg("Loan is not expired yet")]
+    LoanNotExpired,
+
+    #[msg("Loan record already exist")]
+    LoanAlreadyExist,
+
+    #[msg("Loan already finalized")]
+    LoanFinialized,
+
+    #[msg("Loan is not active")]
+    LoanNotActive,
+
+    #[msg("Not enough NFT in pool")]
+    NotEnoughNFTInPool,
+
+    #[msg("NFT already withdrawn")]
+    NFTAlreadyWithdrawn,
+
+    #[msg("NFT is locked")]
+    NFTLocked,
+
+    #[msg("The borrowed amount is too small")]
+    BorrowedAmountTooSmall,
+
+    #[msg("Loan has not been repayed")]
+    LoanNotRepayed,
+
+    #[msg("Lender position address not correct")]
+    LenderPositionAddressNotCorrect,
+
+    #[msg("Lender position already exist")]
+    LenderPositionAlreadyExist,
+
+    #[msg("Not the holder of the lender position")]
+    NotLenderPositionHolder,
+
+    #[msg("Lending vault address not correct")]
+    LendingVaultAddressNotCorrect,
+
+    #[msg("NFT appraisal address not correct")]
+    NFTAppraisalAddressNotCorrect,
+
+    #[msg("Vault borrow amount larger than the LTV allows")]
+    VaultBorrowExceedLTV,
+
+    #[msg("Not enough DAI in the vault")]
+    VaultInsufficientLiquidity,
+
+    #[msg("The vault deposit is too small to get a share")]
+    VaultDepositTooSmall,
+
+    #[msg("Loan is not lent by the vault")]
+    NotVaultLoan,
+
+    #[msg("The vault lost all its assets, deposits would be diluted by the existing shares")]
+    VaultInsolvent,
+
+    #[msg("The vault does not hold the NFT or the NFT is not appraised")]
+    VaultNFTNotForSale,
+
+    #[msg("Vault write-off address not correct")]
+    VaultWriteOffAddressNotCorrect,
+
+    #[msg("Offer signature is not verified")]
+    OfferSignatureNotVerified,
+
+    #[msg("Offer does not cover this NFT")]
+    OfferTargetMismatch,
+
+    #[msg("Offer expired")]
+    OfferExpired,
+
+    #[msg("Offer duration is not allowed by the pool")]
+    OfferDurationNotAllowed,
+
+    #[msg("Offer nonce already used")]
+    OfferNonceUsed,
+
+    #[msg("Offer nonce address not correct")]
+    OfferNonceAddressNotCorrect,
+
+    #[msg("NFT ask address not correct")]
+    NFTAskAddressNotCorrect,
+
+    #[msg("No ask to accept")]
+    AskNotAvailable,
+
+    #[msg("Ask terms changed")]
+    AskTermsChanged,
+
+    #[msg("Ask duration is not allowed by the pool")]
+    AskDurationNotAllowed,
+
+    #[msg("Ask interest rate not allowed")]
+    AskInterestRateNotAllowed,
+
+    #[msg("The bid is restricted to another borrower")]
+    BidRestricted,
+
+    #[msg("The bid has expired")]
+    BidExpired,
+
+    #[msg("The bid has not expired")]
+    BidNotExpired,
+
+    #[msg("Call notice is not allowed by the pool")]
+    CallNoticeNotAllowed,
+
+    #[msg("Only open-ended loans can be called")]
+    LoanNotCallable,
+
+    #[msg("Loan already called")]
+    LoanAlreadyCalled,
+
+    #[msg("Loan has not been called")]
+    LoanNotCalled,
+
+    #[msg("Installment period is not allowed by the pool")]
+    InstallmentPeriodNotAllowed,
+
+    #[msg("Loan is not paid in installments")]
+    LoanNotInstallment,
+
+    #[msg("All installments are paid")]
+    InstallmentsFullyPaid,
+
+    #[msg("Treasury address not correct")]
+    TreasuryAddressNotCorrect,
+
+    #[msg("Referrer address not correct")]
+    ReferrerAddressNotCorrect,
+
+    #[msg("Referrer already registered")]
+    ReferrerAlreadyRegistered,
+
+    #[msg("Referrer not registered")]
+    ReferrerNotRegistered,
+
+    #[msg("Cannot refer yourself")]
+    SelfReferral,
+
+    #[msg("Referral fee rate too high")]
+    ReferralFeeRateTooHigh,
+
+    #[msg("Insurance fund address not correct")]
+    InsuranceFundAddressNotCorrect,
+
+    #[msg("The referrers and the insurance fund take more than the fee")]
+    FeeSharesTooHigh,
+
+    #[msg("Loan not liquidated")]
+    LoanNotLiquidated,
+
+    #[msg("Loan already compensated")]
+    LoanAlreadyCompensated,
+
+    #[msg("The NFT is worth no less than the principal")]
+    NoShortfall,
+
+    #[msg("The LIZ stake account address is not correct")]
+    LizStakeAddressNotCorrect,
+
+    #[msg("Cannot unstake more than staked")]
+    UnstakeExceedStake,
+
+    #[msg("There is no unstaked LIZ to withdraw")]
+    NothingToWithdraw,
+
+    #[msg("The unstaked LIZ is still cooling down")]
+    UnstakeCoolingDown,
+
+    #[msg("The stake settings are not allowed")]
+    StakeSettingsNotAllowed,
+
+    #[msg("The proposal address is not correct")]
+    ProposalAddressNotCorrect,
+
+    #[msg("The proposal already exists")]
+    ProposalAlreadyExists,
+
+    #[msg("The proposal vote address is not correct")]
+    ProposalVoteAddressNotCorrect,
+
+    #[msg("Already voted on the proposal")]
+    AlreadyVoted,
+
+    #[msg("No LIZ is staked")]
+    NothingStaked,
+
+    #[msg("The stake changed more than once since the proposal was created")]
+    StakedAfterProposal,
+
+    #[msg("The voting has ended")]
+    VotingEnded,
+
+    #[msg("The voting has not ended yet")]
+    VotingNotEnded,
+
+    #[msg("The proposal has already been executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Not enough votes for the proposal to pass")]
+    QuorumNotReached,
+
+    #[msg("The proposal is rejected")]
+    ProposalRejected,
+
+    #[msg("The governance settings are not allowed")]
+    GovernanceSettingsNotAllowed,
+
+    #[msg("Loan settings out of range")]
+    LoanSettingsNotAllowed,
+
+    #[msg("The multisig address is not correct")]
+    MultisigAddressNotCorrect,
+
+    #[msg("The multisig members or threshold are not allowed")]
+    MultisigSettingsNotAllowed,
+
+    #[msg("Not a member of the multisig")]
+    NotMultisigMember,
+
+    #[msg("The admin action address is not correct")]
+    AdminActionAddressNotCorrect,
+
+    #[msg("The admin action already exists")]
+    AdminActionAlreadyExists,
+
+    #[msg("The admin action has already been executed")]
+    AdminActionAlreadyExecuted,
+
+    #[msg("The multisig members changed after the admin action was proposed")]
+    MultisigMembersChanged,
+
+    #[msg("The admin action does not have enough approvals")]
+    NotEnoughApprovals,
+
+    #[msg("The exposure account address is not correct")]
+    ExposureAddressNotCorrect,
+
+    #[msg("The loan would exceed the cap of the collection")]
+    CollectionCapExceeded,
 
-    pub spl_program: AccountInfo<'info>,
-}
+    #[msg("The loan would exceed the cap of the borrower")]
+    BorrowerCapExceeded,
 
-#[error]
-pub enum liqzError {
-    #[msg("Not Authorized")]
-    NotAuhorized = 0,
-    #[msg("Contract address not correct")]
-    ContractAddressNotCorrect,
+    #[msg("The denylist address is not correct")]
+    DenylistAddressNotCorrect,
 
-    #[msg("NFT listing address not correct")]
-    NFTListingAddressNotCorrect,
+    #[msg("The denylist is full")]
+    DenylistFull,
 
-    #[msg("NFT bid address not correct")]
-    NFTBidAddressNotCorrect,
+    #[msg("The NFT or its collection is on the denylist")]
+    AssetDenied,
 
-    #[msg("NFT loan address not correct")]
-    NFTLoanAddressNotCorrect,
+    #[msg("The credential account address is not correct")]
+    CredentialAddressNotCorrect,
 
-    #[msg("NFT overdrawn")]
-    NFTOverdrawn,
+    #[msg("Only the credential authority of the pool can do this")]
+    NotCredentialAuthority,
 
-    #[msg("Empty NFT Reserve")]
-    EmptyNFTReserve,
+    #[msg("The pool is private and the wallet has no credential")]
+    CredentialMissing,
 
-    #[msg("NFT overtrade")]
-    NFTOvertrade,
+    #[msg("The credential is revoked")]
+    CredentialRevoked,
 
-    #[msg("NFT bid qty larger than NFT supply")]
-    NFTBidQtyLargerThanSupply,
+    #[msg("The credential is expired")]
+    CredentialExpired,
 
-    #[msg("NFT borrow amount larger than bid amount")]
-    NFTBorrowExceedBidAmount,
+    #[msg("The reputation account address is not correct")]
+    ReputationAddressNotCorrect,
 
-    #[msg("NFT borrow already started")]
-    BorrowAlreadyStarted,
+    #[msg("The reputation settings are not allowed")]
+    ReputationSettingsNotAllowed,
 
-    #[msg("Loan is liquidated")]
-    LoanLiquidated,
+    #[msg("Only a pool ATA can be swept")]
+    SweepAccountNotCorrect,
 
-    #[msg("Loan is not expired yet")]
-    LoanNotExpired,
+    #[msg("NFTs cannot be swept")]
+    SweepNotAllowed,
 
-    #[msg("Loan record already exist")]
-    LoanAlreadyExist,
+    #[msg("The pool releases more DAI than it has locked")]
+    DaiLockedUnderflow,
 
-    #[msg("Loan already finalized")]
-    LoanFinialized,
+    #[msg("The pool DAI account must be the DAI ATA of the pool")]
+    PoolDaiAccountNotCorrect,
 
-    #[msg("Loan is not active")]
-    LoanNotActive,
+    #[msg("The account is already at the current version")]
+    AccountUpToDate,
 
-    #[msg("Not enough NFT in pool")]
-    NotEnoughNFTInPool,
+    #[msg("The account was created before versioning and must be reallocated to migrate")]
+    MigrationNeedsRealloc,
 
-    #[msg("NFT already withdrawn")]
-    NFTAlreadyWithdrawn,
+    #[msg("MigrationBuffer address is not correct")]
+    MigrationBufferAddressNotCorrect,
 
-    #[msg("NFT is locked")]
-    NFTLocked,
+    #[msg("The account was not stashed by a migration")]
+    MigrationNotStarted,
 
-    #[msg("The borrowed amount is too small")]
-    BorrowedAmountTooSmall,
+    #[msg("The account is being migrated and must be restored first")]
+    MigrationInProgress,
 
-    #[msg("Loan has not been repayed")]
-    LoanNotRepayed,
+    #[msg("DepositCounter address is not correct")]
+    DepositCounterAddressNotCorrect,
 }
 
 impl liqzError {
@@ -1082,10 +5362,14 @@ pub struct EventInitialized {
 #[derive(Debug)]
 pub struct EventLoanSettingChanged {
     incentive: u64,
-    interest_rate: u64,
+    interest_model: InterestModel,
     service_fee_rate: u64,
     max_loan_duration: i64,
     mortgage_rate: u64,
+    min_interest_duration: i64,
+    origination_fee: u64,
+    referral_fee_rate: u64,
+    insurance_fee_rate: u64,
 }
 
 #[event]
@@ -1093,6 +5377,7 @@ pub struct EventLoanSettingChanged {
 pub struct EventNFTDeposited {
     mint: Pubkey,
     from: Pubkey,
+    deposit_id: u64,
 }
 
 #[event]
@@ -1125,8 +5410,9 @@ pub struct EventNFTBidCancelled {
 pub struct EventBorrowed {
     borrower: Pubkey,
     lender: Pubkey,
+    position_mint: Pubkey,
     amount: u64,
-    length: i64,
+    term: LoanTerm,
 }
 
 #[event]
@@ -1137,14 +5423,18 @@ pub struct EventRepayed {
     amount: u64,
     fee: u64,
     lender_income: u64,
+    referral_fee: u64,
+    insurance_fee: u64,
 }
 
 #[event]
 #[derive(Debug)]
 pub struct EventLiquidated {
     lender: Pubkey,
-    loan_id: Pubkey,
+    loan_id: u64,
     withdrawable: u64,
+    referral_fee: u64,
+    insurance_fee: u64,
 }
 
 #[event]
@@ -1153,3 +5443,330 @@ pub struct EventWithDrawLockedAsset {
     lender: Pubkey,
     amount: u64,
 }
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultInitialized {
+    account: Pubkey,
+    lp_mint: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultSettingChanged {
+    ltv: u64,
+    interest_model: InterestModel,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventNFTAppraised {
+    mint: Pubkey,
+    value: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultDeposited {
+    lender: Pubkey,
+    amount: u64,
+    shares: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultWithdrawn {
+    lender: Pubkey,
+    amount: u64,
+    shares: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultBorrowed {
+    borrower: Pubkey,
+    loan_id: u64,
+    amount: u64,
+    length: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultRepayed {
+    borrower: Pubkey,
+    loan_id: u64,
+    amount: u64,
+    interest: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultLiquidated {
+    loan_id: u64,
+    mint: Pubkey,
+    principal: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVaultNFTSold {
+    mint: Pubkey,
+    buyer: Pubkey,
+    price: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventOfferTaken {
+    lender: Pubkey,
+    nonce: u64,
+    loan_id: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventOfferCancelled {
+    lender: Pubkey,
+    nonce: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventNFTAskPlaced {
+    mint: Pubkey,
+    from: Pubkey,
+    loan_id: u64,
+    amount: u64,
+    interest_rate: u64,
+    duration: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventNFTAskCancelled {
+    mint: Pubkey,
+    from: Pubkey,
+    loan_id: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventNFTAskAccepted {
+    mint: Pubkey,
+    lender: Pubkey,
+    loan_id: u64,
+    amount: u64,
+    interest_rate: u64,
+    duration: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventNFTBidClosed {
+    mint: Pubkey,
+    from: Pubkey,
+    closer: Pubkey,
+    bounty: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLoanCalled {
+    loan_id: u64,
+    lender: Pubkey,
+    expired_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLoanCallCancelled {
+    loan_id: u64,
+    lender: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventInstallmentPaid {
+    loan_id: u64,
+    borrower: Pubkey,
+    lender: Pubkey,
+    fee: u64,
+    lender_income: u64,
+    paid_until: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventFeesClaimed {
+    mint: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventReferrerRegistered {
+    referrer: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventInsurancePaidOut {
+    loan_id: u64,
+    lender: Pubkey,
+    appraised_value: u64,
+    shortfall: u64,
+    amount: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventStakeSettingChanged {
+    stake_tiers: [StakeTier; 3],
+    max_mortgage_rate: u64,
+    unstake_cooldown: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLizStaked {
+    staker: Pubkey,
+    amount: u64,
+    staked: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventUnstakeRequested {
+    staker: Pubkey,
+    amount: u64,
+    unstake_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventUnstakeWithdrawn {
+    staker: Pubkey,
+    amount: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventGovernanceSettingChanged {
+    proposal_voting_period: i64,
+    proposal_quorum: u64,
+    proposal_threshold: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventProposalCreated {
+    proposal: Pubkey,
+    proposer: Pubkey,
+    settings: LoanSettings,
+    voting_ends_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventVoted {
+    proposal: Pubkey,
+    voter: Pubkey,
+    support: bool,
+    weight: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventProposalExecuted {
+    proposal: Pubkey,
+    votes_for: u64,
+    votes_against: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventMultisigChanged {
+    members: Vec<Pubkey>,
+    threshold: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventAdminActionProposed {
+    action: Pubkey,
+    proposer: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventAdminActionApproved {
+    action: Pubkey,
+    member: Pubkey,
+    approvals: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventAdminActionExecuted {
+    action: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventRiskCapsChanged {
+    max_collection_principal: u64,
+    max_borrower_principal: u64,
+    max_borrower_open_loans: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventDenylistChanged {
+    asset: Pubkey,
+    denied: bool,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventCredentialAuthorityChanged {
+    credential_authority: Option<Pubkey>,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventCredentialIssued {
+    wallet: Pubkey,
+    expires_at: Option<i64>,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventCredentialRevoked {
+    wallet: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventReputationSettingsChanged {
+    reputation_min_repaid: u64,
+    reputation_mortgage_boost: u64,
+    reputation_min_principal: u64,
+    reputation_min_duration: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventSwept {
+    mint: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventMigrated {
+    account: Pubkey,
+    from: u8,
+    to: u8,
+}
@@ -0,0 +1,316 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
+
+use crate::{utils, DerivedAccountIdentifier, LizStake, NFTPool, liqzError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use borsh::{BorshDeserialize, BorshSerialize};
+use fehler::{throw, throws};
+
+static STAKE_VAULT_SEED: &[u8] = b"liqzLizStakeVault";
+
+// Borrowers staking at least `min_stake` LIZ get a cheaper service fee and can borrow more against their NFTs
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct StakeTier {
+    pub min_stake: u64,
+    pub fee_discount: u64,   // in bp of the service fee rate
+    pub mortgage_boost: u64, // in bp, added to the mortgage rate up to the pool cap
+}
+
+impl DerivedAccountIdentifier for LizStake {
+    const SEED: &'static [u8] = b"liqzLizStake";
+}
+
+impl LizStake {
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        wallet: &AccountInfo<'info>,
+        stake_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, wallet.key);
+
+        Self::verify_address(program_id, wallet.key, bump, stake_account.key)?;
+
+        if !utils::is_program_account(stake_account, program_id) {
+            let instance = LizStake {
+                amount: 0,
+                changed_at: 0,
+                previous_amount: 0,
+                previous_changed_at: 0,
+                unstaking: 0,
+                unstake_at: 0,
+            };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &wallet.key.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                wallet,
+                seeds_with_bump,
+                stake_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = stake_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(stake_account)?
+    }
+
+    // The vault holding all the staked LIZ, created by the first staker
+    #[throws(ProgramError)]
+    pub fn ensure_vault<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        liz_mint: &CpiAccount<'info, Mint>,
+        stake_vault_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        let (_, bump) = Self::get_vault_address_with_bump(program_id);
+
+        Self::verify_vault_address(program_id, bump, stake_vault_account.key)?;
+
+        if !utils::is_program_account(stake_vault_account, &spl_token::id()) {
+            utils::create_derived_token_account_with_seed(
+                funder,
+                &[STAKE_VAULT_SEED, &[bump]],
+                stake_vault_account,
+                liz_mint,
+                &pool.to_account_info(),
+                rent,
+                spl_program,
+                system_program,
+            )?;
+        }
+    }
+
+    // The stake tier of a borrower, the stake account may not be allocated if the borrower never staked
+    #[throws(ProgramError)]
+    pub fn tier_of(
+        program_id: &Pubkey,
+        wallet: &Pubkey,
+        stake_account: &AccountInfo,
+        pool: &NFTPool,
+    ) -> Option<StakeTier> {
+        if stake_account.key != &Self::get_address(program_id, wallet) {
+            throw!(liqzError::LizStakeAddressNotCorrect);
+        }
+
+        if !utils::is_program_account(stake_account, program_id) {
+            return None;
+        }
+
+        let stake: ProgramAccount<LizStake> = ProgramAccount::try_from(stake_account)?;
+
+        pool.stake_tiers
+            .iter()
+            .filter(|tier| tier.min_stake > 0 && stake.amount >= tier.min_stake)
+            .max_by_key(|tier| tier.min_stake)
+            .copied()
+    }
+
+    pub fn stake(&mut self, amount: u64, now: UnixTimestamp) {
+        self.set_amount(self.amount.checked_add(amount).unwrap(), now);
+    }
+
+    // Unstaked LIZ stops counting towards the tier right away and can be withdrawn after the cooldown
+    #[throws(liqzError)]
+    pub fn request_unstake(&mut self, amount: u64, now: UnixTimestamp, cooldown: i64) {
+        if amount > self.amount {
+            throw!(liqzError::UnstakeExceedStake)
+        }
+
+        self.set_amount(self.amount - amount, now);
+        self.unstaking = self.unstaking.checked_add(amount).unwrap();
+        self.unstake_at = now.checked_add(cooldown).unwrap();
+    }
+
+    // Keeps the amount held before the change as a checkpoint, changes within the same second are merged
+    fn set_amount(&mut self, amount: u64, now: UnixTimestamp) {
+        if now != self.changed_at {
+            self.previous_amount = self.amount;
+            self.previous_changed_at = self.changed_at;
+            self.changed_at = now;
+        }
+        self.amount = amount;
+    }
+
+    // The amount staked right before `time`, None if it changed twice since then
+    pub fn amount_before(&self, time: UnixTimestamp) -> Option<u64> {
+        if self.changed_at < time {
+            Some(self.amount)
+        } else if self.previous_changed_at < time {
+            Some(self.previous_amount)
+        } else {
+            None
+        }
+    }
+
+    #[throws(liqzError)]
+    pub fn withdraw(&mut self, now: UnixTimestamp) -> u64 {
+        if self.unstaking == 0 {
+            throw!(liqzError::NothingToWithdraw)
+        }
+
+        if now < self.unstake_at {
+            throw!(liqzError::UnstakeCoolingDown)
+        }
+
+        let amount = self.unstaking;
+        self.unstaking = 0;
+        amount
+    }
+
+    // An program derived account that stores the LIZ stake of a wallet
+    // The address of the account is computed as follow:
+    // address = find_program_address([LizStake::SEED, wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, wallet: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, wallet).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &wallet.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, wallet: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr =
+            Pubkey::create_program_address(&[Self::SEED, &wallet.to_bytes(), &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::LizStakeAddressNotCorrect);
+        }
+    }
+
+    // The stake vault should have address find_program_address(&[STAKE_VAULT_SEED], program_id)
+    // the owner of the token account is the pool
+    pub fn get_vault_address(program_id: &Pubkey) -> Pubkey {
+        Self::get_vault_address_with_bump(program_id).0
+    }
+
+    pub(crate) fn get_vault_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_VAULT_SEED], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_vault_address(program_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(&[STAKE_VAULT_SEED, &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::LizStakeAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use solana_program::system_instruction::SystemInstruction;
+
+    fn new_stake() -> LizStake {
+        LizStake {
+            amount: 0,
+            changed_at: 0,
+            previous_amount: 0,
+            previous_changed_at: 0,
+            unstaking: 0,
+            unstake_at: 0,
+        }
+    }
+
+    #[test]
+    fn top_up_keeps_the_older_stake_for_older_proposals() {
+        let mut stake = new_stake();
+        stake.stake(100, 10);
+        stake.stake(50, 30);
+
+        assert_eq!(stake.amount_before(20), Some(100));
+        assert_eq!(stake.amount_before(31), Some(150));
+        assert_eq!(stake.amount_before(5), None);
+    }
+
+    #[test]
+    fn changes_in_the_same_second_are_merged() {
+        let mut stake = new_stake();
+        stake.stake(100, 10);
+        stake.stake(50, 30);
+        stake.request_unstake(20, 30, 100).unwrap();
+
+        assert_eq!(stake.amount_before(20), Some(100));
+        assert_eq!(stake.amount_before(31), Some(130));
+    }
+
+    #[test]
+    fn two_changes_after_a_proposal_lose_the_vote() {
+        let mut stake = new_stake();
+        stake.stake(100, 10);
+        stake.stake(50, 30);
+        stake.request_unstake(20, 40, 100).unwrap();
+
+        assert_eq!(stake.amount_before(20), None);
+        assert_eq!(stake.amount_before(35), Some(150));
+    }
+
+    #[test]
+    fn unstake_more_than_staked_fails() {
+        let mut stake = new_stake();
+        stake.stake(100, 10);
+
+        assert!(stake.request_unstake(101, 20, 100).is_err());
+        assert_eq!(stake.amount, 100);
+    }
+
+    #[test]
+    fn stake_account_funded_by_someone_else_is_still_created() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let wallet = test_utils::wallet();
+        let stake_account =
+            test_utils::funded_address(LizStake::get_address(&program_id, wallet.key));
+
+        // a wallet that never staked has no tier, whatever was sent to the address
+        assert_eq!(
+            LizStake::tier_of(&program_id, wallet.key, &stake_account, &test_utils::pool())
+                .unwrap(),
+            None
+        );
+
+        let stake = LizStake::ensure(
+            &program_id,
+            &wallet,
+            &stake_account,
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+        assert_eq!(stake.amount, 0);
+
+        let instructions = test_utils::system_instructions(&test_utils::invoked());
+        assert!(matches!(
+            instructions.as_slice(),
+            [
+                SystemInstruction::Transfer { .. },
+                SystemInstruction::Allocate { .. },
+                SystemInstruction::Assign { .. },
+            ]
+        ));
+    }
+}
@@ -0,0 +1,367 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
+use solana_program::sysvar::instructions;
+
+use crate::{utils, DerivedAccountIdentifier, LoanOfferNonce, liqzError};
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use fehler::{throw, throws};
+
+// The ed25519 native program, solana-program 1.7 does not export its id yet
+mod ed25519_program {
+    solana_program::declare_id!("Ed25519SigVerify111111111111111111111111111");
+}
+
+static OFFER_DOMAIN: &[u8] = b"liqzLoanOffer";
+
+// Size of one entry of signature offsets in the ed25519 instruction data
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+const SIGNATURE_OFFSETS_START: usize = 2;
+const PUBKEY_LEN: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OfferTarget {
+    Mint(Pubkey),       // any NFT of this mint
+    Collection(Pubkey), // any NFT whose first verified creator is this address
+}
+
+// A loan offer the lender signs off-chain. The borrower submits it together with
+// an ed25519 instruction that verifies the lender signature over `message(program_id, pool)`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct LoanOffer {
+    pub lender: Pubkey,
+    pub target: OfferTarget,
+    pub price: u64,                // max amount of dai the lender is willing to lend
+    pub duration: i64,             // loan duration, secs
+    pub expires_at: UnixTimestamp, // the offer cannot be taken after this time
+    pub nonce: u64,                // each nonce can only be used once per lender
+}
+
+impl LoanOffer {
+    // Binds the offer to this program and pool so that it cannot be replayed on another deployment
+    pub fn message(&self, program_id: &Pubkey, pool: &Pubkey) -> Vec<u8> {
+        let mut message = OFFER_DOMAIN.to_vec();
+        message.extend(program_id.as_ref());
+        message.extend(pool.as_ref());
+        message.extend(self.try_to_vec().unwrap());
+        message
+    }
+
+    // The instruction right before the current one must be an ed25519 instruction
+    // that verifies exactly one signature of the lender over this offer.
+    #[throws(ProgramError)]
+    pub fn verify_signature(
+        &self,
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        instructions_sysvar: &AccountInfo,
+    ) {
+        if !instructions::check_id(instructions_sysvar.key) {
+            throw!(ProgramError::InvalidArgument);
+        }
+
+        let data = instructions_sysvar.try_borrow_data()?;
+        let current = instructions::load_current_index(&data);
+        if current == 0 {
+            throw!(liqzError::OfferSignatureNotVerified);
+        }
+
+        let ix = instructions::load_instruction_at(current as usize - 1, &data)
+            .map_err(|_| liqzError::OfferSignatureNotVerified)?;
+
+        if !ed25519_program::check_id(&ix.program_id) || !ix.accounts.is_empty() {
+            throw!(liqzError::OfferSignatureNotVerified);
+        }
+
+        let ix_data = &ix.data;
+        if ix_data.len() < SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN || ix_data[0] != 1 {
+            throw!(liqzError::OfferSignatureNotVerified);
+        }
+
+        let offsets =
+            &ix_data[SIGNATURE_OFFSETS_START..SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN];
+        let read = |i: usize| u16::from_le_bytes([offsets[i * 2], offsets[i * 2 + 1]]);
+        let (sig_ix, pubkey_offset, pubkey_ix) = (read(1), read(2) as usize, read(3));
+        let (msg_offset, msg_size, msg_ix) = (read(4) as usize, read(5) as usize, read(6));
+
+        // All the data must live in the ed25519 instruction itself, otherwise it is not what we read below
+        if sig_ix != u16::MAX || pubkey_ix != u16::MAX || msg_ix != u16::MAX {
+            throw!(liqzError::OfferSignatureNotVerified);
+        }
+
+        let pubkey = ix_data
+            .get(pubkey_offset..pubkey_offset + PUBKEY_LEN)
+            .ok_or(liqzError::OfferSignatureNotVerified)?;
+        let message = ix_data
+            .get(msg_offset..msg_offset + msg_size)
+            .ok_or(liqzError::OfferSignatureNotVerified)?;
+
+        if pubkey != self.lender.as_ref() || message != self.message(program_id, pool).as_slice() {
+            throw!(liqzError::OfferSignatureNotVerified);
+        }
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_target(&self, nft_mint: &Pubkey, nft_metadata: &AccountInfo) {
+        match self.target {
+            OfferTarget::Mint(mint) => {
+                if &mint != nft_mint {
+                    throw!(liqzError::OfferTargetMismatch);
+                }
+            }
+            OfferTarget::Collection(creator) => {
+                if utils::get_verified_creator(nft_mint, nft_metadata)? != Some(creator) {
+                    throw!(liqzError::OfferTargetMismatch);
+                }
+            }
+        }
+    }
+}
+
+impl DerivedAccountIdentifier for LoanOfferNonce {
+    const SEED: &'static [u8] = b"liqzLoanOfferNonce";
+}
+
+impl LoanOfferNonce {
+    // Creates the nonce account, so that the same offer cannot be taken or cancelled twice
+    #[throws(ProgramError)]
+    pub fn consume<'info>(
+        program_id: &Pubkey,
+        lender: &Pubkey,
+        nonce: u64,
+        funder: &AccountInfo<'info>,
+        nonce_account: &AccountInfo<'info>,
+        now: UnixTimestamp,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, lender, nonce);
+
+        Self::verify_address(program_id, lender, nonce, bump, nonce_account.key)?;
+
+        if utils::is_program_account(nonce_account, program_id) {
+            throw!(liqzError::OfferNonceUsed);
+        }
+
+        let instance = LoanOfferNonce { used_at: now };
+
+        let acc_size = 8 + instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[
+            Self::SEED,
+            &lender.to_bytes(),
+            &nonce.to_le_bytes(),
+            &[bump],
+        ];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            funder,
+            seeds_with_bump,
+            nonce_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        let mut data = nonce_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // An program derived account that marks an offer nonce as used
+    // The address of the account is computed as follow:
+    // address = find_program_address([LoanOfferNonce::SEED, lender_wallet_address, nonce_le_bytes], program_id)
+    pub fn get_address(program_id: &Pubkey, lender: &Pubkey, nonce: u64) -> Pubkey {
+        Self::get_address_with_bump(program_id, lender, nonce).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        lender: &Pubkey,
+        nonce: u64,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[Self::SEED, &lender.to_bytes(), &nonce.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        lender: &Pubkey,
+        nonce: u64,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[
+                Self::SEED,
+                &lender.to_bytes(),
+                &nonce.to_le_bytes(),
+                &[bump],
+            ],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::OfferNonceAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_program::message::Message;
+
+    fn offer(lender: Pubkey) -> LoanOffer {
+        LoanOffer {
+            lender,
+            target: OfferTarget::Mint(Pubkey::new_unique()),
+            price: 1000,
+            duration: 1000,
+            expires_at: 1000,
+            nonce: 0,
+        }
+    }
+
+    // An ed25519 instruction with one signature, the runtime checks the signature itself
+    fn ed25519_data(pubkey: &Pubkey, message: &[u8]) -> Vec<u8> {
+        let pubkey_offset = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN;
+        let signature_offset = pubkey_offset + PUBKEY_LEN;
+        let message_offset = signature_offset + 64;
+
+        let mut data = vec![1, 0];
+        for offset in [
+            signature_offset,
+            u16::MAX as usize,
+            pubkey_offset,
+            u16::MAX as usize,
+            message_offset,
+            message.len(),
+            u16::MAX as usize,
+        ]
+        .iter()
+        {
+            data.extend_from_slice(&(*offset as u16).to_le_bytes());
+        }
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    // The instructions sysvar of a transaction running the ed25519 instruction and then the program
+    fn instructions_sysvar(program_id: &Pubkey, ed25519_data: Vec<u8>) -> AccountInfo<'static> {
+        let ed25519_ix = Instruction::new_with_bytes(ed25519_program::id(), &ed25519_data, vec![]);
+        let program_ix = Instruction::new_with_bytes(
+            *program_id,
+            &[],
+            vec![AccountMeta::new_readonly(instructions::id(), false)],
+        );
+
+        let mut data = Message::new(&[ed25519_ix, program_ix], None).serialize_instructions();
+        data.extend_from_slice(&[0, 0]);
+        instructions::store_current_index(&mut data, 1);
+
+        test_utils::account(instructions::id(), 1, solana_program::sysvar::id(), data)
+    }
+
+    fn verify(offer: &LoanOffer, program_id: &Pubkey, pool: &Pubkey, data: Vec<u8>) -> bool {
+        let sysvar = instructions_sysvar(program_id, data);
+        offer.verify_signature(program_id, pool, &sysvar).is_ok()
+    }
+
+    #[test]
+    fn offer_signed_by_the_lender_is_verified() {
+        let (program_id, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let offer = offer(Pubkey::new_unique());
+        let data = ed25519_data(&offer.lender, &offer.message(&program_id, &pool));
+
+        assert!(verify(&offer, &program_id, &pool, data));
+    }
+
+    #[test]
+    fn ed25519_instruction_with_another_signature_count_is_rejected() {
+        let (program_id, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let offer = offer(Pubkey::new_unique());
+
+        let mut data = ed25519_data(&offer.lender, &offer.message(&program_id, &pool));
+        data[0] = 2;
+        assert!(!verify(&offer, &program_id, &pool, data));
+
+        let mut data = ed25519_data(&offer.lender, &offer.message(&program_id, &pool));
+        data[0] = 0;
+        assert!(!verify(&offer, &program_id, &pool, data));
+    }
+
+    #[test]
+    fn data_read_from_another_instruction_is_rejected() {
+        let (program_id, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let offer = offer(Pubkey::new_unique());
+        let message = offer.message(&program_id, &pool);
+
+        // the signature, pubkey and message instruction indexes in turn
+        for field in [1, 3, 6].iter() {
+            let mut data = ed25519_data(&offer.lender, &message);
+            let at = SIGNATURE_OFFSETS_START + field * 2;
+            data[at..at + 2].copy_from_slice(&1u16.to_le_bytes());
+            assert!(!verify(&offer, &program_id, &pool, data));
+        }
+    }
+
+    #[test]
+    fn signature_of_another_wallet_is_rejected() {
+        let (program_id, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let offer = offer(Pubkey::new_unique());
+        let data = ed25519_data(&Pubkey::new_unique(), &offer.message(&program_id, &pool));
+
+        assert!(!verify(&offer, &program_id, &pool, data));
+    }
+
+    #[test]
+    fn changed_offer_is_rejected() {
+        let (program_id, pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let signed = offer(Pubkey::new_unique());
+        let data = ed25519_data(&signed.lender, &signed.message(&program_id, &pool));
+
+        let offer = LoanOffer {
+            price: signed.price + 1,
+            ..signed
+        };
+        assert!(!verify(&offer, &program_id, &pool, data.clone()));
+
+        // the same offer on another pool or deployment
+        let (other_program_id, other_pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(!verify(&signed, &program_id, &other_pool, data.clone()));
+        assert!(!verify(&signed, &other_program_id, &pool, data));
+    }
+
+    #[test]
+    fn nonce_funded_by_someone_else_is_still_unused() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let lender = Pubkey::new_unique();
+        let nonce_account =
+            test_utils::funded_address(LoanOfferNonce::get_address(&program_id, &lender, 7));
+
+        LoanOfferNonce::consume(
+            &program_id,
+            &lender,
+            7,
+            &test_utils::wallet(),
+            &nonce_account,
+            1000,
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,361 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::{utils, AdminAction, DerivedAccountIdentifier, Multisig, liqzError};
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use fehler::{throw, throws};
+
+pub const MAX_MULTISIG_MEMBERS: usize = 10;
+
+// An account of the instruction an admin action executes
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AdminActionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl DerivedAccountIdentifier for Multisig {
+    const SEED: &'static [u8] = b"liqzMultisig";
+}
+
+impl Multisig {
+    #[throws(ProgramError)]
+    pub fn create<'info>(
+        program_id: &Pubkey,
+        members: Vec<Pubkey>,
+        threshold: u64,
+        funder: &AccountInfo<'info>,
+        multisig_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id);
+
+        Self::verify_address(program_id, bump, multisig_account.key)?;
+
+        Self::verify_members(&members, threshold)?;
+
+        let instance = Multisig {
+            members,
+            threshold,
+            bump_seed: bump,
+            members_seqno: 0,
+        };
+
+        // size the account so that the members can be changed later
+        let largest_instance = Multisig {
+            members: vec![Pubkey::default(); MAX_MULTISIG_MEMBERS],
+            ..instance.clone()
+        };
+
+        let acc_size = 8 + largest_instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            funder,
+            &[Self::SEED, &[bump]],
+            multisig_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        {
+            let mut data = multisig_account.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        ProgramAccount::try_from(multisig_account)?
+    }
+
+    #[throws(liqzError)]
+    pub fn verify_members(members: &[Pubkey], threshold: u64) {
+        let mut unique = members.to_vec();
+        unique.sort();
+        unique.dedup();
+
+        if members.len() > MAX_MULTISIG_MEMBERS
+            || unique.len() != members.len()
+            || threshold == 0
+            || threshold > members.len() as u64
+        {
+            throw!(liqzError::MultisigSettingsNotAllowed);
+        }
+    }
+
+    // Pending admin actions are dropped when the members change
+    #[throws(liqzError)]
+    pub fn set_members(&mut self, members: Vec<Pubkey>, threshold: u64) {
+        Self::verify_members(&members, threshold)?;
+
+        self.members = members;
+        self.threshold = threshold;
+        self.members_seqno = self.members_seqno.checked_add(1).unwrap();
+    }
+
+    #[throws(liqzError)]
+    pub fn member_index(&self, wallet: &Pubkey) -> usize {
+        self.members
+            .iter()
+            .position(|member| member == wallet)
+            .ok_or(liqzError::NotMultisigMember)?
+    }
+
+    // The multisig is a program derived account, the pool owner is set to its address
+    // The address of the account is computed as follow:
+    // address = find_program_address([Multisig::SEED], program_id)
+    pub fn get_address(program_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(&[Self::SEED, &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::MultisigAddressNotCorrect);
+        }
+    }
+}
+
+impl DerivedAccountIdentifier for AdminAction {
+    const SEED: &'static [u8] = b"liqzAdminAction";
+}
+
+impl AdminAction {
+    // The proposer approves the action right away
+    #[throws(ProgramError)]
+    pub fn create<'info>(
+        program_id: &Pubkey,
+        action_id: &Pubkey,
+        multisig: &Multisig,
+        accounts: Vec<AdminActionAccount>,
+        data: Vec<u8>,
+        proposer: &AccountInfo<'info>,
+        action_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, action_id);
+
+        Self::verify_address(program_id, action_id, bump, action_account.key)?;
+
+        if utils::is_program_account(action_account, program_id) {
+            throw!(liqzError::AdminActionAlreadyExists);
+        }
+
+        let mut approvals = vec![false; multisig.members.len()];
+        approvals[multisig.member_index(proposer.key)?] = true;
+
+        let instance = AdminAction {
+            accounts,
+            data,
+            approvals,
+            members_seqno: multisig.members_seqno,
+            executed: false,
+        };
+
+        let acc_size = 8 + instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &action_id.to_bytes(), &[bump]];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            proposer,
+            seeds_with_bump,
+            action_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        {
+            let mut data = action_account.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        ProgramAccount::try_from(action_account)?
+    }
+
+    #[throws(liqzError)]
+    fn verify_pending(&self, multisig: &Multisig) {
+        if self.executed {
+            throw!(liqzError::AdminActionAlreadyExecuted);
+        }
+
+        if self.members_seqno != multisig.members_seqno {
+            throw!(liqzError::MultisigMembersChanged);
+        }
+    }
+
+    #[throws(liqzError)]
+    pub fn approve(&mut self, multisig: &Multisig, member: &Pubkey) -> u64 {
+        self.verify_pending(multisig)?;
+
+        self.approvals[multisig.member_index(member)?] = true;
+        self.approved()
+    }
+
+    pub fn approved(&self) -> u64 {
+        self.approvals.iter().filter(|approved| **approved).count() as u64
+    }
+
+    // The instruction is executed by the program itself, with the multisig as the signer
+    #[throws(liqzError)]
+    pub fn execute(&mut self, program_id: &Pubkey, multisig: &Multisig) -> Instruction {
+        self.verify_pending(multisig)?;
+
+        if self.approved() < multisig.threshold {
+            throw!(liqzError::NotEnoughApprovals);
+        }
+
+        self.executed = true;
+
+        Instruction {
+            program_id: *program_id,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: self.data.clone(),
+        }
+    }
+
+    // An program derived account that holds an admin instruction waiting for the approvals
+    // The address of the account is computed as follow:
+    // address = find_program_address([AdminAction::SEED, action_id], program_id)
+    pub fn get_address(program_id: &Pubkey, action_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, action_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, action_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &action_id.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, action_id: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &action_id.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::AdminActionAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multisig(members: &[Pubkey], threshold: u64) -> Multisig {
+        Multisig {
+            members: members.to_vec(),
+            threshold,
+            bump_seed: 255,
+            members_seqno: 0,
+        }
+    }
+
+    // Proposed by the first member, who approves it right away
+    fn action(multisig: &Multisig) -> AdminAction {
+        let mut approvals = vec![false; multisig.members.len()];
+        approvals[0] = true;
+
+        AdminAction {
+            accounts: vec![AdminActionAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: true,
+                is_writable: false,
+            }],
+            data: vec![1, 2, 3],
+            approvals,
+            members_seqno: multisig.members_seqno,
+            executed: false,
+        }
+    }
+
+    fn wallets(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    #[test]
+    fn members_must_be_unique_and_reach_the_threshold() {
+        let members = wallets(3);
+
+        Multisig::verify_members(&members, 1).unwrap();
+        Multisig::verify_members(&members, 3).unwrap();
+        assert!(Multisig::verify_members(&members, 0).is_err());
+        assert!(Multisig::verify_members(&members, 4).is_err());
+        assert!(Multisig::verify_members(&[members[0], members[0]], 1).is_err());
+        assert!(Multisig::verify_members(&wallets(MAX_MULTISIG_MEMBERS + 1), 1).is_err());
+    }
+
+    #[test]
+    fn approvals_are_counted_once_per_member() {
+        let members = wallets(3);
+        let multisig = multisig(&members, 2);
+        let mut action = action(&multisig);
+
+        assert_eq!(action.approve(&multisig, &members[0]).unwrap(), 1);
+        assert_eq!(action.approve(&multisig, &members[2]).unwrap(), 2);
+        assert_eq!(action.approve(&multisig, &members[2]).unwrap(), 2);
+        assert!(action.approve(&multisig, &Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn changing_the_members_drops_pending_actions() {
+        let members = wallets(3);
+        let mut multisig = multisig(&members, 2);
+        let mut action = action(&multisig);
+
+        multisig.set_members(members.clone(), 1).unwrap();
+        assert_eq!(multisig.members_seqno, 1);
+
+        assert!(action.approve(&multisig, &members[1]).is_err());
+        assert!(action.execute(&Pubkey::new_unique(), &multisig).is_err());
+        assert!(!action.executed);
+    }
+
+    #[test]
+    fn action_executes_once_the_threshold_is_reached() {
+        let program_id = Pubkey::new_unique();
+        let members = wallets(3);
+        let multisig = multisig(&members, 2);
+        let mut action = action(&multisig);
+
+        assert!(action.execute(&program_id, &multisig).is_err());
+        assert!(!action.executed);
+
+        action.approve(&multisig, &members[1]).unwrap();
+        let ix = action.execute(&program_id, &multisig).unwrap();
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(ix.accounts[0].pubkey, action.accounts[0].pubkey);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.data, vec![1, 2, 3]);
+
+        // executed only once
+        assert!(action.execute(&program_id, &multisig).is_err());
+        assert!(action.approve(&multisig, &members[2]).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, NFTAppraisal, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for NFTAppraisal {
+    const SEED: &'static [u8] = b"liqzNFTAppraisal";
+}
+
+impl NFTAppraisal {
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        nft_mint: &Pubkey,
+        funder: &AccountInfo<'info>,
+        appraisal_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, nft_mint);
+
+        Self::verify_address(program_id, nft_mint, bump, appraisal_account.key)?;
+
+        if !crate::utils::is_account_allocated(appraisal_account) {
+            let instance = NFTAppraisal { value: 0 };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &nft_mint.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                funder,
+                seeds_with_bump,
+                appraisal_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = appraisal_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(appraisal_account)?
+    }
+
+    // An program derived account that stores the DAI value the lending vault assigns to an NFT
+    // The address of the account is computed as follow:
+    // address = find_program_address([NFTAppraisal::SEED, nft_mint_address], program_id)
+    // only the pool owner can change the data in this account
+    pub fn get_address(program_id: &Pubkey, nft_mint: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, nft_mint).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &nft_mint.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, nft_mint: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &nft_mint.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::NFTAppraisalAddressNotCorrect);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, NFTAsk, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+// 100% per day, any higher rate would only overflow the interest of the loan
+pub const MAX_ASK_INTEREST_RATE: u64 = 10000;
+
+impl DerivedAccountIdentifier for NFTAsk {
+    const SEED: &'static [u8] = b"liqzNFTAsk";
+}
+
+impl NFTAsk {
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+        wallet: &AccountInfo<'info>,
+        ask_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit_account);
+
+        Self::verify_address(program_id, deposit_account, bump, ask_account.key)?;
+
+        if !crate::utils::is_account_allocated(ask_account) {
+            let instance = NFTAsk {
+                amount: 0,
+                interest_rate: 0,
+                duration: 0,
+            };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &deposit_account.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                wallet,
+                seeds_with_bump,
+                ask_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = ask_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(ask_account)?
+    }
+
+    #[throws(liqzError)]
+    pub fn verify_interest_rate(interest_rate: u64) {
+        if interest_rate > MAX_ASK_INTEREST_RATE {
+            throw!(liqzError::AskInterestRateNotAllowed)
+        }
+    }
+
+    pub fn set(&mut self, amount: u64, interest_rate: u64, duration: i64) {
+        self.amount = amount;
+        self.interest_rate = interest_rate;
+        self.duration = duration;
+    }
+
+    // An ask can only be accepted once, and only on the terms the lender has seen
+    #[throws(liqzError)]
+    pub fn take(&mut self, amount: u64, interest_rate: u64, duration: i64) {
+        if self.amount == 0 {
+            throw!(liqzError::AskNotAvailable)
+        }
+
+        if self.amount != amount || self.interest_rate != interest_rate || self.duration != duration
+        {
+            throw!(liqzError::AskTermsChanged)
+        }
+
+        self.cancel();
+    }
+
+    pub fn cancel(&mut self) {
+        self.set(0, 0, 0);
+    }
+
+    // An program derived account that stores the loan terms the borrower asks for a deposit
+    // The address of the account is computed as follow:
+    // address = find_program_address([NFTAsk::SEED, deposit_account_address], program_id)
+    // only the liqz_contract_address can change the data in this account
+    pub fn get_address(program_id: &Pubkey, deposit_account: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, deposit_account).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &deposit_account.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        deposit_account: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &deposit_account.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::NFTAskAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InterestModel;
+
+    #[test]
+    fn interest_rates_above_the_max_are_rejected() {
+        assert!(NFTAsk::verify_interest_rate(MAX_ASK_INTEREST_RATE + 1).is_err());
+        assert!(NFTAsk::verify_interest_rate(u64::MAX).is_err());
+
+        NFTAsk::verify_interest_rate(MAX_ASK_INTEREST_RATE).unwrap();
+        let model = InterestModel::from_daily_rate(MAX_ASK_INTEREST_RATE);
+        assert_eq!(model.interest(u64::MAX, 10 * 365 * 24 * 60 * 60), u64::MAX);
+    }
+}
@@ -1,13 +1,77 @@
 use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
 
-use crate::{utils, DerivedAccountIdentifier, NFTBid, liqzError};
+use crate::{
+    utils, DerivedAccountIdentifier, Legacy, LoanTerm, LoanTerms, NFTBid, NFTPool, Versioned,
+    RESERVED_SPACE, liqzError,
+};
 use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
 
 impl DerivedAccountIdentifier for NFTBid {
     const SEED: &'static [u8] = b"liqzNFTBid";
 }
 
+// Who can take a private bid
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BidRestriction {
+    Borrower(Pubkey), // only this borrower wallet
+    Deposit(Pubkey),  // only this NFTDeposit account
+}
+
+impl Versioned for NFTBid {
+    const VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    // Borsh does not support vary size structure.
+    // Size the account for a restriction so that it can be set later
+    fn account_size(&self) -> u64 {
+        let largest_instance = NFTBid {
+            restriction: Some(BidRestriction::Borrower(Pubkey::new(&[0u8; 32]))),
+            expires_at: Some(0),
+            call_notice: Some(0),
+            installment_period: Some(0),
+            referrer: Some(Pubkey::new(&[0u8; 32])),
+            ..self.clone()
+        };
+
+        8 + largest_instance.try_to_vec().unwrap().len() as u64 + RESERVED_SPACE
+    }
+}
+
+// The bid as it was laid out before versioning, see migrate_bid
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct LegacyNFTBid {
+    pub price: u64,
+    pub qty: u64,
+}
+
+impl Legacy for LegacyNFTBid {
+    fn account_size() -> usize {
+        8 + Self::default().try_to_vec().unwrap().len()
+    }
+}
+
+impl LegacyNFTBid {
+    // A legacy bid is public, never expires and lends fixed-term loans
+    pub fn upgrade(&self) -> NFTBid {
+        NFTBid {
+            version: NFTBid::VERSION,
+            price: self.price,
+            qty: self.qty,
+            restriction: None,
+            expires_at: None,
+            call_notice: None,
+            installment_period: None,
+            referrer: None,
+        }
+    }
+}
+
 impl NFTBid {
     #[throws(ProgramError)]
     pub fn ensure<'info>(
@@ -23,12 +87,18 @@ impl NFTBid {
         Self::verify_address(program_id, nft_mint, wallet.key, bump, bid_account.key)?;
 
         if !crate::utils::is_account_allocated(bid_account) {
-            let instance = NFTBid { price: 0, qty: 0 };
+            let instance = NFTBid {
+                version: Self::VERSION,
+                price: 0,
+                qty: 0,
+                restriction: None,
+                expires_at: None,
+                call_notice: None,
+                installment_period: None,
+                referrer: None,
+            };
 
-            let acc_size = 8 + instance
-                .try_to_vec()
-                .map_err(|_| ProgramError::Custom(1))?
-                .len() as u64;
+            let acc_size = instance.account_size();
 
             let seeds_with_bump: &[&[_]] = &[
                 Self::SEED,
@@ -57,6 +127,37 @@ impl NFTBid {
         ProgramAccount::try_from(bid_account)?
     }
 
+    // Take one NFT from the bid for a loan of `amount`
+    #[throws(liqzError)]
+    pub fn fill(
+        &mut self,
+        amount: u64,
+        borrower: &Pubkey,
+        deposit_account: &Pubkey,
+        now: UnixTimestamp,
+    ) {
+        if self.is_expired(now) {
+            throw!(liqzError::BidExpired)
+        }
+
+        if amount > self.price {
+            throw!(liqzError::NFTBorrowExceedBidAmount)
+        }
+
+        match self.restriction {
+            Some(BidRestriction::Borrower(b)) if &b != borrower => {
+                throw!(liqzError::BidRestricted)
+            }
+            Some(BidRestriction::Deposit(d)) if &d != deposit_account => {
+                throw!(liqzError::BidRestricted)
+            }
+            _ => {}
+        }
+
+        // decrease the bid qty by 1;
+        self.trade(1)?;
+    }
+
     #[throws(liqzError)]
     pub fn trade(&mut self, qty: u64) {
         if qty > self.qty {
@@ -68,14 +169,53 @@ impl NFTBid {
         }
     }
 
-    pub fn set(&mut self, price: u64, qty: u64) {
+    pub fn set(
+        &mut self,
+        price: u64,
+        qty: u64,
+        restriction: Option<BidRestriction>,
+        expires_at: Option<UnixTimestamp>,
+        call_notice: Option<i64>,
+        installment_period: Option<i64>,
+        referrer: Option<Pubkey>,
+    ) {
         self.price = price;
         self.qty = qty;
+        self.restriction = restriction;
+        self.expires_at = expires_at;
+        self.call_notice = call_notice;
+        self.installment_period = installment_period;
+        self.referrer = referrer;
     }
 
     pub fn cancel(&mut self) {
         self.price = 0;
         self.qty = 0;
+        self.restriction = None;
+        self.expires_at = None;
+        self.call_notice = None;
+        self.installment_period = None;
+        self.referrer = None;
+    }
+
+    // Open-ended bids start loans the lender can call at any time
+    pub fn loan_terms(&self, pool: &NFTPool) -> LoanTerms {
+        LoanTerms {
+            term: match self.call_notice {
+                Some(notice) => LoanTerm::OpenEnded(notice),
+                None => LoanTerm::Fixed(pool.max_loan_duration),
+            },
+            interest_model: pool.interest_model,
+            service_fee_rate: pool.service_fee_rate,
+            installment_period: self.installment_period,
+            min_interest_duration: pool.min_interest_duration,
+            borrow_referrer: None,
+            bid_referrer: self.referrer,
+        }
+    }
+
+    pub fn is_expired(&self, now: UnixTimestamp) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now > expires_at)
     }
 
     // An program derived account that stores nft bid
@@ -120,3 +260,34 @@ impl NFTBid {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning;
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn legacy_bid_is_upgraded_to_a_public_bid() {
+        // the bytes of a bid placed before versioning
+        let mut data = NFTBid::discriminator().to_vec();
+        data.extend_from_slice(&1_500_000u64.to_le_bytes()); // price
+        data.extend_from_slice(&2u64.to_le_bytes()); // qty
+        assert_eq!(data.len(), LegacyNFTBid::account_size());
+
+        assert!(NFTBid::try_deserialize(&mut data.as_slice()).is_err());
+
+        let legacy: LegacyNFTBid = versioning::decode_legacy_data::<NFTBid, _>(&data).unwrap();
+        let bid = legacy.upgrade();
+
+        assert_eq!(bid.version, NFTBid::VERSION);
+        assert_eq!(bid.price, 1_500_000);
+        assert_eq!(bid.qty, 2);
+        assert_eq!(bid.restriction, None);
+        assert_eq!(bid.expires_at, None);
+        assert_eq!(bid.call_notice, None);
+        assert_eq!(bid.installment_period, None);
+        assert_eq!(bid.referrer, None);
+        assert!(bid.account_size() as usize > data.len());
+    }
+}
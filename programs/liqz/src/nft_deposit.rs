@@ -1,7 +1,10 @@
 use anchor_lang::prelude::Pubkey;
 use solana_program::clock::UnixTimestamp;
 
-use crate::{utils, DerivedAccountIdentifier, NFTDeposit, liqzError};
+use crate::{
+    utils, DerivedAccountIdentifier, InterestModel, Legacy, NFTDeposit, NFTPool, Versioned,
+    RESERVED_SPACE, liqzError,
+};
 use anchor_lang::prelude::*;
 use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
@@ -10,6 +13,8 @@ impl DerivedAccountIdentifier for NFTDeposit {
     const SEED: &'static [u8] = b"liqzNFTDeposit";
 }
 
+// The state is stored inline in the deposit account, an active loan is the largest by design
+#[allow(clippy::large_enum_variant)]
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
 pub enum DepositState {
     PendingLoan,                   // Loan hasn't happened yet and the NFT is in the pool
@@ -17,32 +22,326 @@ pub enum DepositState {
     LoanRepayed(LoanRepayedState), // Loan repayed and the NFT is withdrawn by the borrower
 
     // The following three are terminal state
-    Withdrawn,      // Loan did not happen and the NFT is withdrawn by the borrower
-    LoanLiquidated, // Loan liquidated and the NFT is withdrawn by the lender
+    Withdrawn, // Loan did not happen and the NFT is withdrawn by the borrower
+    LoanLiquidated(LoanLiquidatedState), // Loan liquidated and the NFT is withdrawn by the lender
     LoanCleared,
 }
 
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub enum LoanTerm {
+    Fixed(i64),     // loan length, secs
+    OpenEnded(i64), // no expiry until the lender calls the loan, then the borrower has this many secs to repay
+}
+
+// The terms a loan starts with
+#[derive(Debug, Clone, Copy)]
+pub struct LoanTerms {
+    pub term: LoanTerm,
+    pub interest_model: InterestModel,
+    pub service_fee_rate: u64, // in bp, discounted for LIZ stakers
+    pub installment_period: Option<i64>, // secs, Some if interest is paid in installments
+    pub min_interest_duration: i64, // secs, interest is charged for at least this long
+    pub borrow_referrer: Option<Pubkey>, // who brought the borrower
+    pub bid_referrer: Option<Pubkey>, // who brought the lender
+}
+
+// Interest paid periodically, each installment covers one period of interest
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct Installments {
+    pub period: i64,               // secs
+    pub paid_until: UnixTimestamp, // interest is paid up to this time
+}
+
+impl Installments {
+    // The next installment must be paid before this time
+    pub fn due_at(&self) -> UnixTimestamp {
+        self.paid_until.checked_add(self.period).unwrap()
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
 pub struct LoanActiveState {
     pub total_amount: u64,
-    pub borrowed_amount: u64,      // amount of dai
-    pub started_at: UnixTimestamp, // in seconds
-    pub expired_at: UnixTimestamp, // in seconds
-    pub lender: Pubkey,
+    pub borrowed_amount: u64,          // amount of dai
+    pub started_at: UnixTimestamp,     // in seconds
+    pub expired_at: UnixTimestamp,     // in seconds
+    pub interest_model: InterestModel, // fixed when the loan starts
+    pub service_fee_rate: u64,         // in bp, fixed when the loan starts
+    pub call_notice: Option<i64>,      // secs, only for open-ended loans
+    pub called_at: Option<UnixTimestamp>,
+    pub installments: Option<Installments>,
+    pub min_interest_until: UnixTimestamp, // repaying earlier still pays interest up to this time
+    pub borrow_referrer: Option<Pubkey>,
+    pub bid_referrer: Option<Pubkey>,
+    pub lender: Pubkey, // the original lender, the current one is the holder of the lender position
+    pub borrower: Pubkey,
+    pub collection: Pubkey, // the loan counts towards the exposure of this collection
+}
+
+impl LoanActiveState {
+    // The lender can liquidate once the loan expires or an installment is missed
+    pub fn is_defaulted(&self, now: UnixTimestamp) -> bool {
+        let missed_installment = matches!(
+            self.installments,
+            Some(i) if i.paid_until < self.expired_at && now > i.due_at()
+        );
+
+        now > self.expired_at || missed_installment
+    }
+
+    // Interest already paid by installments is not charged again at repay
+    pub fn interest_accrued_since(&self) -> UnixTimestamp {
+        match self.installments {
+            Some(i) => i.paid_until,
+            None => self.started_at,
+        }
+    }
+
+    // Each referrer gets a share of the fee when the loan is settled
+    pub fn referrers(&self) -> Vec<Pubkey> {
+        self.borrow_referrer
+            .into_iter()
+            .chain(self.bid_referrer)
+            .collect()
+    }
+
+    // The time span of interest still owed when the loan is repaid now,
+    // zero when the installments are paid ahead of it
+    pub fn interest_duration(&self, now: UnixTimestamp) -> i64 {
+        now.max(self.min_interest_until)
+            .saturating_sub(self.interest_accrued_since())
+            .max(0)
+    }
+
+    // The interest owed over a duration and the service fee charged on it
+    pub fn interest_and_fee(&self, duration: i64) -> (u64, u64) {
+        let interest = self.interest_model.interest(self.borrowed_amount, duration);
+        let fee = interest
+            .checked_mul(self.service_fee_rate)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap();
+
+        (interest, fee)
+    }
+
+    // The service fee charged at liquidation is paid out of the margin left in the pool,
+    // returns the fee and the rest of the margin that goes to the lender
+    pub fn liquidation_fee(&self, duration: i64) -> (u64, u64) {
+        let margin = self.total_amount.checked_sub(self.borrowed_amount).unwrap();
+        let (_, fee) = self.interest_and_fee(duration);
+        let fee = fee.min(margin);
+
+        (fee, margin.checked_sub(fee).unwrap())
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
 pub struct LoanRepayedState {
+    pub lender_withdrawable: u64,
+    pub lender: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct LoanLiquidatedState {
+    pub borrowed_amount: u64, // the principal the lender lost against the NFT
+    pub lender: Pubkey,       // who took the NFT
+    pub compensated: u64,     // DAI paid back to the lender by the insurance fund
+}
+
+impl Versioned for NFTDeposit {
+    const VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn account_size(&self) -> u64 {
+        // Borsh does not support vary size structure.
+        // Pick the largest variant so that we are safe
+        let largest_instance = NFTDeposit {
+            version: Self::VERSION,
+            deposit_id: 0,
+            state: DepositState::LoanActive(LoanActiveState {
+                total_amount: 0,
+                borrowed_amount: 0,
+                started_at: 0,
+                expired_at: 0,
+                interest_model: InterestModel::largest(),
+                service_fee_rate: 0,
+                call_notice: Some(0),
+                called_at: Some(0),
+                installments: Some(Installments {
+                    period: 0,
+                    paid_until: 0,
+                }),
+                min_interest_until: 0,
+                borrow_referrer: Some(Pubkey::new(&[0u8; 32])),
+                bid_referrer: Some(Pubkey::new(&[0u8; 32])),
+                lender: Pubkey::new(&[0u8; 32]),
+                borrower: Pubkey::new(&[0u8; 32]),
+                collection: Pubkey::new(&[0u8; 32]),
+            }),
+        };
+
+        8 + largest_instance.try_to_vec().unwrap().len() as u64 + RESERVED_SPACE
+    }
+}
+
+// The deposit as it was laid out before versioning, see migrate_deposit.
+// Its id was a key chosen by the borrower, the address was derived from it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct LegacyNFTDeposit {
+    pub deposit_id: Pubkey,
+    pub state: LegacyDepositState,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub enum LegacyDepositState {
+    PendingLoan,
+    LoanActive(LegacyLoanActiveState),
+    LoanRepayed(LegacyLoanRepayedState),
+    Withdrawn,
+    LoanLiquidated,
+    LoanCleared,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct LegacyLoanActiveState {
+    pub total_amount: u64,
+    pub borrowed_amount: u64,
+    pub started_at: UnixTimestamp,
+    pub expired_at: UnixTimestamp,
+    pub lender: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct LegacyLoanRepayedState {
     pub tai_required_to_unlock: u64,
     pub lender_withdrawable: u64,
     pub lender: Pubkey,
 }
 
+impl Legacy for LegacyNFTDeposit {
+    // The legacy deposits were sized for an active loan
+    fn account_size() -> usize {
+        let largest_instance = LegacyNFTDeposit {
+            deposit_id: Pubkey::default(),
+            state: LegacyDepositState::LoanActive(LegacyLoanActiveState {
+                total_amount: 0,
+                borrowed_amount: 0,
+                started_at: 0,
+                expired_at: 0,
+                lender: Pubkey::default(),
+            }),
+        };
+
+        8 + largest_instance.try_to_vec().unwrap().len()
+    }
+}
+
+impl LegacyNFTDeposit {
+    // The lender of a loan that is not settled yet, it gets a lender position when the deposit is migrated
+    pub fn lender(&self) -> Option<Pubkey> {
+        match self.state {
+            LegacyDepositState::LoanActive(s) => Some(s.lender),
+            LegacyDepositState::LoanRepayed(s) => Some(s.lender),
+            _ => None,
+        }
+    }
+
+    // The principal of an active loan
+    pub fn active_principal(&self) -> Option<u64> {
+        match self.state {
+            LegacyDepositState::LoanActive(s) => Some(s.borrowed_amount),
+            _ => None,
+        }
+    }
+
+    // Legacy loans were charged the rate of the pool at repay, an active loan keeps the one the pool has now
+    pub fn upgrade(&self, deposit_id: u64, borrower: Pubkey, pool: &NFTPool) -> NFTDeposit {
+        let state = match self.state {
+            LegacyDepositState::PendingLoan => DepositState::PendingLoan,
+            LegacyDepositState::LoanActive(s) => DepositState::LoanActive(LoanActiveState {
+                total_amount: s.total_amount,
+                borrowed_amount: s.borrowed_amount,
+                started_at: s.started_at,
+                expired_at: s.expired_at,
+                interest_model: pool.interest_model.at_utilization(0),
+                service_fee_rate: pool.service_fee_rate,
+                call_notice: None,
+                called_at: None,
+                installments: None,
+                min_interest_until: s.started_at,
+                borrow_referrer: None,
+                bid_referrer: None,
+                lender: s.lender,
+                borrower,
+                // legacy loans are not counted towards any collection
+                collection: Pubkey::default(),
+            }),
+            LegacyDepositState::LoanRepayed(s) => DepositState::LoanRepayed(LoanRepayedState {
+                lender_withdrawable: s.lender_withdrawable,
+                lender: s.lender,
+            }),
+            LegacyDepositState::Withdrawn => DepositState::Withdrawn,
+            // the legacy record keeps neither the principal nor who took the NFT, there is nothing to compensate
+            LegacyDepositState::LoanLiquidated => {
+                DepositState::LoanLiquidated(LoanLiquidatedState {
+                    borrowed_amount: 0,
+                    lender: Pubkey::default(),
+                    compensated: 0,
+                })
+            }
+            LegacyDepositState::LoanCleared => DepositState::LoanCleared,
+        };
+
+        NFTDeposit {
+            version: NFTDeposit::VERSION,
+            deposit_id,
+            state,
+        }
+    }
+
+    // The address of a legacy deposit is computed as follow:
+    // address = find_program_address([NFTDeposit::SEED, nft_mint_address, borrower_wallet_address, deposit_id], program_id)
+    pub fn get_address(
+        program_id: &Pubkey,
+        nft_mint: &Pubkey,
+        borrower_wallet: &Pubkey,
+        deposit_id: &Pubkey,
+    ) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                NFTDeposit::SEED,
+                &nft_mint.to_bytes(),
+                &borrower_wallet.to_bytes(),
+                &deposit_id.to_bytes(),
+            ],
+            program_id,
+        )
+        .0
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        &self,
+        program_id: &Pubkey,
+        nft_mint: &Pubkey,
+        borrower_wallet: &Pubkey,
+        address: &Pubkey,
+    ) {
+        if &Self::get_address(program_id, nft_mint, borrower_wallet, &self.deposit_id) != address {
+            throw!(liqzError::NFTLoanAddressNotCorrect);
+        }
+    }
+}
+
 impl NFTDeposit {
     #[throws(ProgramError)]
     pub fn deposit<'info>(
         program_id: &Pubkey,
-        deposit_id: &Pubkey,
+        deposit_id: u64,
         nft_mint: &Pubkey,
         borrower_wallet: &AccountInfo<'info>,
         deposit_account: &AccountInfo<'info>,
@@ -61,14 +360,9 @@ impl NFTDeposit {
             deposit_account.key,
         )?;
 
-        // Do not reuse the loan record
-        // TODO: deallocate the loan record and give lamports back to the user.
-        if crate::utils::is_account_allocated(deposit_account) {
-            throw!(liqzError::LoanAlreadyExist);
-        }
-
         let instance = NFTDeposit {
-            deposit_id: *deposit_id,
+            version: Self::VERSION,
+            deposit_id,
             state: DepositState::PendingLoan,
         };
 
@@ -76,16 +370,44 @@ impl NFTDeposit {
             Self::SEED,
             &nft_mint.to_bytes(),
             &borrower_wallet.key.to_bytes(),
-            &deposit_id.to_bytes(),
+            &deposit_id.to_le_bytes(),
             &[bump],
         ];
 
-        utils::create_derived_account_with_seed(
+        Self::create(
             program_id,
+            instance,
+            seeds_with_bump,
+            deposit_account,
             borrower_wallet,
+            rent,
+            system_program,
+        )?
+    }
+
+    // Allocates the deposit account at its verified address and writes the instance
+    #[throws(ProgramError)]
+    pub fn create<'info>(
+        program_id: &Pubkey,
+        instance: Self,
+        seeds_with_bump: &[&[u8]],
+        deposit_account: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system_program: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        // Do not reuse the loan record
+        // TODO: deallocate the loan record and give lamports back to the user.
+        if crate::utils::is_account_allocated(deposit_account) {
+            throw!(liqzError::LoanAlreadyExist);
+        }
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            payer,
             seeds_with_bump,
             deposit_account,
-            Self::account_size() as u64,
+            instance.account_size(),
             &rent,
             &system_program,
         )?;
@@ -101,6 +423,10 @@ impl NFTDeposit {
         loan_account
     }
 
+    pub fn is_pending_loan(&self) -> bool {
+        matches!(self.state, DepositState::PendingLoan)
+    }
+
     #[throws(liqzError)]
     pub fn withdraw(&mut self) {
         use DepositState::*;
@@ -108,7 +434,7 @@ impl NFTDeposit {
         match self.state {
             PendingLoan => self.state = DepositState::Withdrawn,
             Withdrawn | LoanRepayed { .. } | LoanCleared => throw!(liqzError::NFTAlreadyWithdrawn),
-            LoanActive(_) | LoanLiquidated => throw!(liqzError::NFTLocked),
+            LoanActive(_) | LoanLiquidated(_) => throw!(liqzError::NFTLocked),
         }
     }
 
@@ -116,35 +442,51 @@ impl NFTDeposit {
     pub fn start_borrow(
         &mut self,
         lender: Pubkey,
+        borrower: Pubkey,
+        collection: Pubkey,
         total_amount: u64,
         borrowed_amount: u64,
         start: UnixTimestamp,
-        length: i64,
+        terms: LoanTerms,
     ) {
         if !matches!(self.state, DepositState::PendingLoan) {
             throw!(liqzError::BorrowAlreadyStarted)
         }
 
         assert!(total_amount >= borrowed_amount);
+
+        let (expired_at, call_notice) = match terms.term {
+            LoanTerm::Fixed(length) => (start + length, None),
+            LoanTerm::OpenEnded(notice) => (UnixTimestamp::MAX, Some(notice)),
+        };
+
         self.state = DepositState::LoanActive(LoanActiveState {
             lender,
+            borrower,
+            collection,
             total_amount,
-            borrowed_amount,            // amount of dai
-            started_at: start,          // in seconds
-            expired_at: start + length, // in seconds
+            borrowed_amount,   // amount of dai
+            started_at: start, // in seconds
+            expired_at,        // in seconds
+            interest_model: terms.interest_model,
+            service_fee_rate: terms.service_fee_rate,
+            call_notice,
+            called_at: None,
+            installments: terms.installment_period.map(|period| Installments {
+                period,
+                paid_until: start,
+            }),
+            min_interest_until: start.checked_add(terms.min_interest_duration).unwrap(),
+            borrow_referrer: terms.borrow_referrer,
+            bid_referrer: terms.bid_referrer,
         });
     }
 
     #[throws(liqzError)]
     pub fn repay(&mut self, lender_withdrawable: u64) {
         match self.state {
-            DepositState::LoanActive(LoanActiveState {
-                lender,
-                borrowed_amount,
-                ..
-            }) => {
+            DepositState::LoanActive(LoanActiveState { lender, .. }) => {
                 self.state = DepositState::LoanRepayed(LoanRepayedState {
-                    tai_required_to_unlock: borrowed_amount,
                     lender_withdrawable,
                     lender,
                 })
@@ -155,11 +497,68 @@ impl NFTDeposit {
         }
     }
 
+    // The lender calls an open-ended loan, the borrower has the notice period to repay before liquidation
+    #[throws(liqzError)]
+    pub fn call(&mut self, now: UnixTimestamp) -> UnixTimestamp {
+        match &mut self.state {
+            DepositState::LoanActive(loan) => match (loan.call_notice, loan.called_at) {
+                (None, _) => throw!(liqzError::LoanNotCallable),
+                (Some(_), Some(_)) => throw!(liqzError::LoanAlreadyCalled),
+                (Some(notice), None) => {
+                    loan.called_at = Some(now);
+                    loan.expired_at = now.checked_add(notice).unwrap();
+                    loan.expired_at
+                }
+            },
+            _ => throw!(liqzError::LoanNotActive),
+        }
+    }
+
+    #[throws(liqzError)]
+    pub fn cancel_call(&mut self) {
+        match &mut self.state {
+            DepositState::LoanActive(loan) => {
+                if loan.called_at.is_none() {
+                    throw!(liqzError::LoanNotCalled)
+                }
+                loan.called_at = None;
+                loan.expired_at = UnixTimestamp::MAX;
+            }
+            _ => throw!(liqzError::LoanNotActive),
+        }
+    }
+
+    // Pays the next installment, returns the time span of interest it covers
     #[throws(liqzError)]
-    pub fn liquidate(&mut self) {
+    pub fn pay_installment(&mut self) -> i64 {
+        match &mut self.state {
+            DepositState::LoanActive(loan) => match &mut loan.installments {
+                None => throw!(liqzError::LoanNotInstallment),
+                Some(i) => {
+                    if i.paid_until >= loan.expired_at {
+                        throw!(liqzError::InstallmentsFullyPaid)
+                    }
+                    let until = i.due_at().min(loan.expired_at);
+                    let span = until - i.paid_until;
+                    i.paid_until = until;
+                    span
+                }
+            },
+            _ => throw!(liqzError::LoanNotActive),
+        }
+    }
+
+    #[throws(liqzError)]
+    pub fn liquidate(&mut self, lender: Pubkey) {
         match self.state {
-            DepositState::LoanActive { .. } => {
-                self.state = DepositState::LoanLiquidated;
+            DepositState::LoanActive(LoanActiveState {
+                borrowed_amount, ..
+            }) => {
+                self.state = DepositState::LoanLiquidated(LoanLiquidatedState {
+                    borrowed_amount,
+                    lender,
+                    compensated: 0,
+                });
             }
             _ => {
                 throw!(liqzError::LoanNotActive)
@@ -183,7 +582,7 @@ impl NFTDeposit {
     pub fn get_active_state(&self) -> LoanActiveState {
         match self.state {
             DepositState::PendingLoan
-            | DepositState::LoanLiquidated
+            | DepositState::LoanLiquidated(_)
             | DepositState::LoanRepayed { .. }
             | DepositState::Withdrawn
             | DepositState::LoanCleared => throw!(liqzError::LoanNotActive),
@@ -195,7 +594,7 @@ impl NFTDeposit {
     pub fn get_repayed_state(&self) -> LoanRepayedState {
         match self.state {
             DepositState::PendingLoan
-            | DepositState::LoanLiquidated
+            | DepositState::LoanLiquidated(_)
             | DepositState::Withdrawn
             | DepositState::LoanCleared => throw!(liqzError::LoanNotActive),
             DepositState::LoanActive { .. } => throw!(liqzError::LoanNotRepayed),
@@ -203,15 +602,36 @@ impl NFTDeposit {
         }
     }
 
+    // The insurance fund can pay a liquidated loan in several parts, the caller caps the total at the shortfall
+    #[throws(liqzError)]
+    pub fn compensate(&mut self, amount: u64) -> LoanLiquidatedState {
+        match self.state {
+            DepositState::LoanLiquidated(ref mut s) => {
+                let liquidated = *s;
+                s.compensated = s.compensated.checked_add(amount).unwrap();
+                liquidated
+            }
+            _ => throw!(liqzError::LoanNotLiquidated),
+        }
+    }
+
+    #[throws(liqzError)]
+    pub fn get_liquidated_state(&self) -> LoanLiquidatedState {
+        match self.state {
+            DepositState::LoanLiquidated(s) => s,
+            _ => throw!(liqzError::LoanNotLiquidated),
+        }
+    }
+
     // An program derived account that stores nft loan
     // The address of the account is computed as follow:
-    // address = find_program_address([NFTLoan::SEED, nft_mint_address, borrower_wallet_address, loan_id], program_id)
+    // address = find_program_address([NFTLoan::SEED, nft_mint_address, borrower_wallet_address, deposit_id], program_id)
     // only the liqz_contract_address can change the data in this account
     pub fn get_address(
         program_id: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
-        deposit_id: &Pubkey,
+        deposit_id: u64,
     ) -> Pubkey {
         Self::get_address_with_bump(program_id, nft_mint, borrower_wallet, deposit_id).0
     }
@@ -220,14 +640,14 @@ impl NFTDeposit {
         program_id: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
-        deposit_id: &Pubkey,
+        deposit_id: u64,
     ) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[
                 Self::SEED,
                 &nft_mint.to_bytes(),
                 &borrower_wallet.to_bytes(),
-                &deposit_id.to_bytes(),
+                &deposit_id.to_le_bytes(),
             ],
             program_id,
         )
@@ -238,7 +658,7 @@ impl NFTDeposit {
         program_id: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
-        deposit_id: &Pubkey,
+        deposit_id: u64,
         bump: u8,
         address: &Pubkey,
     ) {
@@ -247,7 +667,7 @@ impl NFTDeposit {
                 Self::SEED,
                 &nft_mint.to_bytes(),
                 &borrower_wallet.to_bytes(),
-                &deposit_id.to_bytes(),
+                &deposit_id.to_le_bytes(),
                 &[bump],
             ],
             program_id,
@@ -257,22 +677,269 @@ impl NFTDeposit {
             throw!(liqzError::NFTLoanAddressNotCorrect);
         }
     }
+}
 
-    fn account_size() -> usize {
-        // Borsh does not support vary size structure.
-        // Pick the largest variant so that we are safe
-        let largest_instance = NFTDeposit {
-            deposit_id: Pubkey::new(&[0u8; 32]),
-            state: DepositState::LoanActive(LoanActiveState {
-                total_amount: 0,
-                borrowed_amount: 0,
-                started_at: 0,
-                expired_at: 0,
-                lender: Pubkey::new(&[0u8; 32]),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, pool};
+    use crate::{versioning, LegacyNFTPool};
+    use anchor_lang::Discriminator;
+
+    fn installment_loan(paid_until: UnixTimestamp) -> LoanActiveState {
+        LoanActiveState {
+            installments: Some(Installments {
+                period: 1000,
+                paid_until,
             }),
+            ..test_utils::loan(1000, 10000)
+        }
+    }
+
+    #[test]
+    fn interest_runs_from_the_last_installment() {
+        let loan = installment_loan(2000);
+        assert_eq!(loan.interest_duration(2500), 500);
+    }
+
+    #[test]
+    fn prepaid_installments_owe_nothing_at_repay() {
+        let loan = installment_loan(3000);
+        assert_eq!(loan.interest_duration(1500), 0);
+        assert_eq!(loan.interest_and_fee(loan.interest_duration(1500)), (0, 0));
+    }
+
+    #[test]
+    fn missed_installment_defaults_the_loan() {
+        let loan = installment_loan(2000);
+        assert!(!loan.is_defaulted(3000));
+        assert!(loan.is_defaulted(3001));
+    }
+
+    // Follows the DAI the pool holds through start_loan, repay and withdraw_locked_asset,
+    // none of it may be swept while the lender has not withdrawn it
+    #[test]
+    fn repaid_dai_stays_locked_until_the_lender_withdraws() {
+        let mut pool = pool();
+        let dai = pool.dai_mint;
+        let mut deposit = NFTDeposit {
+            version: NFTDeposit::VERSION,
+            deposit_id: 0,
+            state: DepositState::PendingLoan,
+        };
+
+        // the lender sends the whole amount, the borrower gets the principal and the margin stays
+        deposit
+            .start_borrow(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::default(),
+                1_000_000,
+                800_000,
+                0,
+                LoanTerms {
+                    term: LoanTerm::Fixed(pool.max_loan_duration),
+                    interest_model: InterestModel::SimpleApr(1000),
+                    service_fee_rate: 500,
+                    installment_period: None,
+                    min_interest_duration: 0,
+                    borrow_referrer: None,
+                    bid_referrer: None,
+                },
+            )
+            .unwrap();
+        let loan = deposit.get_active_state().unwrap();
+        let mut balance = loan.total_amount - loan.borrowed_amount;
+        pool.lock_dai(loan.total_amount - loan.borrowed_amount);
+        assert_eq!(pool.sweepable(&dai, 9, false, balance).unwrap(), 0);
+
+        // the borrower pays the principal and the lender income, the fee goes to the treasury
+        let (interest, fee) = loan.interest_and_fee(loan.interest_duration(365 * 24 * 60 * 60));
+        let lender_income = interest - fee;
+        let repayed_amount = loan.borrowed_amount + lender_income;
+        balance += repayed_amount;
+        pool.lock_dai(repayed_amount);
+        deposit.repay(loan.total_amount + lender_income).unwrap();
+        assert_eq!(pool.dai_locked, balance);
+        assert_eq!(pool.sweepable(&dai, 9, false, balance).unwrap(), 0);
+
+        // the holder of the lender position withdraws all of it
+        let lender_withdrawable = deposit.get_repayed_state().unwrap().lender_withdrawable;
+        assert_eq!(lender_withdrawable, 1_000_000 + 76_000);
+        balance -= lender_withdrawable;
+        pool.unlock_dai(lender_withdrawable).unwrap();
+        assert_eq!(balance, 0);
+        assert_eq!(pool.dai_locked, 0);
+    }
+
+    #[test]
+    fn liquidation_fee_is_capped_at_the_margin() {
+        let loan = LoanActiveState {
+            total_amount: 1_000_000,
+            ..test_utils::loan(800_000, 10000)
+        };
+        // 10% a year on 800_000 and 5% of it as fee
+        assert_eq!(loan.liquidation_fee(365 * 24 * 60 * 60), (4_000, 196_000));
+
+        let loan = LoanActiveState {
+            total_amount: 801_000,
+            ..loan
+        };
+        assert_eq!(loan.liquidation_fee(365 * 24 * 60 * 60), (1_000, 0));
+    }
+
+    // The bytes of a deposit created before versioning, the account is sized for an active loan
+    fn legacy_deposit_data(deposit_id: &Pubkey, state: &[u8]) -> Vec<u8> {
+        let mut data = NFTDeposit::discriminator().to_vec();
+        data.extend_from_slice(deposit_id.as_ref());
+        data.extend_from_slice(state);
+        data.resize(LegacyNFTDeposit::account_size(), 0);
+        data
+    }
+
+    #[test]
+    fn loan_on_a_migrated_pool_is_started_and_repaid() {
+        let mut pool = LegacyNFTPool {
+            bump_seed: 254,
+            max_loan_duration: 30 * 24 * 60 * 60,
+            service_fee_rate: 500,
+            interest_rate: 100, // 1% per day
+            mortgage_rate: 8000,
+            ..LegacyNFTPool::default()
+        }
+        .upgrade(9, 6, 0);
+        let (lender, borrower) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut deposit = NFTDeposit {
+            version: NFTDeposit::VERSION,
+            deposit_id: 0,
+            state: DepositState::PendingLoan,
         };
+        deposit
+            .start_borrow(
+                lender,
+                borrower,
+                Pubkey::default(),
+                1_000_000,
+                800_000,
+                0,
+                LoanTerms {
+                    term: LoanTerm::Fixed(pool.max_loan_duration),
+                    interest_model: pool.interest_model,
+                    service_fee_rate: pool.service_fee_rate,
+                    installment_period: None,
+                    min_interest_duration: pool.min_interest_duration,
+                    borrow_referrer: None,
+                    bid_referrer: None,
+                },
+            )
+            .unwrap();
+        pool.record_loan_started(800_000);
+        pool.lock_dai(200_000);
+
+        let loan = deposit.get_active_state().unwrap();
+        let (interest, fee) = loan.interest_and_fee(loan.interest_duration(24 * 60 * 60));
+        assert_eq!(interest, 8_000);
+        assert_eq!(fee, 400);
+        // the insurance fund and the treasury created on restore share the fee
+        assert!(pool.insurance_share(fee) <= fee);
+
+        deposit.repay(loan.total_amount + interest - fee).unwrap();
+        pool.record_loan_repaid(loan.borrowed_amount, interest);
+        pool.lock_dai(loan.borrowed_amount + interest - fee);
 
-        let acc_size = 8 + largest_instance.try_to_vec().unwrap().len();
-        acc_size
+        let lender_withdrawable = deposit.get_repayed_state().unwrap().lender_withdrawable;
+        assert_eq!(lender_withdrawable, 1_007_600);
+        assert_eq!(pool.dai_locked, lender_withdrawable);
+
+        pool.unlock_dai(lender_withdrawable).unwrap();
+        assert_eq!(pool.active_loans, 0);
+        assert_eq!(pool.interest_paid, 8_000);
+        assert_eq!(pool.dai_locked, 0);
+    }
+
+    #[test]
+    fn legacy_active_loan_is_upgraded() {
+        let (deposit_id, lender, borrower) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let mut state = vec![1u8]; // LoanActive
+        state.extend_from_slice(&1000u64.to_le_bytes()); // total_amount
+        state.extend_from_slice(&800u64.to_le_bytes()); // borrowed_amount
+        state.extend_from_slice(&100i64.to_le_bytes()); // started_at
+        state.extend_from_slice(&200i64.to_le_bytes()); // expired_at
+        state.extend_from_slice(lender.as_ref());
+        let data = legacy_deposit_data(&deposit_id, &state);
+        assert_eq!(data.len(), 105);
+
+        let legacy: LegacyNFTDeposit =
+            versioning::decode_legacy_data::<NFTDeposit, _>(&data).unwrap();
+        assert_eq!(legacy.deposit_id, deposit_id);
+        assert_eq!(legacy.lender(), Some(lender));
+        assert_eq!(legacy.active_principal(), Some(800));
+
+        let pool = pool();
+        let deposit = legacy.upgrade(3, borrower, &pool);
+        assert_eq!(deposit.version, NFTDeposit::VERSION);
+        assert_eq!(deposit.deposit_id, 3);
+
+        let loan = deposit.get_active_state().unwrap();
+        assert_eq!(loan.total_amount, 1000);
+        assert_eq!(loan.borrowed_amount, 800);
+        assert_eq!(loan.started_at, 100);
+        assert_eq!(loan.expired_at, 200);
+        assert_eq!(loan.interest_model, pool.interest_model);
+        assert_eq!(loan.service_fee_rate, pool.service_fee_rate);
+        assert_eq!(loan.min_interest_until, 100);
+        assert_eq!(loan.lender, lender);
+        assert_eq!(loan.borrower, borrower);
+        assert_eq!(loan.collection, Pubkey::default());
+        assert!(loan.installments.is_none() && loan.call_notice.is_none());
+    }
+
+    #[test]
+    fn legacy_settled_deposits_are_upgraded() {
+        let deposit_id = Pubkey::new_unique();
+        let lender = Pubkey::new_unique();
+
+        let legacy: LegacyNFTDeposit = versioning::decode_legacy_data::<NFTDeposit, _>(
+            &legacy_deposit_data(&deposit_id, &[0]),
+        )
+        .unwrap();
+        assert_eq!(legacy.lender(), None);
+        assert!(legacy
+            .upgrade(0, Pubkey::default(), &pool())
+            .is_pending_loan());
+
+        let mut state = vec![2u8]; // LoanRepayed
+        state.extend_from_slice(&5u64.to_le_bytes()); // tai_required_to_unlock
+        state.extend_from_slice(&900u64.to_le_bytes()); // lender_withdrawable
+        state.extend_from_slice(lender.as_ref());
+        let legacy: LegacyNFTDeposit = versioning::decode_legacy_data::<NFTDeposit, _>(
+            &legacy_deposit_data(&deposit_id, &state),
+        )
+        .unwrap();
+        assert_eq!(legacy.lender(), Some(lender));
+        assert_eq!(legacy.active_principal(), None);
+        let repayed = legacy
+            .upgrade(0, Pubkey::default(), &pool())
+            .get_repayed_state()
+            .unwrap();
+        assert_eq!(repayed.lender_withdrawable, 900);
+        assert_eq!(repayed.lender, lender);
+
+        let legacy: LegacyNFTDeposit = versioning::decode_legacy_data::<NFTDeposit, _>(
+            &legacy_deposit_data(&deposit_id, &[4]),
+        )
+        .unwrap();
+        assert_eq!(legacy.lender(), None);
+        let liquidated = legacy
+            .upgrade(0, Pubkey::default(), &pool())
+            .get_liquidated_state()
+            .unwrap();
+        assert_eq!(liquidated.borrowed_amount, 0);
     }
 }
@@ -1,17 +1,113 @@
-use crate::{utils, DerivedAccountIdentifier, NFTPool, liqzError};
+use crate::{
+    utils, DerivedAccountIdentifier, EventLoanSettingChanged, InterestModel, NFTPool, StakeTier,
+    Legacy, Versioned, RESERVED_SPACE, liqzError,
+};
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::token::{Mint, TokenAccount};
+use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
-use std::convert::TryInto;
-
-static SECONDS_PER_DAY: u64 = 24 * 60 * 60;
 
 type Result<T> = std::result::Result<T, ProgramError>;
 
+// A change of the loan settings, None leaves the setting as it is
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoanSettings {
+    pub incentive: Option<u64>,
+    pub interest_model: Option<InterestModel>,
+    pub service_fee_rate: Option<u64>,
+    pub max_loan_duration: Option<i64>,
+    pub mortgage_rate: Option<u64>,
+    pub min_interest_duration: Option<i64>,
+    pub origination_fee: Option<u64>,
+    pub referral_fee_rate: Option<u64>,
+    pub insurance_fee_rate: Option<u64>,
+}
+
+impl LoanSettings {
+    // Every setting is changed, with the largest interest model, to size accounts holding a change
+    pub fn largest() -> Self {
+        Self {
+            incentive: Some(0),
+            interest_model: Some(InterestModel::largest()),
+            service_fee_rate: Some(0),
+            max_loan_duration: Some(0),
+            mortgage_rate: Some(0),
+            min_interest_duration: Some(0),
+            origination_fee: Some(0),
+            referral_fee_rate: Some(0),
+            insurance_fee_rate: Some(0),
+        }
+    }
+}
+
 impl DerivedAccountIdentifier for NFTPool {
     const SEED: &'static [u8] = b"liqzNFTPool";
 }
 
+impl Versioned for NFTPool {
+    const VERSION: u8 = 1;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    // size the account so that any interest model and a credential authority can be set later
+    fn account_size(&self) -> u64 {
+        let largest_instance = Self {
+            interest_model: InterestModel::largest(),
+            credential_authority: Some(Pubkey::default()),
+            ..self.clone()
+        };
+
+        8 + largest_instance.try_to_vec().unwrap().len() as u64 + RESERVED_SPACE
+    }
+}
+
+// The pool as it was laid out before versioning, see migrate_pool
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct LegacyNFTPool {
+    pub bump_seed: u8,
+    pub owner: Pubkey,
+    pub liz_mint: Pubkey,
+    pub tai_mint: Pubkey,
+    pub dai_mint: Pubkey,
+    pub incentive: u64,
+    pub max_loan_duration: i64,
+    pub service_fee_rate: u64,
+    pub interest_rate: u64, // in bp per day
+    pub mortgage_rate: u64,
+}
+
+impl Legacy for LegacyNFTPool {
+    fn account_size() -> usize {
+        8 + Self::default().try_to_vec().unwrap().len()
+    }
+}
+
+impl LegacyNFTPool {
+    // The settings the pool had are kept, the ones added since start at their defaults.
+    // A legacy pool does not record what it owes to lenders, all the DAI it holds stays locked.
+    pub fn upgrade(&self, liz_decimals: u8, dai_decimals: u8, dai_held: u64) -> NFTPool {
+        NFTPool {
+            incentive: self.incentive,
+            max_loan_duration: self.max_loan_duration,
+            service_fee_rate: self.service_fee_rate,
+            interest_model: InterestModel::from_daily_rate(self.interest_rate),
+            mortgage_rate: self.mortgage_rate,
+            dai_locked: dai_held,
+            ..NFTPool::with_defaults(
+                self.bump_seed,
+                self.owner,
+                self.liz_mint,
+                liz_decimals,
+                self.tai_mint,
+                self.dai_mint,
+                dai_decimals,
+            )
+        }
+    }
+}
+
 impl NFTPool {
     #[throws(ProgramError)]
     pub fn new_checked<'info>(
@@ -27,26 +123,17 @@ impl NFTPool {
         let (_, bump) = NFTPool::get_address_with_bump(program_id);
         NFTPool::verify_address(program_id, bump, &pool.key)?;
 
-        let instance = Self {
-            bump_seed: bump,
-            owner: *pool_owner.key,
-            liz_mint: *liz_mint.to_account_info().key,
-            tai_mint: *tai_mint.to_account_info().key,
-            dai_mint: *dai_mint.to_account_info().key,
-            incentive: 100 * 10u64.pow(liz_mint.decimals as u32) as u64,
-            max_loan_duration: 30 * 24 * 60 * 60, // 30 days
-            // 5%
-            service_fee_rate: 500,
-            // 1%
-            interest_rate: 100,
-            // 90%
-            mortgage_rate: 9000,
-        };
+        let instance = Self::with_defaults(
+            bump,
+            *pool_owner.key,
+            *liz_mint.to_account_info().key,
+            liz_mint.decimals,
+            *tai_mint.to_account_info().key,
+            *dai_mint.to_account_info().key,
+            dai_mint.decimals,
+        );
 
-        let acc_size = 8 + instance
-            .try_to_vec()
-            .map_err(|_| ProgramError::Custom(1))?
-            .len() as u64;
+        let acc_size = instance.account_size();
 
         // allocate the space for the contract account
         utils::create_derived_account_with_seed(
@@ -69,6 +156,89 @@ impl NFTPool {
         ProgramAccount::try_from(pool)?
     }
 
+    // The settings a new pool starts with
+    pub(crate) fn with_defaults(
+        bump: u8,
+        owner: Pubkey,
+        liz_mint: Pubkey,
+        liz_decimals: u8,
+        tai_mint: Pubkey,
+        dai_mint: Pubkey,
+        dai_decimals: u8,
+    ) -> Self {
+        let liz = 10u64.pow(liz_decimals as u32);
+        let dai = 10u64.pow(dai_decimals as u32);
+
+        Self {
+            version: Self::VERSION,
+            bump_seed: bump,
+            owner,
+            liz_mint,
+            tai_mint,
+            dai_mint,
+            incentive: 100 * liz,
+            max_loan_duration: 30 * 24 * 60 * 60, // 30 days
+            // 5%
+            service_fee_rate: 500,
+            // 1% per day
+            interest_model: InterestModel::SimpleApr(36500),
+            // 90%
+            mortgage_rate: 9000,
+            min_interest_duration: 24 * 60 * 60, // 1 day
+            origination_fee: 0,
+            dai_fees_accrued: 0,
+            // 10% of the fee for each referrer
+            referral_fee_rate: 1000,
+            // 10% of the fee
+            insurance_fee_rate: 1000,
+            insurance_paid_out: 0,
+            stake_tiers: [
+                StakeTier {
+                    min_stake: 1000 * liz,
+                    fee_discount: 1000,
+                    mortgage_boost: 100,
+                },
+                StakeTier {
+                    min_stake: 10000 * liz,
+                    fee_discount: 2500,
+                    mortgage_boost: 250,
+                },
+                StakeTier {
+                    min_stake: 100000 * liz,
+                    fee_discount: 5000,
+                    mortgage_boost: 500,
+                },
+            ],
+            // 95%
+            max_mortgage_rate: 9500,
+            unstake_cooldown: 7 * 24 * 60 * 60, // 7 days
+            liz_staked: 0,
+            proposal_voting_period: 3 * 24 * 60 * 60, // 3 days
+            // 20% of the staked LIZ
+            proposal_quorum: 2000,
+            // more than 50% of the votes
+            proposal_threshold: 5000,
+            // no caps until the owner sets them
+            max_collection_principal: u64::MAX,
+            max_borrower_principal: u64::MAX,
+            max_borrower_open_loans: u64::MAX,
+            credential_authority: None,
+            // disabled until the owner sets the repayments needed
+            reputation_min_repaid: 0,
+            reputation_mortgage_boost: 200,
+            total_deposits: 0,
+            active_loans: 0,
+            outstanding_principal: 0,
+            interest_paid: 0,
+            liquidations: 0,
+            default_volume: 0,
+            dai_locked: 0,
+            // 100 DAI for at least a week
+            reputation_min_principal: 100 * dai,
+            reputation_min_duration: 7 * 24 * 60 * 60,
+        }
+    }
+
     pub fn ensure_pool_token_account<'info>(
         pool: &ProgramAccount<'info, NFTPool>,
         mint: &CpiAccount<'info, Mint>,
@@ -120,24 +290,193 @@ impl NFTPool {
         Ok(())
     }
 
-    pub fn calculate_interest_and_fee(&self, borrowed_amount: u64, duration: i64) -> (u64, u64) {
-        let interest = borrowed_amount
-            .checked_mul(self.interest_rate)
+    // Both the owner and the governance change the settings through here
+    #[throws(liqzError)]
+    pub fn change_loan_settings(&mut self, settings: &LoanSettings) {
+        let referral_fee_rate = settings.referral_fee_rate.unwrap_or(self.referral_fee_rate);
+        let insurance_fee_rate = settings
+            .insurance_fee_rate
+            .unwrap_or(self.insurance_fee_rate);
+
+        // a loan has at most two referrers
+        if referral_fee_rate > 5000 {
+            throw!(liqzError::ReferralFeeRateTooHigh);
+        }
+
+        // the referrers and the insurance fund share the same fee
+        let fee_shares = referral_fee_rate
+            .checked_mul(2)
+            .and_then(|shares| shares.checked_add(insurance_fee_rate));
+        if insurance_fee_rate > 10000 || !matches!(fee_shares, Some(shares) if shares <= 10000) {
+            throw!(liqzError::FeeSharesTooHigh);
+        }
+
+        // rates are in bp and a loan has to last
+        if matches!(settings.service_fee_rate, Some(rate) if rate > 10000)
+            || matches!(settings.mortgage_rate, Some(rate) if rate > 10000)
+            || matches!(settings.max_loan_duration, Some(duration) if duration <= 0)
+            || matches!(settings.min_interest_duration, Some(duration) if duration < 0)
+        {
+            throw!(liqzError::LoanSettingsNotAllowed);
+        }
+
+        settings.incentive.map(|v| self.incentive = v);
+        settings.interest_model.map(|v| self.interest_model = v);
+        settings.service_fee_rate.map(|v| self.service_fee_rate = v);
+        settings
+            .max_loan_duration
+            .map(|v| self.max_loan_duration = v);
+        settings.mortgage_rate.map(|v| self.mortgage_rate = v);
+        settings
+            .min_interest_duration
+            .map(|v| self.min_interest_duration = v);
+        settings.origination_fee.map(|v| self.origination_fee = v);
+        settings
+            .referral_fee_rate
+            .map(|v| self.referral_fee_rate = v);
+        settings
+            .insurance_fee_rate
+            .map(|v| self.insurance_fee_rate = v);
+
+        emit!(EventLoanSettingChanged {
+            incentive: self.incentive,
+            interest_model: self.interest_model,
+            service_fee_rate: self.service_fee_rate,
+            max_loan_duration: self.max_loan_duration,
+            mortgage_rate: self.mortgage_rate,
+            min_interest_duration: self.min_interest_duration,
+            origination_fee: self.origination_fee,
+            referral_fee_rate: self.referral_fee_rate,
+            insurance_fee_rate: self.insurance_fee_rate,
+        });
+    }
+
+    // The mortgage rate of a borrower, boosted by the stake tier and the reputation up to the pool cap
+    pub fn borrower_mortgage_rate(&self, tier: Option<StakeTier>, reputation_boost: u64) -> u64 {
+        let boost = tier.map(|tier| tier.mortgage_boost).unwrap_or(0);
+
+        self.mortgage_rate
+            .checked_add(boost)
             .unwrap()
-            .checked_mul(duration.try_into().unwrap())
+            .checked_add(reputation_boost)
             .unwrap()
-            .checked_div(SECONDS_PER_DAY)
+            .min(self.max_mortgage_rate.max(self.mortgage_rate))
+    }
+
+    // The service fee rate of a borrower, discounted by the stake tier
+    pub fn staked_service_fee_rate(&self, tier: Option<StakeTier>) -> u64 {
+        let discount = tier.map(|tier| tier.fee_discount).unwrap_or(0);
+
+        self.service_fee_rate
+            .checked_mul(10000 - discount)
             .unwrap()
             .checked_div(10000)
-            .unwrap();
-        let fee = interest
-            .checked_mul(self.service_fee_rate)
+            .unwrap()
+    }
+
+    // The share of the fee paid to each referrer of a loan
+    pub fn referral_share(&self, fee: u64) -> u64 {
+        fee.checked_mul(self.referral_fee_rate)
             .unwrap()
             .checked_div(10000)
-            .unwrap();
+            .unwrap()
+    }
+
+    // The cut of the fee kept in the insurance fund
+    pub fn insurance_share(&self, fee: u64) -> u64 {
+        fee.checked_mul(self.insurance_fee_rate)
+            .unwrap()
+            .checked_div(10000)
+            .unwrap()
+    }
+
+    // Fees are kept in the DAI treasury, this only keeps the count
+    pub fn accrue_fee(&mut self, fee: u64) {
+        self.dai_fees_accrued = self.dai_fees_accrued.checked_add(fee).unwrap();
+    }
 
-        (interest, fee)
+    // The statistics below only keep the count, the assets are held elsewhere
+    pub fn record_deposit(&mut self) {
+        self.total_deposits = self.total_deposits.checked_add(1).unwrap();
     }
+
+    pub fn record_loan_started(&mut self, principal: u64) {
+        self.active_loans = self.active_loans.checked_add(1).unwrap();
+        self.outstanding_principal = self.outstanding_principal.checked_add(principal).unwrap();
+    }
+
+    // Interest paid in installments is recorded as it is paid
+    pub fn record_interest(&mut self, interest: u64) {
+        self.interest_paid = self.interest_paid.checked_add(interest).unwrap();
+    }
+
+    pub fn record_loan_repaid(&mut self, principal: u64, interest: u64) {
+        self.close_loan(principal);
+        self.record_interest(interest);
+    }
+
+    pub fn record_liquidation(&mut self, principal: u64) {
+        self.close_loan(principal);
+        self.liquidations = self.liquidations.checked_add(1).unwrap();
+        self.default_volume = self.default_volume.checked_add(principal).unwrap();
+    }
+
+    // Loans started before the statistics were kept were never counted in
+    fn close_loan(&mut self, principal: u64) {
+        self.active_loans = self.active_loans.saturating_sub(1);
+        self.outstanding_principal = self.outstanding_principal.saturating_sub(principal);
+    }
+
+    // The DAI locked for lenders is held in the DAI ATA of the pool, the one sweep checks it against
+    #[throws(ProgramError)]
+    pub fn verify_dai_account(
+        pool: &ProgramAccount<NFTPool>,
+        pool_dai_account: &CpiAccount<TokenAccount>,
+    ) {
+        if pool_dai_account.to_account_info().key
+            != &spl_associated_token_account::get_associated_token_address(
+                pool.to_account_info().key,
+                &pool.dai_mint,
+            )
+        {
+            throw!(liqzError::PoolDaiAccountNotCorrect);
+        }
+    }
+
+    // The DAI held in the pool for lenders, anything above can be swept
+    pub fn lock_dai(&mut self, amount: u64) {
+        self.dai_locked = self.dai_locked.checked_add(amount).unwrap();
+    }
+
+    #[throws(liqzError)]
+    pub fn unlock_dai(&mut self, amount: u64) {
+        self.dai_locked = self
+            .dai_locked
+            .checked_sub(amount)
+            .ok_or(liqzError::DaiLockedUnderflow)?;
+    }
+
+    // Tokens sent to the pool by mistake. NFTs may be escrowed, LIZ funds the deposit incentives
+    // and the pool issues TAI and the lender positions, none of them is ever swept.
+    #[throws(liqzError)]
+    pub fn sweepable(
+        &self,
+        mint: &Pubkey,
+        decimals: u8,
+        issued_by_pool: bool,
+        balance: u64,
+    ) -> u64 {
+        if decimals == 0 || issued_by_pool || mint == &self.liz_mint || mint == &self.tai_mint {
+            throw!(liqzError::SweepNotAllowed);
+        }
+
+        if mint == &self.dai_mint {
+            balance.saturating_sub(self.dai_locked)
+        } else {
+            balance
+        }
+    }
+
     pub fn get_address(program_id: &Pubkey) -> Pubkey {
         Self::get_address_with_bump(program_id).0
     }
@@ -155,3 +494,209 @@ impl NFTPool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::pool;
+    use crate::{versioning, NFTBid};
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn loan_statistics_follow_the_loans() {
+        let mut pool = pool();
+        pool.record_loan_started(100);
+        pool.record_loan_started(50);
+        pool.record_loan_repaid(100, 7);
+        pool.record_liquidation(50);
+
+        assert_eq!(pool.active_loans, 0);
+        assert_eq!(pool.outstanding_principal, 0);
+        assert_eq!(pool.interest_paid, 7);
+        assert_eq!(pool.liquidations, 1);
+        assert_eq!(pool.default_volume, 50);
+    }
+
+    #[test]
+    fn closing_a_loan_started_before_the_statistics_does_not_underflow() {
+        let mut pool = pool();
+        pool.record_loan_started(50);
+        pool.record_loan_repaid(100, 0);
+        pool.record_liquidation(100);
+
+        assert_eq!(pool.active_loans, 0);
+        assert_eq!(pool.outstanding_principal, 0);
+        assert_eq!(pool.default_volume, 100);
+    }
+
+    #[test]
+    fn overflowing_fee_shares_are_rejected_before_any_change() {
+        let mut pool = pool();
+        let settings = LoanSettings {
+            incentive: Some(1),
+            insurance_fee_rate: Some(u64::MAX - 1000),
+            ..LoanSettings::default()
+        };
+
+        assert!(pool.change_loan_settings(&settings).is_err());
+        assert_eq!(pool.insurance_fee_rate, 1000);
+        assert_ne!(pool.incentive, 1);
+
+        let settings = LoanSettings {
+            referral_fee_rate: Some(4000),
+            insurance_fee_rate: Some(2001),
+            ..LoanSettings::default()
+        };
+        assert!(pool.change_loan_settings(&settings).is_err());
+        assert_eq!(pool.referral_fee_rate, 1000);
+    }
+
+    #[test]
+    fn out_of_range_loan_settings_are_rejected_before_any_change() {
+        let mut pool = pool();
+        let rejected = [
+            LoanSettings {
+                service_fee_rate: Some(10001),
+                ..LoanSettings::default()
+            },
+            LoanSettings {
+                mortgage_rate: Some(10001),
+                ..LoanSettings::default()
+            },
+            LoanSettings {
+                max_loan_duration: Some(0),
+                ..LoanSettings::default()
+            },
+            LoanSettings {
+                min_interest_duration: Some(-1),
+                ..LoanSettings::default()
+            },
+        ];
+
+        for settings in rejected.iter() {
+            let settings = LoanSettings {
+                incentive: Some(1),
+                ..*settings
+            };
+            assert!(pool.change_loan_settings(&settings).is_err());
+            assert_ne!(pool.incentive, 1);
+        }
+
+        let settings = LoanSettings {
+            service_fee_rate: Some(10000),
+            mortgage_rate: Some(10000),
+            max_loan_duration: Some(1),
+            min_interest_duration: Some(0),
+            ..LoanSettings::default()
+        };
+        pool.change_loan_settings(&settings).unwrap();
+        assert_eq!(pool.max_loan_duration, 1);
+    }
+
+    #[test]
+    fn dai_above_the_locked_amount_is_sweepable() {
+        let mut pool = pool();
+        let dai = pool.dai_mint;
+        pool.lock_dai(100);
+
+        assert_eq!(pool.sweepable(&dai, 9, false, 150).unwrap(), 50);
+        assert_eq!(pool.sweepable(&dai, 9, false, 80).unwrap(), 0);
+    }
+
+    #[test]
+    fn stray_tokens_are_sweepable() {
+        let pool = pool();
+        assert_eq!(
+            pool.sweepable(&Pubkey::new_unique(), 6, false, 42).unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn pool_tokens_and_nfts_are_never_swept() {
+        let pool = pool();
+        let stray = Pubkey::new_unique();
+
+        assert!(pool.sweepable(&pool.liz_mint, 9, false, 1).is_err());
+        assert!(pool.sweepable(&pool.tai_mint, 9, false, 1).is_err());
+        assert!(pool.sweepable(&stray, 0, false, 1).is_err());
+        assert!(pool.sweepable(&stray, 6, true, 1).is_err());
+    }
+
+    #[test]
+    fn unlocking_more_dai_than_locked_fails() {
+        let mut pool = pool();
+        pool.lock_dai(100);
+
+        assert!(pool.unlock_dai(150).is_err());
+        assert_eq!(pool.dai_locked, 100);
+        pool.unlock_dai(100).unwrap();
+        assert_eq!(pool.dai_locked, 0);
+    }
+
+    // The bytes of a pool created before versioning
+    fn legacy_pool_data(
+        owner: &Pubkey,
+        liz_mint: &Pubkey,
+        tai_mint: &Pubkey,
+        dai_mint: &Pubkey,
+    ) -> Vec<u8> {
+        let mut data = NFTPool::discriminator().to_vec();
+        data.push(254); // bump_seed
+        for key in [owner, liz_mint, tai_mint, dai_mint].iter() {
+            data.extend_from_slice(key.as_ref());
+        }
+        data.extend_from_slice(&(100 * 10u64.pow(9)).to_le_bytes()); // incentive
+        data.extend_from_slice(&(30 * 24 * 60 * 60i64).to_le_bytes()); // max_loan_duration
+        data.extend_from_slice(&500u64.to_le_bytes()); // service_fee_rate
+        data.extend_from_slice(&100u64.to_le_bytes()); // interest_rate, 1% per day
+        data.extend_from_slice(&8000u64.to_le_bytes()); // mortgage_rate
+        data
+    }
+
+    #[test]
+    fn legacy_pool_is_upgraded_with_its_settings() {
+        let (owner, liz_mint, tai_mint, dai_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let data = legacy_pool_data(&owner, &liz_mint, &tai_mint, &dai_mint);
+        assert_eq!(data.len(), LegacyNFTPool::account_size());
+
+        // the current layout cannot read it
+        assert!(NFTPool::try_deserialize(&mut data.as_slice()).is_err());
+
+        let legacy: LegacyNFTPool = versioning::decode_legacy_data::<NFTPool, _>(&data).unwrap();
+        let pool = legacy.upgrade(9, 6, 2_000_000);
+
+        assert_eq!(pool.version, NFTPool::VERSION);
+        assert_eq!(pool.bump_seed, 254);
+        assert_eq!(pool.owner, owner);
+        assert_eq!(pool.liz_mint, liz_mint);
+        assert_eq!(pool.tai_mint, tai_mint);
+        assert_eq!(pool.dai_mint, dai_mint);
+        assert_eq!(pool.incentive, 100 * 10u64.pow(9));
+        assert_eq!(pool.max_loan_duration, 30 * 24 * 60 * 60);
+        assert_eq!(pool.service_fee_rate, 500);
+        assert_eq!(pool.interest_model, InterestModel::SimpleApr(36500));
+        assert_eq!(pool.mortgage_rate, 8000);
+        assert_eq!(pool.dai_locked, 2_000_000);
+        assert_eq!(pool.sweepable(&dai_mint, 6, false, 2_000_000).unwrap(), 0);
+        assert!(pool.account_size() as usize > data.len());
+    }
+
+    #[test]
+    fn only_legacy_pools_are_decoded_as_legacy() {
+        let key = Pubkey::new_unique();
+        let mut data = legacy_pool_data(&key, &key, &key, &key);
+        data[..8].copy_from_slice(&NFTBid::discriminator());
+        assert!(versioning::decode_legacy_data::<NFTPool, LegacyNFTPool>(&data).is_err());
+
+        let pool = pool();
+        let mut data = vec![0u8; pool.account_size() as usize];
+        pool.try_serialize(&mut data.as_mut_slice()).unwrap();
+        assert!(versioning::decode_legacy_data::<NFTPool, LegacyNFTPool>(&data).is_err());
+    }
+}
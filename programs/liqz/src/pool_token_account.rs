@@ -0,0 +1,102 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, NFTPool, liqzError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use fehler::{throw, throws};
+
+// A program derived token account per mint owned by the pool, like the treasury and the insurance fund.
+// Only the pool owner can move the tokens out.
+pub trait PoolTokenAccount: DerivedAccountIdentifier {
+    // Thrown when an account is not the one of the mint
+    const ADDRESS_NOT_CORRECT: liqzError;
+
+    #[throws(ProgramError)]
+    fn create_account<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        mint: &CpiAccount<'info, Mint>,
+        token_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        let mint_key = *mint.to_account_info().key;
+        let (_, bump) = Self::get_address_with_bump(program_id, &mint_key);
+
+        Self::verify_address(program_id, &mint_key, bump, token_account.key)?;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &mint_key.to_bytes(), &[bump]];
+
+        utils::create_derived_token_account_with_seed(
+            funder,
+            seeds_with_bump,
+            token_account,
+            mint,
+            &pool.to_account_info(),
+            rent,
+            spl_program,
+            system_program,
+        )?;
+    }
+
+    // Pools created before the account get it when they are migrated
+    #[throws(ProgramError)]
+    fn ensure_account<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        mint: &CpiAccount<'info, Mint>,
+        token_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        if !utils::is_program_account(token_account, &spl_token::id()) {
+            Self::create_account(
+                program_id,
+                pool,
+                mint,
+                token_account,
+                funder,
+                spl_program,
+                system_program,
+                rent,
+            )?;
+        }
+    }
+
+    // Make sure the tokens go to the account of the mint
+    #[throws(ProgramError)]
+    fn verify_account(
+        program_id: &Pubkey,
+        mint: &Pubkey,
+        token_account: &CpiAccount<TokenAccount>,
+    ) {
+        if token_account.to_account_info().key != &Self::get_address(program_id, mint) {
+            throw!(Self::ADDRESS_NOT_CORRECT);
+        }
+    }
+
+    // The address of the account is computed as follow:
+    // address = find_program_address([Self::SEED, mint_address], program_id)
+    // the owner of the token account is the pool
+    fn get_address(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, mint).0
+    }
+
+    fn get_address_with_bump(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &mint.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    fn verify_address(program_id: &Pubkey, mint: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr =
+            Pubkey::create_program_address(&[Self::SEED, &mint.to_bytes(), &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(Self::ADDRESS_NOT_CORRECT);
+        }
+    }
+}
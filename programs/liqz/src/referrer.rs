@@ -0,0 +1,129 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, Referrer, liqzError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for Referrer {
+    const SEED: &'static [u8] = b"liqzReferrer";
+}
+
+impl Referrer {
+    #[throws(ProgramError)]
+    pub fn register<'info>(
+        program_id: &Pubkey,
+        wallet: &AccountInfo<'info>,
+        referrer_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, wallet.key);
+
+        Self::verify_address(program_id, wallet.key, bump, referrer_account.key)?;
+
+        if utils::is_account_allocated(referrer_account) {
+            throw!(liqzError::ReferrerAlreadyRegistered);
+        }
+
+        let instance = Referrer {
+            loans_settled: 0,
+            fees_earned: 0,
+        };
+
+        let acc_size = 8 + instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &wallet.key.to_bytes(), &[bump]];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            wallet,
+            seeds_with_bump,
+            referrer_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        let mut data = referrer_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // Anchor does not support optional accounts, the referrer account is passed in the remaining accounts
+    #[throws(ProgramError)]
+    pub fn verify_registered(
+        program_id: &Pubkey,
+        wallet: &Pubkey,
+        referrer_account: Option<&AccountInfo>,
+    ) {
+        let referrer_account = referrer_account.ok_or(liqzError::ReferrerNotRegistered)?;
+
+        if referrer_account.key != &Self::get_address(program_id, wallet) {
+            throw!(liqzError::ReferrerAddressNotCorrect);
+        }
+
+        if referrer_account.owner != program_id || !utils::is_account_allocated(referrer_account) {
+            throw!(liqzError::ReferrerNotRegistered);
+        }
+    }
+
+    // Loads the referrers of a loan from the remaining accounts.
+    // Each referrer comes as a pair of its referrer account and its DAI account, in the order of `referrers`.
+    #[throws(ProgramError)]
+    pub fn load_all<'info>(
+        program_id: &Pubkey,
+        referrers: &[Pubkey],
+        accounts: &[AccountInfo<'info>],
+        dai_mint: &Pubkey,
+    ) -> Vec<(
+        ProgramAccount<'info, Referrer>,
+        CpiAccount<'info, TokenAccount>,
+    )> {
+        if accounts.len() < referrers.len() * 2 {
+            throw!(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let mut loaded = vec![];
+        for (wallet, pair) in referrers.iter().zip(accounts.chunks(2)) {
+            Self::verify_registered(program_id, wallet, Some(&pair[0]))?;
+
+            let referrer_dai_account: CpiAccount<TokenAccount> = CpiAccount::try_from(&pair[1])?;
+            assert_eq!(&referrer_dai_account.owner, wallet);
+            assert_eq!(&referrer_dai_account.mint, dai_mint);
+
+            loaded.push((ProgramAccount::try_from(&pair[0])?, referrer_dai_account));
+        }
+
+        loaded
+    }
+
+    pub fn record(&mut self, earned: u64) {
+        self.loans_settled += 1;
+        self.fees_earned = self.fees_earned.checked_add(earned).unwrap();
+    }
+
+    // An program derived account that keeps the stats of a referrer
+    // The address of the account is computed as follow:
+    // address = find_program_address([Referrer::SEED, referrer_wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, wallet: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, wallet).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &wallet.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, wallet: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr =
+            Pubkey::create_program_address(&[Self::SEED, &wallet.to_bytes(), &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(liqzError::ReferrerAddressNotCorrect);
+        }
+    }
+}
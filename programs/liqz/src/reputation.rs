@@ -0,0 +1,243 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::clock::UnixTimestamp;
+
+use crate::{utils, DerivedAccountIdentifier, LoanActiveState, NFTPool, Reputation, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for Reputation {
+    const SEED: &'static [u8] = b"liqzReputation";
+}
+
+impl Reputation {
+    // Creates the reputation account on the first borrow of the wallet
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        borrower: &Pubkey,
+        reputation_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, borrower);
+
+        Self::verify_address(program_id, borrower, bump, reputation_account.key)?;
+
+        if !utils::is_program_account(reputation_account, program_id) {
+            let instance = Reputation {
+                loans_taken: 0,
+                repaid_on_time: 0,
+                repaid_late: 0,
+                liquidated: 0,
+                principal_borrowed: 0,
+            };
+
+            let acc_size = 8 + instance
+                .try_to_vec()
+                .map_err(|_| ProgramError::Custom(1))?
+                .len() as u64;
+
+            let seeds_with_bump: &[&[_]] = &[Self::SEED, &borrower.to_bytes(), &[bump]];
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                funder,
+                seeds_with_bump,
+                reputation_account,
+                acc_size,
+                rent,
+                system,
+            )?;
+
+            {
+                let mut data = reputation_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(reputation_account)?
+    }
+
+    // Loans opened before the reputation accounts were added may have none, nothing is recorded then
+    #[throws(ProgramError)]
+    pub fn update<'info>(
+        program_id: &Pubkey,
+        borrower: &Pubkey,
+        reputation_account: &AccountInfo<'info>,
+        record: impl FnOnce(&mut Self),
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, borrower);
+
+        Self::verify_address(program_id, borrower, bump, reputation_account.key)?;
+
+        if !utils::is_program_account(reputation_account, program_id) {
+            return;
+        }
+
+        let mut reputation: ProgramAccount<Self> = ProgramAccount::try_from(reputation_account)?;
+        record(&mut reputation);
+        reputation.exit(program_id)?;
+    }
+
+    // Borrowers who repaid enough loans on time and were never liquidated borrow more
+    pub fn mortgage_boost(&self, pool: &NFTPool) -> u64 {
+        if pool.reputation_min_repaid > 0
+            && self.repaid_on_time >= pool.reputation_min_repaid
+            && self.liquidated == 0
+        {
+            pool.reputation_mortgage_boost
+        } else {
+            0
+        }
+    }
+
+    pub fn record_borrow(&mut self, principal: u64) {
+        self.loans_taken = self.loans_taken.checked_add(1).unwrap();
+        self.principal_borrowed = self.principal_borrowed.checked_add(principal).unwrap();
+    }
+
+    // A loan repaid after it defaulted counts as late. A loan repaid on time only counts if it is large
+    // and long enough, otherwise the boost could be farmed with dust loans.
+    pub fn record_repay(&mut self, pool: &NFTPool, loan: &LoanActiveState, now: UnixTimestamp) {
+        if loan.is_defaulted(now) {
+            self.repaid_late = self.repaid_late.checked_add(1).unwrap();
+        } else if loan.borrowed_amount >= pool.reputation_min_principal
+            && now.saturating_sub(loan.started_at) >= pool.reputation_min_duration
+        {
+            self.repaid_on_time = self.repaid_on_time.checked_add(1).unwrap();
+        }
+    }
+
+    pub fn record_liquidation(&mut self) {
+        self.liquidated = self.liquidated.checked_add(1).unwrap();
+    }
+
+    // An program derived account that keeps the credit history of a borrower across loans
+    // The address of the account is computed as follow:
+    // address = find_program_address([Reputation::SEED, borrower_wallet_address], program_id)
+    pub fn get_address(program_id: &Pubkey, borrower: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, borrower).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, borrower: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &borrower.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, borrower: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &borrower.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::ReputationAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, loan};
+
+    static DAI: u64 = 1_000_000_000;
+    static DAY: i64 = 24 * 60 * 60;
+
+    fn pool() -> NFTPool {
+        let mut pool = test_utils::pool();
+        pool.reputation_min_repaid = 2;
+        pool
+    }
+
+    fn reputation() -> Reputation {
+        Reputation {
+            loans_taken: 0,
+            repaid_on_time: 0,
+            repaid_late: 0,
+            liquidated: 0,
+            principal_borrowed: 0,
+        }
+    }
+
+    #[test]
+    fn boost_after_enough_loans_repaid_on_time() {
+        let pool = pool();
+        let mut reputation = reputation();
+        let loan = loan(100 * DAI, 30 * DAY);
+
+        reputation.record_repay(&pool, &loan, 7 * DAY);
+        assert_eq!(reputation.mortgage_boost(&pool), 0);
+
+        reputation.record_repay(&pool, &loan, 8 * DAY);
+        assert_eq!(
+            reputation.mortgage_boost(&pool),
+            pool.reputation_mortgage_boost
+        );
+    }
+
+    #[test]
+    fn dust_and_short_loans_do_not_count() {
+        let pool = pool();
+        let mut reputation = reputation();
+
+        for _ in 0..10 {
+            reputation.record_repay(&pool, &loan(1, 30 * DAY), 7 * DAY);
+            reputation.record_repay(&pool, &loan(100 * DAI, 30 * DAY), 60);
+        }
+
+        assert_eq!(reputation.repaid_on_time, 0);
+        assert_eq!(reputation.mortgage_boost(&pool), 0);
+    }
+
+    #[test]
+    fn late_repayments_and_liquidations_give_no_boost() {
+        let pool = pool();
+        let mut reputation = reputation();
+        let loan = loan(100 * DAI, 7 * DAY);
+
+        reputation.record_repay(&pool, &loan, 8 * DAY);
+        assert_eq!(reputation.repaid_late, 1);
+
+        reputation.repaid_on_time = 10;
+        reputation.record_liquidation();
+        assert_eq!(reputation.mortgage_boost(&pool), 0);
+    }
+
+    #[test]
+    fn boost_is_disabled_without_min_repaid() {
+        let mut pool = pool();
+        pool.reputation_min_repaid = 0;
+        let mut reputation = reputation();
+        reputation.repaid_on_time = 10;
+
+        assert_eq!(reputation.mortgage_boost(&pool), 0);
+    }
+
+    #[test]
+    fn reputation_address_funded_by_someone_else_has_no_history() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let reputation_account =
+            test_utils::funded_address(Reputation::get_address(&program_id, &borrower));
+
+        Reputation::update(&program_id, &borrower, &reputation_account, |reputation| {
+            reputation.record_liquidation()
+        })
+        .unwrap();
+
+        let reputation = Reputation::ensure(
+            &program_id,
+            &borrower,
+            &reputation_account,
+            &test_utils::wallet(),
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+        assert_eq!(reputation.liquidated, 0);
+    }
+}
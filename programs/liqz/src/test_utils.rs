@@ -0,0 +1,166 @@
+// Shared by the unit tests. The system program is emulated so that the account creation can run outside
+// of the runtime, the instructions invoked are recorded for the tests to inspect.
+use crate::{InterestModel, LoanActiveState, NFTPool};
+use anchor_lang::prelude::*;
+use solana_program::clock::UnixTimestamp;
+use solana_program::instruction::Instruction;
+use solana_program::program_stubs::{self, SyscallStubs};
+use solana_program::system_instruction::SystemInstruction;
+use solana_program::{system_program, sysvar};
+use solana_sdk::program_utils::limited_deserialize;
+use std::cell::RefCell;
+use std::sync::Once;
+
+thread_local! {
+    static INVOKED: RefCell<Vec<Instruction>> = RefCell::new(vec![]);
+}
+
+struct SystemProgramStub;
+
+impl SyscallStubs for SystemProgramStub {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+
+        if !system_program::check_id(&instruction.program_id) {
+            return Ok(());
+        }
+
+        let info = |i: usize| {
+            account_infos
+                .iter()
+                .find(|info| info.key == &instruction.accounts[i].pubkey)
+                .unwrap()
+        };
+
+        match limited_deserialize(&instruction.data).unwrap() {
+            SystemInstruction::CreateAccount {
+                lamports, space, ..
+            } => {
+                // the runtime refuses an address that already holds lamports
+                if info(1).lamports() > 0 {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                transfer(info(0), info(1), lamports);
+                allocate(info(1), space);
+            }
+            SystemInstruction::Transfer { lamports } => transfer(info(0), info(1), lamports),
+            SystemInstruction::Allocate { space } => allocate(info(0), space),
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) {
+    **from.lamports.borrow_mut() -= lamports;
+    **to.lamports.borrow_mut() += lamports;
+}
+
+fn allocate(account: &AccountInfo, space: u64) {
+    *account.data.borrow_mut() = Box::leak(vec![0u8; space as usize].into_boxed_slice());
+}
+
+// Called by a test before it invokes anything
+pub fn emulate_system_program() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(SystemProgramStub));
+    });
+
+    invoked();
+}
+
+// Takes the instructions invoked so far by the current test
+pub fn invoked() -> Vec<Instruction> {
+    INVOKED.with(|invoked| invoked.borrow_mut().drain(..).collect())
+}
+
+pub fn system_instructions(instructions: &[Instruction]) -> Vec<SystemInstruction> {
+    instructions
+        .iter()
+        .filter(|ix| system_program::check_id(&ix.program_id))
+        .map(|ix| limited_deserialize(&ix.data).unwrap())
+        .collect()
+}
+
+pub fn account(key: Pubkey, lamports: u64, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        false,
+        true,
+        Box::leak(Box::new(lamports)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+// An address someone sent lamports to before it was created
+pub fn funded_address(key: Pubkey) -> AccountInfo<'static> {
+    account(key, 1, system_program::id(), vec![])
+}
+
+pub fn wallet() -> AccountInfo<'static> {
+    account(
+        Pubkey::new_unique(),
+        1_000_000_000_000,
+        system_program::id(),
+        vec![],
+    )
+}
+
+pub fn system() -> AccountInfo<'static> {
+    account(system_program::id(), 1, Pubkey::default(), vec![])
+}
+
+pub fn rent() -> Sysvar<'static, Rent> {
+    use solana_program::sysvar::Sysvar as _;
+
+    let mut info = account(
+        sysvar::rent::id(),
+        1,
+        sysvar::id(),
+        vec![0; Rent::size_of()],
+    );
+    Rent::default().to_account_info(&mut info).unwrap();
+    Sysvar::from_account_info(&info).unwrap()
+}
+
+pub fn pool() -> NFTPool {
+    NFTPool::with_defaults(
+        255,
+        Pubkey::default(),
+        Pubkey::new_unique(),
+        9,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        9,
+    )
+}
+
+pub fn loan(borrowed_amount: u64, expired_at: UnixTimestamp) -> LoanActiveState {
+    LoanActiveState {
+        total_amount: borrowed_amount,
+        borrowed_amount,
+        started_at: 0,
+        expired_at,
+        interest_model: InterestModel::SimpleApr(1000),
+        service_fee_rate: 500,
+        call_notice: None,
+        called_at: None,
+        installments: None,
+        min_interest_until: 0,
+        borrow_referrer: None,
+        bid_referrer: None,
+        lender: Pubkey::default(),
+        borrower: Pubkey::default(),
+        collection: Pubkey::default(),
+    }
+}
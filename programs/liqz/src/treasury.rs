@@ -0,0 +1,13 @@
+use crate::{DerivedAccountIdentifier, PoolTokenAccount, liqzError};
+
+// The treasury is a token account per mint that collects all the fees charged by liqz.
+// It is owned by the pool, only the pool owner can claim from it.
+pub struct Treasury;
+
+impl DerivedAccountIdentifier for Treasury {
+    const SEED: &'static [u8] = b"liqzTreasury";
+}
+
+impl PoolTokenAccount for Treasury {
+    const ADDRESS_NOT_CORRECT: liqzError = liqzError::TreasuryAddressNotCorrect;
+}
@@ -1,8 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::Mint;
-use fehler::throws;
+use fehler::{throw, throws};
 use solana_program::{instruction::Instruction, program::invoke_signed, system_program};
-use solana_program::{program::invoke, system_instruction};
+use solana_program::{program::invoke, program_pack::Pack, system_instruction};
 
 #[throws(ProgramError)]
 pub fn create_derived_account_with_seed<'info>(
@@ -16,19 +16,144 @@ pub fn create_derived_account_with_seed<'info>(
 ) {
     let required_lamports = rent.minimum_balance(acc_size as usize).max(1);
 
+    create_derived_account(
+        funder,
+        seeds_with_bump,
+        account,
+        required_lamports,
+        acc_size,
+        program_id,
+        system,
+    )?;
+}
+
+// The system program refuses to create an account holding lamports, and anyone can send some to a program
+// derived address before it is created. Such an account is topped up, allocated and assigned instead.
+#[throws(ProgramError)]
+fn create_derived_account<'info>(
+    funder: &AccountInfo<'info>,
+    seeds_with_bump: &[&[u8]],
+    account: &AccountInfo<'info>,
+    required_lamports: u64,
+    acc_size: u64,
+    owner: &Pubkey,
+    system: &AccountInfo<'info>,
+) {
+    if account.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                funder.key,
+                account.key,
+                required_lamports,
+                acc_size,
+                owner,
+            ),
+            &[funder.clone(), account.clone(), system.clone()],
+            &[seeds_with_bump],
+        )?;
+        return;
+    }
+
+    let shortfall = required_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(funder.key, account.key, shortfall),
+            &[funder.clone(), account.clone(), system.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &system_instruction::allocate(account.key, acc_size),
+        &[account.clone(), system.clone()],
+        &[seeds_with_bump],
+    )?;
+
     invoke_signed(
-        &system_instruction::create_account(
-            funder.key,
-            account.key,
-            required_lamports,
-            acc_size,
-            program_id,
-        ),
-        &[funder.clone(), account.clone(), system.clone()],
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system.clone()],
         &[seeds_with_bump],
     )?;
 }
 
+// Creates a program derived mint, so that the mint address can be found from the seeds
+#[throws(ProgramError)]
+pub fn create_derived_mint_with_seed<'info>(
+    funder: &AccountInfo<'info>,
+    seeds_with_bump: &[&[u8]],
+    mint: &AccountInfo<'info>,
+    mint_authority: &AccountInfo<'info>,
+    decimals: u8,
+    rent: &Sysvar<'info, Rent>,
+    spl_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+) {
+    let acc_size = spl_token::state::Mint::LEN;
+    let required_lamports = rent.minimum_balance(acc_size).max(1);
+
+    create_derived_account(
+        funder,
+        seeds_with_bump,
+        mint,
+        required_lamports,
+        acc_size as u64,
+        &spl_token::id(),
+        system,
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            mint.key,
+            mint_authority.key,
+            None,
+            decimals,
+        )?,
+        &[mint.clone(), rent.to_account_info(), spl_program.clone()],
+    )?;
+}
+
+// Creates a program derived token account, so that the account address can be found from the seeds
+#[throws(ProgramError)]
+pub fn create_derived_token_account_with_seed<'info>(
+    funder: &AccountInfo<'info>,
+    seeds_with_bump: &[&[u8]],
+    token_account: &AccountInfo<'info>,
+    mint: &CpiAccount<'info, Mint>,
+    owner: &AccountInfo<'info>,
+    rent: &Sysvar<'info, Rent>,
+    spl_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+) {
+    let acc_size = spl_token::state::Account::LEN;
+    let required_lamports = rent.minimum_balance(acc_size).max(1);
+
+    create_derived_account(
+        funder,
+        seeds_with_bump,
+        token_account,
+        required_lamports,
+        acc_size as u64,
+        &spl_token::id(),
+        system,
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            token_account.key,
+            mint.to_account_info().key,
+            owner.key,
+        )?,
+        &[
+            token_account.clone(),
+            mint.to_account_info(),
+            owner.clone(),
+            rent.to_account_info(),
+            spl_program.clone(),
+        ],
+    )?;
+}
+
 #[throws(ProgramError)]
 pub fn create_associated_token_account<'info>(
     wallet: &AccountInfo<'info>,
@@ -82,3 +207,103 @@ pub fn is_account_allocated(acc: &AccountInfo) -> bool {
     //    if the account has non zero lamports or has data stored or has the owner != system_program, then this account is already allocated
     acc.lamports() != 0 || !acc.data_is_empty() || !system_program::check_id(&acc.owner)
 }
+
+// A program derived account is only created once it is owned by the program and holds data.
+// Lamports alone do not count, anyone can send some to the address.
+pub fn is_program_account(acc: &AccountInfo, program_id: &Pubkey) -> bool {
+    acc.owner == program_id && !acc.data_is_empty()
+}
+
+// Closes a program owned account, the runtime drops it at the end of the transaction.
+// The data is cleared so that the account cannot be read again in the same transaction.
+#[throws(ProgramError)]
+pub fn close_account<'info>(account: &AccountInfo<'info>, receiver: &AccountInfo<'info>) {
+    let lamports = receiver.lamports().checked_add(account.lamports()).unwrap();
+
+    **receiver.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    for byte in account.try_borrow_mut_data()?.iter_mut() {
+        *byte = 0;
+    }
+}
+
+// The Metaplex token metadata program, we only read its accounts so the crate is not pulled in
+mod metadata_program {
+    solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+pub fn get_metadata_address(nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            &metadata_program::ID.to_bytes(),
+            &nft_mint.to_bytes(),
+        ],
+        &metadata_program::ID,
+    )
+    .0
+}
+
+// Returns the first verified creator of the NFT, which is how collections are identified.
+#[throws(ProgramError)]
+pub fn get_verified_creator(nft_mint: &Pubkey, metadata: &AccountInfo) -> Option<Pubkey> {
+    if &get_metadata_address(nft_mint) != metadata.key
+        || !metadata_program::check_id(metadata.owner)
+    {
+        throw!(ProgramError::InvalidAccountData);
+    }
+
+    parse_verified_creator(&metadata.try_borrow_data()?)?
+}
+
+// Loans are capped per collection, an NFT without metadata or verified creator is a collection of its own
+#[throws(ProgramError)]
+pub fn get_collection(nft_mint: &Pubkey, metadata: &AccountInfo) -> Pubkey {
+    if &get_metadata_address(nft_mint) == metadata.key && !is_account_allocated(metadata) {
+        return *nft_mint;
+    }
+
+    get_verified_creator(nft_mint, metadata)?.unwrap_or(*nft_mint)
+}
+
+// The metadata account is laid out as:
+// key(1) update_authority(32) mint(32) name(4+n) symbol(4+n) uri(4+n) seller_fee_basis_points(2)
+// creators(Option<Vec<{address(32) verified(1) share(1)}>>)
+#[throws(ProgramError)]
+pub fn parse_verified_creator(data: &[u8]) -> Option<Pubkey> {
+    let mut offset = 1 + 32 + 32;
+
+    let read_u32 = |offset: usize| -> Result<usize, ProgramError> {
+        let bytes = data
+            .get(offset..offset + 4)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    // name, symbol and uri
+    for _ in 0..3 {
+        offset += 4 + read_u32(offset)?;
+    }
+    offset += 2;
+
+    if data.get(offset) != Some(&1) {
+        return None;
+    }
+    offset += 1;
+
+    let count = read_u32(offset)?;
+    offset += 4;
+
+    for _ in 0..count {
+        let creator = data
+            .get(offset..offset + 34)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        if creator[32] == 1 {
+            return Some(Pubkey::new(&creator[..32]));
+        }
+        offset += 34;
+    }
+
+    None
+}
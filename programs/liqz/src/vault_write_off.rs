@@ -0,0 +1,91 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, VaultWriteOff, liqzError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for VaultWriteOff {
+    const SEED: &'static [u8] = b"liqzVaultWriteOff";
+}
+
+impl VaultWriteOff {
+    // Books an NFT the vault takes from a liquidated loan, the account lives until the NFT is sold
+    #[throws(ProgramError)]
+    pub fn create<'info>(
+        program_id: &Pubkey,
+        nft_mint: &Pubkey,
+        booked_value: u64,
+        funder: &AccountInfo<'info>,
+        write_off_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, nft_mint);
+
+        Self::verify_address(program_id, nft_mint, bump, write_off_account.key)?;
+
+        let instance = VaultWriteOff { booked_value };
+
+        let acc_size = 8 + instance
+            .try_to_vec()
+            .map_err(|_| ProgramError::Custom(1))?
+            .len() as u64;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &nft_mint.to_bytes(), &[bump]];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            funder,
+            seeds_with_bump,
+            write_off_account,
+            acc_size,
+            rent,
+            system,
+        )?;
+
+        let mut data = write_off_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // Only NFTs the vault wrote off can be sold
+    #[throws(ProgramError)]
+    pub fn load<'info>(
+        program_id: &Pubkey,
+        nft_mint: &Pubkey,
+        write_off_account: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        if write_off_account.key != &Self::get_address(program_id, nft_mint) {
+            throw!(liqzError::VaultWriteOffAddressNotCorrect);
+        }
+
+        if !utils::is_program_account(write_off_account, program_id) {
+            throw!(liqzError::VaultNFTNotForSale);
+        }
+
+        ProgramAccount::try_from(write_off_account)?
+    }
+
+    // An program derived account that keeps the value the lending vault booked an NFT at
+    // The address of the account is computed as follow:
+    // address = find_program_address([VaultWriteOff::SEED, nft_mint_address], program_id)
+    pub fn get_address(program_id: &Pubkey, nft_mint: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, nft_mint).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &nft_mint.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, nft_mint: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &nft_mint.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::VaultWriteOffAddressNotCorrect);
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{utils, DerivedAccountIdentifier, liqzError};
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use borsh::BorshDeserialize;
+use fehler::{throw, throws};
+
+// Bytes kept at the end of the pool, deposit and bid accounts so that fields can be added later.
+// Accounts cannot be reallocated by the runtime, a new field has to fit in here.
+pub const RESERVED_SPACE: u64 = 128;
+
+// The layout version of an account, bumped when a field is added
+pub trait Versioned {
+    const VERSION: u8;
+
+    fn version(&self) -> u8;
+
+    // The size of an account holding the largest instance, with the reserved space
+    fn account_size(&self) -> u64;
+}
+
+// The layout of an account created before versioning, without the version and the reserved space
+pub trait Legacy: BorshDeserialize {
+    // The exact size the legacy account was created with
+    fn account_size() -> usize;
+}
+
+// Decodes an account created before versioning with its legacy layout
+#[throws(ProgramError)]
+pub fn decode_legacy<T, L>(program_id: &Pubkey, account: &AccountInfo) -> L
+where
+    T: Discriminator,
+    L: Legacy,
+{
+    if account.owner != program_id {
+        throw!(ProgramError::IncorrectProgramId);
+    }
+
+    decode_legacy_data::<T, L>(&account.try_borrow_data()?)?
+}
+
+#[throws(ProgramError)]
+pub fn decode_legacy_data<T, L>(data: &[u8]) -> L
+where
+    T: Discriminator,
+    L: Legacy,
+{
+    // versioned accounts are larger, they have the reserved space
+    if data.len() != L::account_size() {
+        throw!(liqzError::AccountUpToDate);
+    }
+
+    if data[..8] != T::discriminator() {
+        throw!(ProgramError::InvalidAccountData);
+    }
+
+    L::deserialize(&mut &data[8..]).map_err(|_| ProgramError::InvalidAccountData)?
+}
+
+// Holds a migrated account between closing it and creating it again with the larger size.
+// The runtime cannot grow an account and the address is only free again after the transaction that closed it,
+// so a migration takes two transactions, one to stash the account and one to restore it.
+pub struct MigrationBuffer;
+
+impl DerivedAccountIdentifier for MigrationBuffer {
+    const SEED: &'static [u8] = b"liqzMigrationBuffer";
+}
+
+impl MigrationBuffer {
+    // Writes the upgraded instance into the buffer and closes the account, the payer gets its lamports back
+    #[throws(ProgramError)]
+    pub fn stash<'info, T>(
+        program_id: &Pubkey,
+        instance: &T,
+        account: &AccountInfo<'info>,
+        buffer: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) where
+        T: Versioned + AccountSerialize,
+    {
+        let (_, bump) = Self::get_address_with_bump(program_id, account.key);
+
+        Self::verify_address(program_id, account.key, bump, buffer.key)?;
+
+        let seeds_with_bump: &[&[_]] = &[Self::SEED, &account.key.to_bytes(), &[bump]];
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            payer,
+            seeds_with_bump,
+            buffer,
+            instance.account_size(),
+            rent,
+            system,
+        )?;
+
+        {
+            let mut data = buffer.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+
+            // the buffer must not pass for the account itself
+            data[..8].copy_from_slice(&[0u8; 8]);
+        }
+
+        utils::close_account(account, payer)?;
+    }
+
+    // The address of a stashed account is free until it is restored, nothing else may take it meanwhile
+    #[throws(ProgramError)]
+    pub fn verify_not_stashed(program_id: &Pubkey, account: &Pubkey, buffer: &AccountInfo) {
+        let (_, bump) = Self::get_address_with_bump(program_id, account);
+
+        Self::verify_address(program_id, account, bump, buffer.key)?;
+
+        if utils::is_program_account(buffer, program_id) {
+            throw!(liqzError::MigrationInProgress);
+        }
+    }
+
+    // Reads back the instance stashed for an account
+    #[throws(ProgramError)]
+    pub fn load<T: BorshDeserialize>(
+        program_id: &Pubkey,
+        account: &Pubkey,
+        buffer: &AccountInfo,
+    ) -> T {
+        let (_, bump) = Self::get_address_with_bump(program_id, account);
+
+        Self::verify_address(program_id, account, bump, buffer.key)?;
+
+        if buffer.owner != program_id || buffer.data_is_empty() {
+            throw!(liqzError::MigrationNotStarted);
+        }
+
+        let data = buffer.try_borrow_data()?;
+        T::deserialize(&mut &data[8..]).map_err(|_| ProgramError::InvalidAccountData)?
+    }
+
+    // Creates the account again with the stashed instance and closes the buffer
+    #[throws(ProgramError)]
+    pub fn restore<'info, T: Discriminator>(
+        program_id: &Pubkey,
+        account: &AccountInfo<'info>,
+        seeds_with_bump: &[&[u8]],
+        buffer: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, account.key);
+
+        Self::verify_address(program_id, account.key, bump, buffer.key)?;
+
+        if buffer.owner != program_id || buffer.data_is_empty() {
+            throw!(liqzError::MigrationNotStarted);
+        }
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            payer,
+            seeds_with_bump,
+            account,
+            buffer.data_len() as u64,
+            rent,
+            system,
+        )?;
+
+        {
+            let stashed = buffer.try_borrow_data()?;
+            let mut data = account.try_borrow_mut_data()?;
+            data[..8].copy_from_slice(&T::discriminator());
+            data[8..].copy_from_slice(&stashed[8..]);
+        }
+
+        utils::close_account(buffer, payer)?;
+    }
+
+    // An program derived account that holds an account during its migration
+    // The address of the account is computed as follow:
+    // address = find_program_address([MigrationBuffer::SEED, account_address], program_id)
+    pub fn get_address(program_id: &Pubkey, account: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, account).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, account: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &account.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, account: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &account.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(liqzError::MigrationBufferAddressNotCorrect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils, NFTPool};
+
+    #[test]
+    fn restore_into_an_address_funded_by_someone_else() {
+        test_utils::emulate_system_program();
+        let program_id = Pubkey::new_unique();
+        let (pool_address, bump) = Pubkey::find_program_address(&[NFTPool::SEED], &program_id);
+        let pool_account = test_utils::funded_address(pool_address);
+        let buffer = test_utils::account(
+            MigrationBuffer::get_address(&program_id, &pool_address),
+            1_000_000,
+            program_id,
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3],
+        );
+        let payer = test_utils::wallet();
+
+        // lamports alone do not make the address stashed
+        MigrationBuffer::verify_not_stashed(
+            &program_id,
+            &pool_address,
+            &test_utils::funded_address(*buffer.key),
+        )
+        .unwrap();
+
+        MigrationBuffer::restore::<NFTPool>(
+            &program_id,
+            &pool_account,
+            &[NFTPool::SEED, &[bump]],
+            &buffer,
+            &payer,
+            &test_utils::rent(),
+            &test_utils::system(),
+        )
+        .unwrap();
+
+        let data = pool_account.try_borrow_data().unwrap();
+        assert_eq!(data[..8], NFTPool::discriminator());
+        assert_eq!(data[8..], [1, 2, 3]);
+        assert_eq!(buffer.lamports(), 0);
+    }
+}